@@ -0,0 +1,99 @@
+//! Full renders against the snapshot fixtures, a large generated transcript
+//! and this repository's own git checkout. Run with `cargo bench`; a render
+//! should stay well under 50ms.
+
+use cc_statusline_rs::{render, selftest, Config, Registry};
+use criterion::{criterion_group, criterion_main, Criterion};
+use serde_json::json;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+/// Loads a payload like the snapshot tests do, with `{home}` in `scratch`.
+fn load_payload(path: &Path, scratch: &Path) -> serde_json::Value {
+    let home = scratch.join("home");
+    let data = std::fs::read_to_string(path).expect("read payload");
+    let data = data
+        .replace("{fixtures}", &fixtures_dir().to_string_lossy())
+        .replace("{home}", &home.to_string_lossy());
+    let payload: serde_json::Value = serde_json::from_str(&data).expect("payload is valid JSON");
+    for dir in ["current_dir", "project_dir"] {
+        if let Some(dir) = payload["workspace"][dir].as_str() {
+            std::fs::create_dir_all(dir).expect("create workspace dir");
+        }
+    }
+    payload
+}
+
+/// A transcript of `messages` assistant turns with tool calls, like a long
+/// working session.
+fn write_transcript(path: &Path, messages: usize) {
+    let mut file = std::io::BufWriter::new(std::fs::File::create(path).expect("create transcript"));
+    let start = chrono::Utc::now() - chrono::Duration::hours(8);
+    for i in 0..messages {
+        let timestamp = (start + chrono::Duration::seconds(i as i64)).to_rfc3339();
+        if i % 5 == 0 {
+            let prompt = json!({"type": "user", "uuid": format!("u{}", i), "timestamp": timestamp,
+                "message": {"role": "user", "content": "continue"}});
+            writeln!(file, "{}", prompt).expect("write transcript");
+        }
+        let assistant = json!({"type": "assistant", "timestamp": timestamp, "message": {
+        "id": format!("msg_{}", i),
+        "model": "claude-sonnet-4-5-20250929",
+        "content": [{"type": "tool_use", "id": format!("tool_{}", i), "name": "Edit", "input": {}}],
+        "usage": {
+            "input_tokens": 1200,
+            "output_tokens": 400,
+            "cache_creation_input_tokens": 3000,
+            "cache_read_input_tokens": 90_000,
+        }}});
+        writeln!(file, "{}", assistant).expect("write transcript");
+    }
+}
+
+fn benches(c: &mut Criterion) {
+    let scratch = selftest::isolated_scratch_dir("bench").expect("scratch directory");
+    std::env::set_var("CC_STATUSLINE_CONFIG", scratch.join("missing.toml"));
+    let config = Config::load();
+    let registry = Registry::default();
+
+    let mut payloads: Vec<PathBuf> = std::fs::read_dir(fixtures_dir().join("payloads"))
+        .expect("read fixtures")
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .collect();
+    payloads.sort();
+    let payloads: Vec<serde_json::Value> =
+        payloads.iter().map(|p| load_payload(p, &scratch)).collect();
+    c.bench_function("fixtures", |b| {
+        b.iter(|| {
+            for payload in &payloads {
+                render(payload, &config, &registry);
+            }
+        })
+    });
+
+    let transcript = scratch.join("large.jsonl");
+    write_transcript(&transcript, 20_000);
+    let mut large = load_payload(&fixtures_dir().join("payloads/opus-basic.json"), &scratch);
+    large["transcript_path"] = json!(transcript);
+    c.bench_function("large_transcript", |b| {
+        b.iter(|| render(&large, &config, &registry))
+    });
+
+    let mut repo = large.clone();
+    repo["workspace"] = json!({
+        "current_dir": env!("CARGO_MANIFEST_DIR"),
+        "project_dir": env!("CARGO_MANIFEST_DIR"),
+    });
+    c.bench_function("git_repository", |b| {
+        b.iter(|| render(&repo, &config, &registry))
+    });
+
+    let _ = std::fs::remove_dir_all(&scratch);
+}
+
+criterion_group!(render_benches, benches);
+criterion_main!(render_benches);
@@ -0,0 +1,41 @@
+use clap::{CommandFactory, ValueEnum};
+use std::path::Path;
+
+#[allow(dead_code)]
+#[path = "src/cli.rs"]
+mod cli;
+
+/// Generates the shell completions and the man page into `OUT_DIR`, where
+/// `statusline completions` and `statusline man` embed them from, and
+/// records the build time (`$SOURCE_DATE_EPOCH` for reproducible builds) for
+/// the doctor's price table check.
+fn main() -> std::io::Result<()> {
+    println!("cargo:rerun-if-changed=src/cli.rs");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    let built_at = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.trim().parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0)
+        });
+    println!("cargo:rustc-env=CC_STATUSLINE_BUILT_AT={}", built_at);
+    let out_dir = std::env::var_os("OUT_DIR").expect("OUT_DIR is set by cargo");
+    let out_dir = Path::new(&out_dir);
+
+    let mut command = cli::Cli::command();
+    for shell in cli::Shell::value_variants() {
+        let generator = match shell {
+            cli::Shell::Bash => clap_complete::Shell::Bash,
+            cli::Shell::Zsh => clap_complete::Shell::Zsh,
+            cli::Shell::Fish => clap_complete::Shell::Fish,
+        };
+        clap_complete::generate_to(generator, &mut command, "statusline", out_dir)?;
+    }
+
+    let mut man = Vec::new();
+    clap_mangen::Man::new(command).render(&mut man)?;
+    std::fs::write(out_dir.join("statusline.1"), man)
+}
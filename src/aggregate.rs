@@ -0,0 +1,192 @@
+use crate::transcript;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+const CACHE_NAME: &str = "aggregate-today.json";
+/// Lock held while a background scan runs.
+const LOCK_NAME: &str = "aggregate-today";
+/// A scan that hasn't finished after this long is assumed dead, and the next
+/// render may start another one.
+const LOCK_STALE_AFTER: Duration = Duration::from_secs(600);
+
+/// `[aggregate]` config section: machine-wide usage across every transcript
+/// directory this user can read, for teams sharing a dev server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AggregateConfig {
+    /// Expose `shared_cost_today` to alert rules.
+    pub enabled: bool,
+    /// Transcript globs; `~` is expanded. Unreadable directories are skipped.
+    /// Every user's transcripts by default: `/home/*/.claude/...` on Linux,
+    /// `/Users/*/.claude/...` on macOS.
+    pub transcript_globs: Vec<String>,
+    /// How long the machine-wide total is reused before a background rescan.
+    pub refresh_secs: i64,
+}
+
+impl Default for AggregateConfig {
+    fn default() -> Self {
+        AggregateConfig {
+            enabled: false,
+            transcript_globs: vec![all_users_transcripts()],
+            refresh_secs: 60,
+        }
+    }
+}
+
+/// Transcripts of every user under the directory holding this user's home.
+fn all_users_transcripts() -> String {
+    let home = PathBuf::from(crate::home_dir());
+    let homes = home.parent().unwrap_or(&home);
+    homes
+        .join("*")
+        .join(".claude")
+        .join("projects")
+        .join("*")
+        .join("*.jsonl")
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Cost and number of transcripts with activity on one local day.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DayTotal {
+    pub transcripts: usize,
+    pub cost_usd: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedTotal {
+    date: String,
+    cost_usd: f64,
+    computed_at: i64,
+}
+
+/// Every transcript file matched by `globs`, deduplicated.
+pub fn transcript_files(globs: &[String]) -> Vec<String> {
+    let mut files = BTreeSet::new();
+    for pattern in globs {
+        let pattern = crate::expand_home(pattern);
+        let Ok(paths) = glob::glob(&pattern.to_string_lossy()) else {
+            continue;
+        };
+        for path in paths.flatten() {
+            files.insert(path.to_string_lossy().into_owned());
+        }
+    }
+    files.into_iter().collect()
+}
+
+/// `YYYY-MM-DD` of a Unix-millisecond timestamp in local time.
+pub fn local_date(timestamp_ms: i64) -> Option<String> {
+    chrono::DateTime::from_timestamp_millis(timestamp_ms).map(|t| {
+        t.with_timezone(&chrono::Local)
+            .format("%Y-%m-%d")
+            .to_string()
+    })
+}
+
+/// Combined cost per local day (`YYYY-MM-DD`) over all matched transcripts.
+pub fn daily_totals(
+    globs: &[String],
+    aliases: &HashMap<String, String>,
+) -> BTreeMap<String, DayTotal> {
+    let mut days: BTreeMap<String, DayTotal> = BTreeMap::new();
+
+    for file in transcript_files(globs) {
+        let mut touched = BTreeSet::new();
+        for entry in transcript::read_usage_entries(&file) {
+            let Some(date) = entry.timestamp_ms.and_then(local_date) else {
+                continue;
+            };
+            days.entry(date.clone()).or_default().cost_usd +=
+                transcript::entry_cost(&entry, None, aliases);
+            touched.insert(date);
+        }
+        for date in touched {
+            days.entry(date).or_default().transcripts += 1;
+        }
+    }
+
+    days
+}
+
+/// Today's machine-wide cost as of the last scan. Once that is
+/// `refresh_secs` old, a rescan starts in the background and the old total is
+/// served until it finishes. `None` until the day's first scan is done.
+pub fn shared_cost_today(
+    config: &AggregateConfig,
+    aliases: &HashMap<String, String>,
+) -> Option<f64> {
+    let now = chrono::Local::now();
+    let today = now.format("%Y-%m-%d").to_string();
+    let cached = crate::cache::read_cache(CACHE_NAME)
+        .and_then(|data| serde_json::from_str::<CachedTotal>(&data).ok())
+        .filter(|cached| cached.date == today);
+    if cached
+        .as_ref()
+        .is_none_or(|cached| now.timestamp() - cached.computed_at >= config.refresh_secs)
+    {
+        refresh_in_background(config, aliases);
+    }
+    cached.map(|cached| cached.cost_usd)
+}
+
+/// Starts a rescan unless one is already running: a `statusline
+/// aggregate-refresh` process, or a thread when embedded.
+fn refresh_in_background(config: &AggregateConfig, aliases: &HashMap<String, String>) {
+    if !crate::cache::try_lock(LOCK_NAME, LOCK_STALE_AFTER) {
+        return;
+    }
+    match crate::process::statusline_exe() {
+        Some(exe) => {
+            let spawned = Command::new(exe)
+                .arg("aggregate-refresh")
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn();
+            if spawned.is_err() {
+                crate::cache::unlock(LOCK_NAME);
+            }
+        }
+        None => {
+            let (config, aliases) = (config.clone(), aliases.clone());
+            std::thread::spawn(move || {
+                refresh(&config, &aliases);
+                crate::cache::unlock(LOCK_NAME);
+            });
+        }
+    }
+}
+
+/// Scans every matched transcript and caches today's machine-wide cost.
+pub fn refresh(config: &AggregateConfig, aliases: &HashMap<String, String>) -> f64 {
+    let now = chrono::Local::now();
+    let today = now.format("%Y-%m-%d").to_string();
+    let cost_usd = daily_totals(&config.transcript_globs, aliases)
+        .get(&today)
+        .map(|d| d.cost_usd)
+        .unwrap_or(0.0);
+    let cached = CachedTotal {
+        date: today,
+        cost_usd,
+        computed_at: now.timestamp(),
+    };
+    if let Ok(data) = serde_json::to_string(&cached) {
+        let _ = crate::cache::write_cache(CACHE_NAME, &data);
+    }
+    cost_usd
+}
+
+/// `statusline aggregate-refresh`: the background worker started by
+/// [`shared_cost_today`], scanning with the globs of the config file.
+pub fn run_cli() -> i32 {
+    let config = crate::Config::load();
+    refresh(&config.aggregate, &config.models.aliases);
+    crate::cache::unlock(LOCK_NAME);
+    0
+}
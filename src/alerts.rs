@@ -0,0 +1,277 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// `[alerts]` config section: a list of `[[alerts.rules]]` plus the built-in
+/// `[alerts.notifications]`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertsConfig {
+    pub rules: Vec<AlertRule>,
+    pub notifications: NotificationsConfig,
+}
+
+/// Opt-in desktop notifications for the common thresholds, without writing
+/// rules by hand. They go through the same edge-triggered evaluation as
+/// `notification` rules and fall back to the terminal bell when no notifier
+/// is available.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    pub enabled: bool,
+    /// Notify when context usage reaches this percentage.
+    pub context_pct: f64,
+    /// Notify when a `[budget]` limit is exceeded.
+    pub budget: bool,
+    /// Notify when the build/CI status file flips to failing.
+    pub build: bool,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        NotificationsConfig {
+            enabled: false,
+            context_pct: 90.0,
+            budget: true,
+            build: true,
+        }
+    }
+}
+
+impl NotificationsConfig {
+    /// The enabled notifications as rules.
+    pub fn rules(&self) -> Vec<AlertRule> {
+        if !self.enabled {
+            return Vec::new();
+        }
+        let rule = |name: &str, when: String, message: &str| AlertRule {
+            name: name.to_string(),
+            when,
+            channel: AlertChannel::Notification,
+            message: Some(message.to_string()),
+            url: None,
+            command: None,
+        };
+
+        let mut rules = vec![rule(
+            "Context almost full",
+            format!("context_pct >= {}", self.context_pct),
+            "Context is {context_pct}% full",
+        )];
+        if self.budget {
+            rules.push(rule(
+                "Over budget",
+                "over_budget == 1".to_string(),
+                "Session cost is ${cost_usd}",
+            ));
+        }
+        if self.build {
+            rules.push(rule(
+                "Build failing",
+                "build_failing == 1".to_string(),
+                "The build status changed to failing",
+            ));
+        }
+        rules
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub name: String,
+    /// Condition such as `context_pct >= 90` or `cost_usd > 5 && lines_added > 500`.
+    pub when: String,
+    #[serde(default)]
+    pub channel: AlertChannel,
+    /// Text shown or sent; `{metric}` placeholders are filled in.
+    #[serde(default)]
+    pub message: Option<String>,
+    /// Target for the `webhook` channel.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Shell command for the `command` channel. Receives `CC_ALERT_NAME` and
+    /// `CC_ALERT_MESSAGE` in its environment.
+    #[serde(default)]
+    pub command: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertChannel {
+    /// A `⚠ message` badge appended to the statusline while the condition holds.
+    #[default]
+    Badge,
+    /// Terminal bell when the condition starts holding.
+    Bell,
+    /// Desktop notification when the condition starts holding.
+    Notification,
+    /// JSON POST to `url` when the condition starts holding.
+    Webhook,
+    /// Runs `command` when the condition starts holding.
+    Command,
+}
+
+/// Numbers a rule condition can refer to, keyed by name (`context_pct`,
+/// `cost_usd`, ...).
+pub type AlertMetrics = HashMap<&'static str, f64>;
+
+/// What the renderer has to add to the line after evaluation.
+#[derive(Debug, Clone, Default)]
+pub struct AlertOutcome {
+    pub badges: Vec<String>,
+    pub bell: bool,
+}
+
+/// Evaluates every rule. Badges reflect the current state; the other channels
+/// are edge-triggered, firing once when a rule starts matching in a session
+/// (state kept in the cache dir).
+pub fn evaluate(config: &AlertsConfig, metrics: &AlertMetrics, session_id: &str) -> AlertOutcome {
+    let mut outcome = AlertOutcome::default();
+    let builtin = config.notifications.rules();
+    if config.rules.is_empty() && builtin.is_empty() {
+        return outcome;
+    }
+
+    let state_name = format!("alerts-{}", crate::cache::cache_key(session_id));
+    let previously_firing: BTreeSet<String> = crate::cache::read_cache(&state_name)
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default();
+    let mut firing = BTreeSet::new();
+
+    for rule in config.rules.iter().chain(&builtin) {
+        if !matches(&rule.when, metrics) {
+            continue;
+        }
+        firing.insert(rule.name.clone());
+
+        let message = render_message(rule, metrics);
+        let newly_firing = !previously_firing.contains(&rule.name);
+
+        match rule.channel {
+            AlertChannel::Badge => {
+                outcome
+                    .badges
+                    .push(format!("\x1b[1;31m\u{26a0} {}\x1b[0m", message));
+            }
+            AlertChannel::Bell if newly_firing => outcome.bell = true,
+            // Without a notifier, ring the bell instead.
+            AlertChannel::Notification if newly_firing && !notify(&rule.name, &message) => {
+                outcome.bell = true
+            }
+            AlertChannel::Webhook if newly_firing => {
+                if let Some(url) = &rule.url {
+                    post_webhook(url, &rule.name, &message, metrics);
+                }
+            }
+            AlertChannel::Command if newly_firing => {
+                if let Some(command) = &rule.command {
+                    run_command(command, &rule.name, &message);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if firing != previously_firing {
+        if let Ok(json) = serde_json::to_string(&firing) {
+            let _ = crate::cache::write_cache(&state_name, &json);
+        }
+    }
+
+    outcome
+}
+
+/// Evaluates a condition: comparisons (`>`, `>=`, `<`, `<=`, `==`, `!=`)
+/// between a metric and a number, combined with `&&` and `||` (`&&` binds
+/// tighter). Unknown metrics make their comparison false.
+pub fn matches(expr: &str, metrics: &AlertMetrics) -> bool {
+    expr.split("||").any(|clause| {
+        clause
+            .split("&&")
+            .all(|cmp| compare(cmp.trim(), metrics).unwrap_or(false))
+    })
+}
+
+fn compare(expr: &str, metrics: &AlertMetrics) -> Option<bool> {
+    const OPS: [&str; 6] = [">=", "<=", "==", "!=", ">", "<"];
+    let (op, pos) = OPS
+        .iter()
+        .filter_map(|op| expr.find(op).map(|pos| (*op, pos)))
+        .min_by_key(|(op, pos)| (*pos, std::cmp::Reverse(op.len())))?;
+
+    let lhs = expr[..pos].trim();
+    let rhs = expr[pos + op.len()..].trim();
+    let value = |s: &str| s.parse::<f64>().ok().or_else(|| metrics.get(s).copied());
+    let (lhs, rhs) = (value(lhs)?, value(rhs)?);
+
+    Some(match op {
+        ">=" => lhs >= rhs,
+        "<=" => lhs <= rhs,
+        "==" => (lhs - rhs).abs() < f64::EPSILON,
+        "!=" => (lhs - rhs).abs() >= f64::EPSILON,
+        ">" => lhs > rhs,
+        _ => lhs < rhs,
+    })
+}
+
+fn render_message(rule: &AlertRule, metrics: &AlertMetrics) -> String {
+    let mut message = rule.message.clone().unwrap_or_else(|| rule.name.clone());
+    for (name, value) in metrics {
+        let placeholder = format!("{{{}}}", name);
+        if message.contains(&placeholder) {
+            let formatted = if value.fract() == 0.0 {
+                format!("{}", value)
+            } else {
+                format!("{:.2}", value)
+            };
+            message = message.replace(&placeholder, &formatted);
+        }
+    }
+    message
+}
+
+/// Fires a desktop notification via `osascript` on macOS or `notify-send`
+/// elsewhere, without waiting for it. Returns `false` when neither could be
+/// started.
+pub fn notify(title: &str, message: &str) -> bool {
+    let spawned = if cfg!(target_os = "macos") {
+        Command::new("osascript")
+            .arg("-e")
+            .arg("on run argv\ndisplay notification (item 2 of argv) with title (item 1 of argv)\nend run")
+            .arg(title)
+            .arg(message)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+    } else {
+        Command::new("notify-send")
+            .arg(title)
+            .arg(message)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+    };
+    spawned.is_ok()
+}
+
+fn post_webhook(url: &str, name: &str, message: &str, metrics: &AlertMetrics) {
+    let body = serde_json::json!({
+        "alert": name,
+        "message": message,
+        "metrics": metrics,
+    });
+    crate::post::in_background(url, &body, Duration::from_secs(2));
+}
+
+fn run_command(command: &str, name: &str, message: &str) {
+    let _ = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("CC_ALERT_NAME", name)
+        .env("CC_ALERT_MESSAGE", message)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+}
@@ -0,0 +1,173 @@
+use crate::transcript::Turn;
+use serde::{Deserialize, Serialize};
+
+/// `[anomaly]` config section.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnomalyConfig {
+    pub enabled: bool,
+    /// A turn is unusual when it exceeds this multiple of the rolling median.
+    pub factor: f64,
+    /// Number of preceding turns the median is taken over, up to the
+    /// transcript's last [`crate::transcript::MAX_TURNS`].
+    pub window: usize,
+    /// Turns of history required before anything is flagged.
+    pub min_turns: usize,
+    /// Which built-in detectors run: `cost`, `duration`. Detectors
+    /// registered by an embedding application always run.
+    pub detectors: Vec<String>,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        AnomalyConfig {
+            enabled: false,
+            factor: 3.0,
+            window: 20,
+            min_turns: 5,
+            detectors: vec!["cost".to_string(), "duration".to_string()],
+        }
+    }
+}
+
+/// Scores a turn; the rolling-median comparison is shared by all detectors.
+/// Embedding applications add their own through
+/// [`crate::Registry::register_detector`].
+pub trait AnomalyDetector: Send + Sync {
+    fn name(&self) -> &str;
+    /// The value compared against the median of previous turns, or `None`
+    /// when the turn has nothing to measure.
+    fn measure(&self, turn: &Turn) -> Option<f64>;
+}
+
+/// Cost per turn.
+pub struct CostPerTurn;
+
+impl AnomalyDetector for CostPerTurn {
+    fn name(&self) -> &str {
+        "cost"
+    }
+
+    fn measure(&self, turn: &Turn) -> Option<f64> {
+        Some(turn.cost_usd)
+    }
+}
+
+/// Wall-clock duration per turn.
+pub struct DurationPerTurn;
+
+impl AnomalyDetector for DurationPerTurn {
+    fn name(&self) -> &str {
+        "duration"
+    }
+
+    fn measure(&self, turn: &Turn) -> Option<f64> {
+        turn.duration_ms().map(|ms| ms as f64)
+    }
+}
+
+/// The built-in detectors named in `config.detectors`, followed by the
+/// ones in `registry`. A registered detector with a built-in's name
+/// replaces it.
+pub fn detectors_for<'a>(
+    config: &AnomalyConfig,
+    registry: &'a crate::Registry,
+) -> Vec<&'a dyn AnomalyDetector> {
+    let registered = registry.detectors();
+    let replaced = |name: &str| registered.iter().any(|d| d.name() == name);
+    let built_in: Vec<&'a dyn AnomalyDetector> = config
+        .detectors
+        .iter()
+        .filter(|name| !replaced(name))
+        .filter_map(|name| -> Option<&'a dyn AnomalyDetector> {
+            match name.as_str() {
+                "cost" => Some(&CostPerTurn),
+                "duration" => Some(&DurationPerTurn),
+                _ => None,
+            }
+        })
+        .collect();
+    built_in.into_iter().chain(registered).collect()
+}
+
+fn median(values: &mut [f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    })
+}
+
+/// A turn that one detector flagged.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Anomaly {
+    pub detector: String,
+    pub turn_id: String,
+    pub value: f64,
+    pub median: f64,
+}
+
+/// Checks the latest turn against the median of the `config.window` turns
+/// before it, once per detector.
+pub fn check_latest(
+    turns: &[Turn],
+    detectors: &[&dyn AnomalyDetector],
+    config: &AnomalyConfig,
+) -> Vec<Anomaly> {
+    let Some((latest, history)) = turns.split_last() else {
+        return Vec::new();
+    };
+    let history = &history[history.len().saturating_sub(config.window)..];
+
+    detectors
+        .iter()
+        .filter_map(|detector| {
+            let value = detector.measure(latest)?;
+            let mut previous: Vec<f64> =
+                history.iter().filter_map(|t| detector.measure(t)).collect();
+            if previous.len() < config.min_turns {
+                return None;
+            }
+            let median = median(&mut previous)?;
+            (median > 0.0 && value > config.factor * median).then(|| Anomaly {
+                detector: detector.name().to_string(),
+                turn_id: latest.id.clone(),
+                value,
+                median,
+            })
+        })
+        .collect()
+}
+
+/// Writes each anomaly to the debug log once per turn (state in the cache
+/// dir, keyed by session).
+pub fn log_anomalies(session_id: &str, anomalies: &[Anomaly]) {
+    let Some(first) = anomalies.first() else {
+        return;
+    };
+    let state_name = format!("anomaly-{}", crate::cache::cache_key(session_id));
+    if crate::cache::read_cache(&state_name).as_deref() == Some(first.turn_id.as_str()) {
+        return;
+    }
+    for anomaly in anomalies {
+        crate::debug::log(&format!(
+            "anomaly session={} turn={} detector={} value={:.2} median={:.2}",
+            session_id, anomaly.turn_id, anomaly.detector, anomaly.value, anomaly.median
+        ));
+    }
+    let _ = crate::cache::write_cache(&state_name, &first.turn_id);
+}
+
+/// `⚡unusual`, shown while the latest turn is flagged.
+pub fn format_anomaly_badge(anomalies: &[Anomaly]) -> String {
+    if anomalies.is_empty() {
+        String::new()
+    } else {
+        "\x1b[1;33m\u{26a1}unusual\x1b[0m".to_string()
+    }
+}
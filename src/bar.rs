@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+
+/// How the context bar is drawn.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BarStyle {
+    /// Whole cells of `fill` and `empty`.
+    #[default]
+    Blocks,
+    /// Eighth-cell block glyphs for a smooth edge.
+    Smooth,
+    /// Braille dots, eight steps per cell.
+    Braille,
+    /// A single vertical gauge glyph; `width` is ignored.
+    Battery,
+}
+
+/// `[bar]` config section: the context window bar.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BarConfig {
+    pub style: BarStyle,
+    /// Width in cells.
+    pub width: usize,
+    /// Filled and empty cells for the `blocks` style.
+    pub fill: String,
+    pub empty: String,
+    /// Color filled cells from green to red along the bar instead of gray.
+    pub gradient: bool,
+}
+
+impl Default for BarConfig {
+    fn default() -> Self {
+        BarConfig {
+            style: BarStyle::Blocks,
+            width: 15,
+            fill: "█".to_string(),
+            empty: "░".to_string(),
+            gradient: false,
+        }
+    }
+}
+
+const SMOOTH: [&str; 8] = ["▏", "▎", "▍", "▌", "▋", "▊", "▉", "█"];
+const BRAILLE: [&str; 8] = ["⡀", "⡄", "⡆", "⡇", "⣇", "⣧", "⣷", "⣿"];
+const GAUGE: [&str; 8] = ["▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"];
+
+/// Green at the start of the bar, yellow in the middle, red at the end.
+fn gradient_color(position: f64) -> String {
+    let position = position.clamp(0.0, 1.0);
+    let (r, g) = if position < 0.5 {
+        ((position * 2.0 * 255.0).round() as u8, 200)
+    } else {
+        (255, ((1.0 - position) * 2.0 * 200.0).round() as u8)
+    };
+    format!("\x1b[38;2;{};{};0m", r, g)
+}
+
+/// The cells of the bar for `pct` percent, each paired with its position
+/// along the bar (0.0 to 1.0) so gradients can color it. Empty cells have no
+/// position.
+fn cells(config: &BarConfig, pct: f64) -> Vec<(String, Option<f64>)> {
+    let fraction = (pct / 100.0).clamp(0.0, 1.0);
+    let width = config.width.max(1);
+
+    let partial = |glyphs: &[&str; 8], empty: &str| {
+        let eighths = (fraction * width as f64 * 8.0).round() as usize;
+        (0..width)
+            .map(|i| {
+                let position = Some((i as f64 + 0.5) / width as f64);
+                match eighths.saturating_sub(i * 8).min(8) {
+                    0 => (empty.to_string(), None),
+                    n => (glyphs[n - 1].to_string(), position),
+                }
+            })
+            .collect()
+    };
+
+    match config.style {
+        BarStyle::Blocks => {
+            let filled = (fraction * width as f64).round() as usize;
+            (0..width)
+                .map(|i| {
+                    if i < filled {
+                        (config.fill.clone(), Some((i as f64 + 0.5) / width as f64))
+                    } else {
+                        (config.empty.clone(), None)
+                    }
+                })
+                .collect()
+        }
+        BarStyle::Smooth => partial(&SMOOTH, " "),
+        BarStyle::Braille => partial(&BRAILLE, "⠀"),
+        BarStyle::Battery => {
+            let level = ((fraction * 8.0).ceil() as usize).clamp(1, 8);
+            vec![(GAUGE[level - 1].to_string(), Some(fraction))]
+        }
+    }
+}
+
+/// The bar in dim gray, or in gradient colors with `gradient`.
+pub fn render_bar(config: &BarConfig, pct: f64) -> String {
+    let cells = cells(config, pct);
+    if !config.gradient {
+        let bar: String = cells.into_iter().map(|(glyph, _)| glyph).collect();
+        return format!("\x1b[90m{}\x1b[0m", bar);
+    }
+    let mut out = String::new();
+    for (glyph, position) in cells {
+        match position {
+            Some(position) => out.push_str(&gradient_color(position)),
+            None => out.push_str("\x1b[90m"),
+        }
+        out.push_str(&glyph);
+    }
+    out.push_str("\x1b[0m");
+    out
+}
@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+/// `[budget]` config section: spending limits that turn the cost segment
+/// bold red once exceeded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BudgetConfig {
+    /// Limit for a single session, in dollars.
+    pub session: Option<f64>,
+    /// Limit for all sessions active today (local time), in dollars.
+    pub daily: Option<f64>,
+    /// Prepend `⚠` to the cost segment while over budget.
+    pub warn_icon: bool,
+    /// Appended to the line while over budget; `{limit}` is replaced with the
+    /// exceeded limit. Empty to disable.
+    pub hard_warn: String,
+}
+
+impl Default for BudgetConfig {
+    fn default() -> Self {
+        BudgetConfig {
+            session: None,
+            daily: None,
+            warn_icon: true,
+            hard_warn: "\u{26a0} ${limit} cap".to_string(),
+        }
+    }
+}
+
+/// The first limit a render exceeds, session before daily.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BudgetExceeded {
+    Session(f64),
+    Daily(f64),
+}
+
+impl BudgetExceeded {
+    pub fn limit(self) -> f64 {
+        match self {
+            BudgetExceeded::Session(limit) | BudgetExceeded::Daily(limit) => limit,
+        }
+    }
+}
+
+/// Today's spend across recorded sessions, with `session_id` counted at
+/// `session_cost` (the snapshot on disk lags one render behind).
+pub fn daily_cost(session_id: Option<&str>, session_cost: f64) -> f64 {
+    let today = chrono::Local::now().date_naive();
+    let others: f64 = crate::index::load_sessions()
+        .iter()
+        .filter(|s| Some(s.session_id.as_str()) != session_id)
+        .filter(|s| {
+            chrono::DateTime::from_timestamp(s.updated_at, 0)
+                .is_some_and(|t| t.with_timezone(&chrono::Local).date_naive() == today)
+        })
+        .filter_map(|s| s.cost_usd)
+        .sum();
+    others + session_cost
+}
+
+pub fn check(
+    config: &BudgetConfig,
+    session_cost: f64,
+    daily_cost: Option<f64>,
+) -> Option<BudgetExceeded> {
+    if let Some(limit) = config.session.filter(|&limit| session_cost > limit) {
+        return Some(BudgetExceeded::Session(limit));
+    }
+    match (config.daily, daily_cost) {
+        (Some(limit), Some(cost)) if cost > limit => Some(BudgetExceeded::Daily(limit)),
+        _ => None,
+    }
+}
+
+/// The `hard_warn` message for `exceeded`, in bold red.
+pub fn format_hard_warn(config: &BudgetConfig, exceeded: BudgetExceeded) -> String {
+    if config.hard_warn.is_empty() {
+        return String::new();
+    }
+    let limit = exceeded.limit();
+    let limit = if limit.fract() == 0.0 {
+        format!("{}", limit)
+    } else {
+        crate::format_cost(limit)
+    };
+    format!(
+        "\x1b[1;31m{}\x1b[0m",
+        config.hard_warn.replace("{limit}", &limit)
+    )
+}
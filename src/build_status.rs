@@ -0,0 +1,46 @@
+use std::fs;
+use std::path::Path;
+
+/// State of the last build recorded by a watch process or wrapper script.
+///
+/// Wrappers write `<git-dir>/statusbar/build.json`, either as
+/// `{"status": "pass" | "fail" | "running"}` or as `{"exit_code": N}` (the
+/// shape produced by `cargo check; echo "{\"exit_code\": $?}" > ...`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildStatus {
+    Pass,
+    Fail,
+    Running,
+}
+
+pub fn read_build_status(state_file: &Path) -> Option<BuildStatus> {
+    let data = fs::read_to_string(state_file).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&data).ok()?;
+
+    if let Some(status) = json.get("status").and_then(|s| s.as_str()) {
+        return match status.to_ascii_lowercase().as_str() {
+            "pass" | "ok" | "success" => Some(BuildStatus::Pass),
+            "fail" | "failed" | "error" => Some(BuildStatus::Fail),
+            "running" | "pending" | "building" => Some(BuildStatus::Running),
+            _ => None,
+        };
+    }
+
+    json.get("exit_code")
+        .and_then(crate::as_f64_lenient)
+        .map(|code| {
+            if code == 0.0 {
+                BuildStatus::Pass
+            } else {
+                BuildStatus::Fail
+            }
+        })
+}
+
+pub fn format_build_status(status: BuildStatus) -> String {
+    match status {
+        BuildStatus::Pass => "\x1b[32m●\x1b[0m".to_string(),
+        BuildStatus::Fail => "\x1b[31m●\x1b[0m".to_string(),
+        BuildStatus::Running => "\x1b[33m◌\x1b[0m".to_string(),
+    }
+}
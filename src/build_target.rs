@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// `[build_target]` config section: the Bazel or Buck package of the current
+/// directory (`//services/auth`), for monorepos where targets are easier to
+/// place than filesystem paths.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BuildTargetConfig {
+    pub enabled: bool,
+    /// Prefix the package with the build tool (`bazel //services/auth`).
+    pub show_tool: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildTool {
+    Bazel,
+    Buck,
+}
+
+impl BuildTool {
+    pub fn name(self) -> &'static str {
+        match self {
+            BuildTool::Bazel => "bazel",
+            BuildTool::Buck => "buck",
+        }
+    }
+}
+
+/// Files marking the root of a Bazel workspace.
+const BAZEL_ROOTS: &[&str] = &["MODULE.bazel", "REPO.bazel", "WORKSPACE.bazel", "WORKSPACE"];
+const BAZEL_BUILD_FILES: &[&str] = &["BUILD.bazel", "BUILD"];
+const BUCK_BUILD_FILES: &[&str] = &["BUCK", "TARGETS"];
+
+/// The package containing a directory of a Bazel or Buck workspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Package {
+    pub tool: BuildTool,
+    pub root: PathBuf,
+    /// Path of the package relative to the workspace root, `""` for the
+    /// root package.
+    pub path: String,
+}
+
+impl Package {
+    /// The package as a label, `//services/auth`.
+    pub fn label(&self) -> String {
+        format!("//{}", self.path)
+    }
+}
+
+/// The workspace or cell containing `dir`: the nearest directory with a
+/// Bazel root file or a `.buckconfig`.
+pub fn workspace(dir: &Path) -> Option<(BuildTool, PathBuf)> {
+    dir.ancestors().find_map(|root| {
+        if BAZEL_ROOTS.iter().any(|name| root.join(name).is_file()) {
+            Some((BuildTool::Bazel, root.to_path_buf()))
+        } else if root.join(".buckconfig").is_file() {
+            Some((BuildTool::Buck, root.to_path_buf()))
+        } else {
+            None
+        }
+    })
+}
+
+/// Build file names set in the `[buildfile]` section of a `.buckconfig`
+/// (`name`, or Buck2's `name_v2`); `BUCK` and `TARGETS` otherwise.
+pub fn buck_build_files(buckconfig: &str) -> Vec<String> {
+    let mut section = "";
+    let mut names = Vec::new();
+    for line in buckconfig.lines().map(str::trim) {
+        if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = header.trim();
+        } else if section == "buildfile" {
+            if let Some((key, value)) = line.split_once('=') {
+                if matches!(key.trim(), "name" | "name_v2") {
+                    names.extend(value.split(',').map(|n| n.trim().to_string()));
+                }
+            }
+        }
+    }
+    names.retain(|n| !n.is_empty());
+    if names.is_empty() {
+        BUCK_BUILD_FILES.iter().map(|n| n.to_string()).collect()
+    } else {
+        names
+    }
+}
+
+/// The package of `dir`: its nearest directory, up to the workspace root,
+/// holding a build file. `None` outside a workspace or outside any package.
+pub fn package(dir: &Path) -> Option<Package> {
+    let (tool, root) = workspace(dir)?;
+    let build_files = match tool {
+        BuildTool::Bazel => BAZEL_BUILD_FILES.iter().map(|n| n.to_string()).collect(),
+        BuildTool::Buck => {
+            buck_build_files(&std::fs::read_to_string(root.join(".buckconfig")).unwrap_or_default())
+        }
+    };
+    let package_dir = dir
+        .ancestors()
+        .take_while(|d| d.starts_with(&root))
+        .find(|d| build_files.iter().any(|name| d.join(name).is_file()))?;
+    let path = package_dir
+        .strip_prefix(&root)
+        .ok()?
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    Some(Package { tool, root, path })
+}
+
+/// `//services/auth`, or `bazel //services/auth` with `show_tool`.
+pub fn format_package(config: &BuildTargetConfig, package: &Package) -> String {
+    if config.show_tool {
+        format!(
+            "\x1b[90m{} \x1b[38;5;73m{}\x1b[0m",
+            package.tool.name(),
+            package.label()
+        )
+    } else {
+        format!("\x1b[38;5;73m{}\x1b[0m", package.label())
+    }
+}
@@ -0,0 +1,91 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// `$XDG_CACHE_HOME/cc-statusline`, else the platform cache directory
+/// (`~/.cache` on Linux, `~/Library/Caches` on macOS, `%LOCALAPPDATA%` on
+/// Windows).
+pub fn cache_dir() -> PathBuf {
+    crate::base_dir("XDG_CACHE_HOME", dirs::cache_dir(), ".cache").join("cc-statusline")
+}
+
+/// Turns an arbitrary key (session ID, branch name) into a safe file name.
+pub fn cache_key(key: &str) -> String {
+    key.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+pub fn read_cache(name: &str) -> Option<String> {
+    fs::read_to_string(cache_dir().join(name)).ok()
+}
+
+/// Writes `contents` to the cache file `name` atomically (temp file + rename),
+/// so a concurrent render never observes a half-written file.
+pub fn write_cache(name: &str, contents: &str) -> std::io::Result<()> {
+    write_atomic(&cache_dir().join(name), contents)
+}
+
+/// Writes `contents` to `path` through a temp file in the same directory and
+/// a rename, creating parent directories as needed.
+pub fn write_atomic(path: &Path, contents: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let tmp = path.with_extension(format!("tmp.{}", std::process::id()));
+    {
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(contents.as_bytes())?;
+    }
+    fs::rename(&tmp, path)
+}
+
+/// Claims the cache entry `name` for a background worker. Creating the lock
+/// file is atomic, so of several concurrent renders exactly one wins. A lock
+/// older than `stale_after` belongs to a worker that died and is taken over.
+///
+/// Taking over removes the old lock before creating a new one, so it is
+/// done holding an exclusive lock on a guard file next to it. Otherwise
+/// two renders that both found the old lock stale could each remove the
+/// lock the other had just created.
+pub fn try_lock(name: &str, stale_after: Duration) -> bool {
+    let path = cache_dir().join(format!("{}.lock", name));
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let guard = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path.with_extension("lock.guard"));
+    if let Ok(guard) = &guard {
+        let _ = guard.lock();
+    }
+
+    let stale = fs::metadata(&path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age > stale_after);
+    if stale {
+        let _ = fs::remove_file(&path);
+    }
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .is_ok()
+}
+
+/// Releases a lock taken with [`try_lock`].
+pub fn unlock(name: &str) {
+    let _ = fs::remove_file(cache_dir().join(format!("{}.lock", name)));
+}
@@ -0,0 +1,235 @@
+//! The command line. `build.rs` includes this file to generate the shell
+//! completions and man page, so it only depends on clap.
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+/// Statusline for Claude Code: reads the session JSON Claude Code pipes to
+/// the `statusLine` command on stdin and prints one line.
+#[derive(Debug, Parser)]
+#[command(name = "statusline", version, about, long_about = None)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Read the JSON from a file or FIFO instead of stdin (`-` for stdin).
+    #[arg(long, value_name = "PATH")]
+    pub input: Option<std::path::PathBuf>,
+    /// Log input errors and failing segments to the state directory's log,
+    /// or to stderr with `--debug=stderr`.
+    #[arg(long, value_name = "SINK", num_args = 0..=1, require_equals = true, default_missing_value = "file")]
+    pub debug: Option<Sink>,
+    /// Log per-segment render timings, like `--debug`.
+    #[arg(long, value_name = "SINK", num_args = 0..=1, require_equals = true, default_missing_value = "file")]
+    pub profile: Option<Sink>,
+    /// Don't show the branch's pull request (`[repo] pr`).
+    #[arg(long)]
+    pub skip_pr_status: bool,
+    /// Collapse the `•` separators to single spaces (`layout.compact`).
+    #[arg(long)]
+    pub compact: bool,
+    /// Show token counts instead of the context bar (`layout.verbose_context`).
+    #[arg(long)]
+    pub verbose_context: bool,
+    /// Hide the path inside standard project directories (`path.short`).
+    #[arg(long)]
+    pub short: bool,
+    /// Print the line as ANSI escapes, an HTML snippet or an SVG image, or
+    /// print its metrics in Prometheus text format instead.
+    #[arg(long, value_enum, value_name = "FORMAT", default_value_t = Output::Ansi)]
+    pub output: Output,
+}
+
+/// What `--output` prints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Output {
+    Ansi,
+    Html,
+    Svg,
+    Prometheus,
+}
+
+/// Where `--debug` and `--profile` write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Sink {
+    File,
+    Stderr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Start, stop or show the focus timer.
+    Timer {
+        #[command(subcommand)]
+        action: TimerAction,
+    },
+    /// Read and edit the config file.
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Check git, gh, the config file and Claude Code's settings.
+    Doctor,
+    /// Record a Claude Code hook event from stdin (for the `last_tool` segment).
+    Hook,
+    /// Render random inputs and check for panics, slow renders and broken
+    /// ANSI.
+    Selftest(SelftestArgs),
+    /// Point Claude Code's `statusLine` at this binary.
+    Install(InstallArgs),
+    /// Render sample sessions with the current config.
+    Preview(PreviewArgs),
+    /// Background worker that titles a session with `claude -p`.
+    #[command(hide = true)]
+    Summarize(SummarizeArgs),
+    /// Background worker that POSTs an alert webhook or OTLP metrics.
+    #[command(hide = true)]
+    Post(PostArgs),
+    /// Background worker that rescans transcripts for `shared_cost_today`.
+    #[command(hide = true)]
+    AggregateRefresh,
+    /// Background worker that looks up a branch's pull request with `gh`.
+    #[command(hide = true)]
+    PrRefresh(PrRefreshArgs),
+    /// Cost per branch, day, project or model.
+    Report(ReportArgs),
+    /// Live view of every recorded session.
+    #[cfg(feature = "dashboard")]
+    Dashboard,
+    /// Print a shell completion script.
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Print the man page (roff).
+    Man,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TimerAction {
+    /// Start a timer, replacing a running one.
+    Start {
+        /// `25m`, `1h30m`, `90s`, or plain minutes.
+        duration: String,
+    },
+    /// Stop the running timer.
+    Stop,
+    /// Show the running timer.
+    Status,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigAction {
+    /// Print the value of a dotted key, e.g. `layout.compact`.
+    Get { key: String },
+    /// Set a dotted key, keeping the file's comments and layout.
+    Set { key: String, value: String },
+    /// Remove a key from the file, so its default applies.
+    Unset { key: String },
+    /// Print the config file's path.
+    Path,
+    /// Write the defaults to the config path, commented out.
+    Init {
+        /// Replace an existing file.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Report type errors and unknown keys; exits 1 when there are any.
+    Validate,
+    /// Print the effective config after the file and `CC_STATUSLINE_*`
+    /// overrides.
+    Show,
+}
+
+#[derive(Debug, Args)]
+pub struct SelftestArgs {
+    /// Number of random inputs to render.
+    #[arg(long, value_name = "N", default_value_t = 200)]
+    pub iterations: usize,
+    /// Seed for the inputs, to repeat a run; the current time otherwise.
+    #[arg(long, value_name = "N")]
+    pub seed: Option<u64>,
+    /// Slowest render that still passes, in milliseconds.
+    #[arg(long, value_name = "N", default_value_t = 500)]
+    pub max_ms: u64,
+}
+
+#[derive(Debug, Args)]
+pub struct InstallArgs {
+    /// Write the project's `.claude/settings.json` instead of the user's.
+    #[arg(long)]
+    pub project: bool,
+    /// The command to run; this binary's path by default.
+    #[arg(long, value_name = "CMD")]
+    pub command: Option<String>,
+    /// Also set a key in the statusline's own config file.
+    #[arg(long = "set", value_name = "KEY=VALUE", value_parser = key_value)]
+    pub sets: Vec<(String, String)>,
+    /// Arguments appended to the command, after `--` (e.g. `-- --short`).
+    #[arg(last = true, value_name = "ARGS")]
+    pub args: Vec<String>,
+}
+
+fn key_value(arg: &str) -> Result<(String, String), String> {
+    arg.split_once('=')
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .ok_or_else(|| "expected <key>=<value>".to_string())
+}
+
+#[derive(Debug, Args)]
+pub struct PreviewArgs {
+    /// Sample sessions to render; all of them by default.
+    #[arg(value_name = "FIXTURE")]
+    pub fixtures: Vec<String>,
+    /// List the sample sessions instead.
+    #[arg(long)]
+    pub list: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct SummarizeArgs {
+    pub session_id: String,
+    pub transcript: std::path::PathBuf,
+    #[arg(long, default_value = "haiku")]
+    pub model: String,
+}
+
+#[derive(Debug, Args)]
+pub struct PostArgs {
+    pub url: String,
+    #[arg(long, default_value_t = 2000)]
+    pub timeout_ms: u64,
+}
+
+#[derive(Debug, Args)]
+pub struct PrRefreshArgs {
+    pub dir: std::path::PathBuf,
+    pub branch: String,
+}
+
+#[derive(Debug, Args)]
+pub struct ReportArgs {
+    /// How to group the costs; `day` with `--aggregate`, `branch` otherwise.
+    #[arg(long, value_enum, value_name = "GROUPING")]
+    pub by: Option<ReportBy>,
+    /// Read every transcript matched by `[aggregate] transcript_globs`.
+    #[arg(long)]
+    pub aggregate: bool,
+    /// Print JSON instead of a table.
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// What `report --by` groups by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReportBy {
+    Day,
+    Project,
+    Model,
+    Branch,
+}
@@ -0,0 +1,134 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// `[cloud]` config section: the cloud account the agent's CLI and
+/// terraform commands will act on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CloudConfig {
+    pub enabled: bool,
+    /// Which of `aws`, `gcp` and `azure` to look for.
+    pub providers: Vec<String>,
+    /// Glob patterns (`*prod*`) for profiles, projects or subscriptions shown
+    /// in red.
+    pub production: Vec<String>,
+}
+
+impl Default for CloudConfig {
+    fn default() -> Self {
+        CloudConfig {
+            enabled: false,
+            providers: vec!["aws".to_string(), "gcp".to_string(), "azure".to_string()],
+            production: vec!["*prod*".to_string()],
+        }
+    }
+}
+
+/// An active cloud identity, e.g. `aws` / `prod-admin`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloudProfile {
+    pub provider: &'static str,
+    pub name: String,
+}
+
+fn env(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.trim().is_empty())
+}
+
+/// `$AWS_VAULT` (set inside `aws-vault exec`), else `$AWS_PROFILE` or
+/// `$AWS_DEFAULT_PROFILE`.
+pub fn aws_profile() -> Option<String> {
+    env("AWS_VAULT")
+        .or_else(|| env("AWS_PROFILE"))
+        .or_else(|| env("AWS_DEFAULT_PROFILE"))
+}
+
+/// The `[core] project` of the active gcloud configuration, falling back to
+/// the configuration's name. `$CLOUDSDK_CORE_PROJECT` and
+/// `$CLOUDSDK_ACTIVE_CONFIG_NAME` override the files like they do for gcloud.
+pub fn gcp_project(gcloud_dir: &Path) -> Option<String> {
+    if let Some(project) = env("CLOUDSDK_CORE_PROJECT") {
+        return Some(project);
+    }
+    let config_name = env("CLOUDSDK_ACTIVE_CONFIG_NAME").or_else(|| {
+        std::fs::read_to_string(gcloud_dir.join("active_config"))
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    })?;
+    let properties =
+        std::fs::read_to_string(gcloud_dir.join(format!("configurations/config_{}", config_name)))
+            .unwrap_or_default();
+    Some(ini_value(&properties, "core", "project").unwrap_or(config_name))
+}
+
+/// `key` in `[section]` of an INI file.
+fn ini_value(data: &str, section: &str, key: &str) -> Option<String> {
+    let mut current = "";
+    for line in data.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            current = name.trim();
+        } else if current == section {
+            if let Some((k, v)) = line.split_once('=') {
+                if k.trim() == key {
+                    return Some(v.trim().to_string()).filter(|v| !v.is_empty());
+                }
+            }
+        }
+    }
+    None
+}
+
+/// `$ARM_SUBSCRIPTION_ID`, as used by the azurerm terraform provider.
+pub fn azure_subscription() -> Option<String> {
+    env("ARM_SUBSCRIPTION_ID")
+}
+
+/// The active identity of every enabled provider.
+pub fn active_profiles(config: &CloudConfig) -> Vec<CloudProfile> {
+    let gcloud_dir = env("CLOUDSDK_CONFIG")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| crate::expand_home("~/.config/gcloud"));
+    config
+        .providers
+        .iter()
+        .filter_map(|provider| match provider.as_str() {
+            "aws" => aws_profile().map(|name| CloudProfile {
+                provider: "aws",
+                name,
+            }),
+            "gcp" => gcp_project(&gcloud_dir).map(|name| CloudProfile {
+                provider: "gcp",
+                name,
+            }),
+            "azure" => azure_subscription().map(|name| CloudProfile {
+                provider: "az",
+                name,
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// `aws prod-admin gcp my-project`, each red when it matches `production`.
+pub fn format_cloud(config: &CloudConfig, profiles: &[CloudProfile]) -> String {
+    profiles
+        .iter()
+        .map(|profile| {
+            let color = if crate::matches_any(&config.production, &profile.name) {
+                "\x1b[1;31m"
+            } else {
+                match profile.provider {
+                    "aws" => "\x1b[38;5;208m",
+                    "gcp" => "\x1b[34m",
+                    _ => "\x1b[36m",
+                }
+            };
+            format!(
+                "\x1b[90m{} {}{}\x1b[0m",
+                profile.provider, color, profile.name
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,589 @@
+use crate::aggregate::AggregateConfig;
+use crate::alerts::AlertsConfig;
+use crate::anomaly::AnomalyConfig;
+use crate::budget::BudgetConfig;
+use crate::export::ExportConfig;
+use crate::path::ShortenOptions;
+use crate::summary::SummaryConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// User configuration, read from `~/.config/cc-statusline/config.toml`.
+///
+/// Every field has a default so a missing file, or a file that only sets a
+/// couple of keys, behaves like the stock statusline.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub layout: LayoutConfig,
+    #[serde(alias = "segment")]
+    pub segments: SegmentsConfig,
+    pub path: PathConfig,
+    pub models: ModelsConfig,
+    pub cost: CostConfig,
+    pub budget: BudgetConfig,
+    pub theme: ThemeConfig,
+    pub alerts: AlertsConfig,
+    pub aggregate: AggregateConfig,
+    pub anomaly: AnomalyConfig,
+    pub export: ExportConfig,
+    pub summary: SummaryConfig,
+    pub script: crate::script::ScriptConfig,
+    pub kube: crate::kube::KubeConfig,
+    pub cloud: crate::cloud::CloudConfig,
+    pub runtime: crate::runtime::RuntimeConfig,
+    pub build_target: crate::build_target::BuildTargetConfig,
+    pub host: crate::host::HostConfig,
+    pub clock: crate::system::ClockConfig,
+    pub battery: crate::system::BatteryConfig,
+    pub icons: crate::icons::IconsConfig,
+    pub bar: crate::bar::BarConfig,
+    pub thresholds: ThresholdsConfig,
+    pub repo: crate::remote::RepoConfig,
+    pub remote_workspace: crate::remote_workspace::RemoteWorkspaceConfig,
+    pub git: crate::git::GitConfig,
+    pub vcs: crate::vcs::VcsConfig,
+    pub release: crate::release::ReleaseConfig,
+    pub trends: crate::state::TrendsConfig,
+    pub format: crate::format::FormatConfig,
+    pub history: crate::history::HistoryConfig,
+    /// `[[projects]]`: labels for repositories matching path or remote
+    /// patterns.
+    #[serde(alias = "project")]
+    pub projects: Vec<crate::project::ProjectLabel>,
+    /// Set from `--skip-pr-status`; not read from the config file.
+    #[serde(skip)]
+    pub skip_pr_status: bool,
+    /// Why the config file was ignored, when [`Config::load`] couldn't use
+    /// it. Rendered as a `⚠ config` marker.
+    #[serde(skip)]
+    pub load_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LayoutConfig {
+    /// Spaces emitted before the line, for layouts that clip the first column.
+    pub padding_left: usize,
+    /// Spaces emitted after the line, for layouts that clip the last column.
+    pub padding_right: usize,
+    /// Collapse the `•` separators between segments to single spaces.
+    pub compact: bool,
+    /// Emit only the cells that changed since the previous render of the same
+    /// session (cursor-positioned), to avoid flicker on full redraws.
+    pub frame_diff: bool,
+    /// Show the context segment as input/output/cache token counts instead
+    /// of a bar.
+    pub verbose_context: bool,
+    /// OSC 8 links on the branch: `auto`, `always` or `never`. Not used
+    /// with `frame_diff`, whose cell diffing can't carry them.
+    pub hyperlinks: crate::hyperlink::HyperlinkMode,
+    /// Format string such as `"{dir} {git}[ • {cost}]"` replacing the
+    /// default arrangement of segments.
+    pub template: Option<String>,
+    /// Milliseconds after which optional segments (git extras, transcript
+    /// scans, kube, custom commands, ...) are skipped for this render.
+    pub budget_ms: Option<u64>,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        LayoutConfig {
+            padding_left: 0,
+            padding_right: 0,
+            compact: false,
+            frame_diff: false,
+            verbose_context: false,
+            hyperlinks: Default::default(),
+            template: None,
+            budget_ms: Some(50),
+        }
+    }
+}
+
+/// Visibility of individual segments.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SegmentsConfig {
+    /// The output style name (`default`, `Explanatory`, ...).
+    pub output_style: bool,
+    /// Claude's permission mode, e.g. `⏸ plan` while in plan mode.
+    pub permission_mode: bool,
+    /// The vim input mode (`[N]`/`[I]`) when vim keybindings are enabled.
+    pub vim_mode: bool,
+    /// The active agent/subagent name (`🤖 code-reviewer`).
+    pub agent: bool,
+    /// The last tool reported by `statusline hook` (`Bash 2s ago`).
+    pub last_tool: bool,
+    /// The last tool in the transcript and the session's tool call count
+    /// (`🛠 Edit ×47`).
+    pub tool_calls: bool,
+    /// Output tokens of the session and size and speed of the last response
+    /// (`✍ 48k · last 3.2k 62 t/s`).
+    pub output: bool,
+    /// How many times the context has been compacted (`⟳2`).
+    pub compactions: bool,
+    /// Estimated thinking tokens of the last turn (`🧠 1.2k`) when it used
+    /// extended thinking.
+    pub thinking: bool,
+    /// Progress of Claude's `TodoWrite` checklist (`☑ 3/7`), followed by
+    /// the task in progress with `tasks_active`.
+    pub tasks: bool,
+    pub tasks_active: bool,
+    /// API errors and failed tool calls in the transcript (`❗ 5 (2 api)`),
+    /// red while the last one is under `errors_recent_secs` old.
+    pub errors: bool,
+    pub errors_recent_secs: i64,
+    /// `idle 12m` once the transcript has been quiet for `idle_after_secs`.
+    pub idle: bool,
+    pub idle_after_secs: i64,
+    /// The session ID: `off`, `short`, `full` or `resume`.
+    pub session_id: SessionIdMode,
+    /// How the session duration is written: `compact` (`1h23m`), `clock`
+    /// (`01:23:45`) or `started` (`started 14:02`).
+    pub duration_format: DurationFormat,
+    /// Seconds in compact durations under an hour (`4m12s`, `45s`) instead
+    /// of `<1m`.
+    pub duration_seconds: bool,
+    /// `[[segments.custom]]`: segments produced by external commands.
+    pub custom: Vec<crate::custom::CustomSegment>,
+    /// Conditions for showing `cost`, `context` and `duration`, e.g.
+    /// `cost = "> 0.05"`, `duration = ">= 10m"`, `context = "> 30%"`.
+    pub show_when: HashMap<String, String>,
+}
+
+impl Default for SegmentsConfig {
+    fn default() -> Self {
+        SegmentsConfig {
+            output_style: true,
+            permission_mode: true,
+            vim_mode: true,
+            agent: true,
+            last_tool: true,
+            tool_calls: false,
+            output: false,
+            compactions: false,
+            thinking: false,
+            tasks: false,
+            tasks_active: false,
+            errors: false,
+            errors_recent_secs: 300,
+            idle: false,
+            idle_after_secs: 600,
+            session_id: SessionIdMode::Off,
+            duration_format: DurationFormat::Compact,
+            duration_seconds: false,
+            custom: Vec::new(),
+            show_when: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PathConfig {
+    /// How the working directory is rendered.
+    pub mode: PathMode,
+    /// Hide the path entirely when it is a repository directly inside one of
+    /// `project_roots` (the branch already says where you are).
+    pub short: bool,
+    /// Directories whose immediate children are considered "standard" project
+    /// locations for `short`. `~` is expanded.
+    pub project_roots: Vec<String>,
+    /// Number of trailing components kept at full length.
+    pub full_components: usize,
+    /// Trailing components kept full for directories inside one of
+    /// `project_roots`, so the places you actually work stay readable while
+    /// everything else is shortened per `full_components`.
+    pub project_full_components: Option<usize>,
+    /// Maximum width of the path in cells; `0` means unlimited.
+    pub max_length: usize,
+    /// Keep the repository root directory name unabbreviated.
+    pub keep_repo_root: bool,
+    /// Only abbreviate paths wider than this many cells; `0` always does.
+    pub shorten_over_width: usize,
+}
+
+impl Default for PathConfig {
+    fn default() -> Self {
+        PathConfig {
+            mode: PathMode::Fish,
+            short: false,
+            project_roots: vec!["~/Projects".to_string()],
+            full_components: 1,
+            project_full_components: None,
+            max_length: 0,
+            keep_repo_root: false,
+            shorten_over_width: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PathMode {
+    /// Fish-style abbreviation relative to `$HOME` (`~/P/w/m/src/api`).
+    Fish,
+    /// Relative to the repository root (`myrepo/src/api`); falls back to
+    /// `fish` outside a repository.
+    Repo,
+}
+
+impl PathConfig {
+    /// Options for shortening `dir`, honoring `project_full_components` when
+    /// `dir` lies inside a project root.
+    pub fn shorten_options_for(&self, dir: impl AsRef<std::path::Path>) -> ShortenOptions {
+        let mut opts = self.shorten_options();
+        if let Some(n) = self.project_full_components {
+            if self.is_in_project_root(dir) {
+                opts.full_components = n;
+            }
+        }
+        opts
+    }
+
+    pub fn is_in_project_root(&self, dir: impl AsRef<std::path::Path>) -> bool {
+        let dir = dir.as_ref();
+        self.project_roots.iter().any(|root| {
+            let root = crate::expand_home(root);
+            dir != root && dir.starts_with(&root)
+        })
+    }
+
+    pub fn shorten_options(&self) -> ShortenOptions {
+        ShortenOptions {
+            full_components: self.full_components,
+            max_length: self.max_length,
+            keep_repo_root: self.keep_repo_root,
+            shorten_over_width: self.shorten_over_width,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelsConfig {
+    /// Maps gateway-specific model IDs to canonical ones for pricing and
+    /// context-window detection, e.g. `"corp-fast" = "claude-haiku-4-5"`.
+    pub aliases: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CostConfig {
+    /// How the session cost is presented.
+    pub mode: CostMode,
+    /// Also show the cost of the last N minutes (from transcript
+    /// timestamps, up to a day), which reacts to runaway loops faster than
+    /// the session total. `0` disables it.
+    pub rolling_window_minutes: u64,
+    /// After the total, show what each model cost when the session used more
+    /// than one (`Opus $6.10 · Sonnet $1.40`).
+    pub breakdown: bool,
+}
+
+/// How the `session` segment shows the session ID.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionIdMode {
+    #[default]
+    Off,
+    /// The first 8 characters, `1a2b3c4d`.
+    Short,
+    /// The whole UUID.
+    Full,
+    /// A `claude --resume <id>` command to copy.
+    Resume,
+}
+
+/// How the time segment shows the session duration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DurationFormat {
+    /// `1h23m`.
+    #[default]
+    Compact,
+    /// `01:23:45`.
+    Clock,
+    /// The local time the session started, `started 14:02`.
+    Started,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CostMode {
+    /// API-equivalent dollars.
+    #[default]
+    Api,
+    /// Share of the Max 5x plan's monthly price ($100) the session used.
+    Max5,
+    /// Share of the Max 20x plan's monthly price ($200) the session used.
+    Max20,
+    /// No cost segment, for subscribers who find dollar figures misleading.
+    Hidden,
+}
+
+impl CostMode {
+    /// Monthly plan price the cost is measured against, for plan modes.
+    pub fn plan_price_usd(self) -> Option<f64> {
+        match self {
+            CostMode::Max5 => Some(100.0),
+            CostMode::Max20 => Some(200.0),
+            CostMode::Api | CostMode::Hidden => None,
+        }
+    }
+
+    pub fn plan_name(self) -> &'static str {
+        match self {
+            CostMode::Max5 => "Max5",
+            CostMode::Max20 => "Max20",
+            CostMode::Api | CostMode::Hidden => "",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeConfig {
+    /// Terminal background as `#rrggbb`, used for contrast checks.
+    pub background: String,
+    /// Minimum WCAG contrast ratio for foreground colors (e.g. `4.5`).
+    /// Colors below it are lightened or darkened. `0` disables the check.
+    pub min_contrast: f64,
+    /// `auto`, `truecolor`, `256`, `16` or `none`.
+    pub color_depth: crate::theme::ColorDepth,
+    /// Colors of the context percentage below, between and above
+    /// `thresholds.context`.
+    pub context_colors: Vec<String>,
+    /// Colors of the cost bands of `thresholds.cost`.
+    pub cost_colors: Vec<String>,
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        ThemeConfig {
+            background: "#000000".to_string(),
+            min_contrast: 0.0,
+            color_depth: crate::theme::ColorDepth::Auto,
+            context_colors: ["gray", "yellow", "orange", "red"]
+                .map(String::from)
+                .to_vec(),
+            cost_colors: ["green", "yellow", "red"].map(String::from).to_vec(),
+        }
+    }
+}
+
+/// `[thresholds]` config section: color breakpoints, ascending. `n`
+/// thresholds make `n + 1` bands, colored from the theme's color lists.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThresholdsConfig {
+    /// Context window percentages.
+    pub context: Vec<f64>,
+    /// Session cost in dollars (percent of the plan price in plan modes).
+    pub cost: Vec<f64>,
+}
+
+impl Default for ThresholdsConfig {
+    fn default() -> Self {
+        ThresholdsConfig {
+            context: vec![50.0, 70.0, 90.0],
+            cost: vec![5.0, 20.0],
+        }
+    }
+}
+
+impl Config {
+    /// Layers the config file and `CC_STATUSLINE_*` overrides from the
+    /// environment over the defaults. A missing file counts as empty; an
+    /// unreadable or invalid one too, with the reason in `load_error`.
+    pub fn load() -> Config {
+        let (file, load_error) = match config_path() {
+            Some(path) => match read_config_file(&path) {
+                Ok(table) => (table, None),
+                Err(e) => {
+                    crate::debug::trace(&format!("ignoring {}: {}", path.display(), e));
+                    (toml::Table::new(), Some(e))
+                }
+            },
+            None => (toml::Table::new(), None),
+        };
+        let mut config = Config::from_layers(file, &env_overrides(std::env::vars()));
+        config.load_error = load_error;
+        config
+    }
+
+    /// Applies `overrides` to the parsed config file in order. An override
+    /// whose value doesn't fit its key is skipped (and traced with
+    /// `--debug`) rather than discarding the whole config.
+    pub fn from_layers(mut table: toml::Table, overrides: &[EnvOverride]) -> Config {
+        let mut config: Config = toml::Value::Table(table.clone())
+            .try_into()
+            .unwrap_or_default();
+        for item in overrides {
+            let mut candidate = table.clone();
+            item.apply(&mut candidate);
+            match toml::Value::Table(candidate.clone()).try_into::<Config>() {
+                Ok(layered) => {
+                    table = candidate;
+                    config = layered;
+                }
+                Err(e) => crate::debug::trace(&format!("ignoring {}: {}", item.var, e.message())),
+            }
+        }
+        config
+    }
+}
+
+/// The last config file read by this process, reused until its modification
+/// time or size changes. Long-running callers (the dashboard, embedding
+/// applications) can call [`Config::load`] freely and still see edits.
+struct LoadedFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    len: u64,
+    result: Result<toml::Table, String>,
+}
+
+static LOADED: Mutex<Option<LoadedFile>> = Mutex::new(None);
+
+/// The parsed config file at `path`, checked against the schema. A missing
+/// file is an empty table.
+fn read_config_file(path: &Path) -> Result<toml::Table, String> {
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(toml::Table::new()),
+        Err(e) => return Err(e.to_string()),
+    };
+    let modified = metadata.modified().ok();
+
+    let mut loaded = LOADED.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(file) = loaded.as_ref() {
+        if file.path == path && file.modified == modified && file.len == metadata.len() {
+            return file.result.clone();
+        }
+    }
+
+    let result = fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|data| data.parse::<toml::Table>().map_err(|e| e.to_string()))
+        .and_then(|table| {
+            toml::Value::Table(table.clone())
+                .try_into::<Config>()
+                .map(|_| table)
+                .map_err(|e| e.message().to_string())
+        });
+    *loaded = Some(LoadedFile {
+        path: path.to_path_buf(),
+        modified,
+        len: metadata.len(),
+        result: result.clone(),
+    });
+    result
+}
+
+/// Prefix of environment variables that override config keys.
+pub const ENV_PREFIX: &str = "CC_STATUSLINE_";
+
+/// A config key set from the environment: `CC_STATUSLINE_LAYOUT__COMPACT=true`
+/// sets `layout.compact`. `__` separates sections from keys.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvOverride {
+    /// The variable's name, for messages.
+    pub var: String,
+    pub path: Vec<String>,
+    pub value: toml::Value,
+}
+
+impl EnvOverride {
+    fn apply(&self, table: &mut toml::Table) {
+        let Some((key, sections)) = self.path.split_last() else {
+            return;
+        };
+        let mut table = table;
+        for section in sections {
+            let entry = table
+                .entry(section.clone())
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+            if !entry.is_table() {
+                *entry = toml::Value::Table(toml::Table::new());
+            }
+            let toml::Value::Table(inner) = entry else {
+                return;
+            };
+            table = inner;
+        }
+        table.insert(key.clone(), self.value.clone());
+    }
+}
+
+/// Parses an override's value as TOML (`true`, `2`, `[50, 80]`,
+/// `"quoted"`), else as a comma-separated list (`5,20`, `dir,git`), else as
+/// a bare string.
+pub fn parse_env_value(raw: &str) -> toml::Value {
+    let scalar = |raw: &str| {
+        format!("value = {}", raw)
+            .parse::<toml::Table>()
+            .ok()
+            .and_then(|mut table| table.remove("value"))
+    };
+    if let Some(value) = scalar(raw) {
+        return value;
+    }
+    if raw.contains(',') {
+        return toml::Value::Array(
+            raw.split(',')
+                .map(str::trim)
+                .map(|item| scalar(item).unwrap_or_else(|| toml::Value::String(item.to_string())))
+                .collect(),
+        );
+    }
+    toml::Value::String(raw.to_string())
+}
+
+/// `CC_STATUSLINE_*` variables as config overrides, sorted by name so the
+/// result doesn't depend on environment order. `$CC_STATUSLINE_CONFIG`, which
+/// names the config file, is not an override.
+pub fn env_overrides(vars: impl Iterator<Item = (String, String)>) -> Vec<EnvOverride> {
+    let mut overrides: Vec<EnvOverride> = vars
+        .filter(|(name, _)| name != "CC_STATUSLINE_CONFIG")
+        .filter_map(|(name, raw)| {
+            let key = name.strip_prefix(ENV_PREFIX)?.to_ascii_lowercase();
+            let path: Vec<String> = key.split("__").map(str::to_string).collect();
+            if path.iter().any(|part| part.is_empty()) {
+                return None;
+            }
+            Some(EnvOverride {
+                value: parse_env_value(&raw),
+                var: name,
+                path,
+            })
+        })
+        .collect();
+    overrides.sort_by(|a, b| a.var.cmp(&b.var));
+    overrides
+}
+
+/// `$CC_STATUSLINE_CONFIG`, else `$XDG_CONFIG_HOME/cc-statusline/config.toml`,
+/// else `cc-statusline/config.toml` in the platform config directory
+/// (`~/.config` on Linux, `~/Library/Application Support` on macOS,
+/// `%APPDATA%` on Windows). An existing `~/.config/cc-statusline/config.toml`
+/// keeps being used on macOS and Windows.
+pub fn config_path() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var("CC_STATUSLINE_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+
+    let path = crate::base_dir("XDG_CONFIG_HOME", dirs::config_dir(), ".config")
+        .join("cc-statusline")
+        .join("config.toml");
+    let legacy = crate::expand_home("~/.config/cc-statusline/config.toml");
+    if !path.exists() && legacy.exists() {
+        return Some(legacy);
+    }
+    Some(path)
+}
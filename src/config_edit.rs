@@ -0,0 +1,400 @@
+use crate::cli::ConfigAction;
+use crate::config::{config_path, Config};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, Item, Table, Value};
+
+/// The config file as an editable document. Comments, key order and
+/// formatting survive a read/modify/write cycle, so scripted setup (dotfiles
+/// managers, `statusline config set`) can change single keys safely.
+#[derive(Debug, Clone)]
+pub struct ConfigDocument {
+    path: PathBuf,
+    doc: DocumentMut,
+}
+
+impl ConfigDocument {
+    /// Opens the file at `path`; a missing file is an empty document.
+    pub fn open(path: &Path) -> Result<ConfigDocument, Box<dyn Error>> {
+        let data = match fs::read_to_string(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(ConfigDocument {
+            path: path.to_path_buf(),
+            doc: data.parse()?,
+        })
+    }
+
+    /// Opens the file [`Config::load`] reads.
+    pub fn open_default() -> Result<ConfigDocument, Box<dyn Error>> {
+        let path = config_path().ok_or("no config path")?;
+        ConfigDocument::open(&path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The value at a dotted key such as `layout.compact`, as TOML.
+    pub fn get(&self, key: &str) -> Option<String> {
+        let mut item = self.doc.as_item();
+        for part in key.split('.') {
+            item = item.get(part)?;
+        }
+        Some(match item {
+            Item::Value(value) => {
+                let mut value = value.clone();
+                value.decor_mut().clear();
+                value.to_string()
+            }
+            other => other.to_string().trim().to_string(),
+        })
+    }
+
+    /// Sets a dotted key, creating missing tables on the way.
+    pub fn set(&mut self, key: &str, value: Value) -> Result<(), Box<dyn Error>> {
+        let (tables, last) = split_key(key)?;
+        let mut table = self.doc.as_table_mut();
+        for part in tables {
+            let entry = table.entry(part).or_insert_with(|| {
+                let mut t = Table::new();
+                t.set_implicit(true);
+                Item::Table(t)
+            });
+            table = entry
+                .as_table_mut()
+                .ok_or_else(|| format!("`{}` is not a table", part))?;
+        }
+
+        match table.get_mut(last).and_then(Item::as_value_mut) {
+            // Keep the existing key's comments and spacing.
+            Some(existing) => {
+                let decor = existing.decor().clone();
+                *existing = value;
+                *existing.decor_mut() = decor;
+            }
+            None => {
+                table.insert(last, Item::Value(value));
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses `raw` as a TOML value (`true`, `30`, `"x"`, `["a"]`), falling
+    /// back to a plain string, and sets it.
+    pub fn set_from_str(&mut self, key: &str, raw: &str) -> Result<(), Box<dyn Error>> {
+        let value = raw.parse::<Value>().unwrap_or_else(|_| Value::from(raw));
+        self.set(key, value)
+    }
+
+    /// Removes a dotted key; returns whether it existed.
+    pub fn unset(&mut self, key: &str) -> bool {
+        let Ok((tables, last)) = split_key(key) else {
+            return false;
+        };
+        let mut table = self.doc.as_table_mut();
+        for part in tables {
+            match table.get_mut(part).and_then(Item::as_table_mut) {
+                Some(t) => table = t,
+                None => return false,
+            }
+        }
+        table.remove(last).is_some()
+    }
+
+    /// Deserializes the document, catching type errors before it is saved.
+    pub fn validate(&self) -> Result<Config, Box<dyn Error>> {
+        Ok(toml::from_str(&self.doc.to_string())?)
+    }
+
+    /// Writes the document back atomically.
+    pub fn save(&self) -> std::io::Result<()> {
+        crate::cache::write_atomic(&self.path, &self.doc.to_string())
+    }
+}
+
+impl std::fmt::Display for ConfigDocument {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.doc)
+    }
+}
+
+fn split_key(key: &str) -> Result<(Vec<&str>, &str), Box<dyn Error>> {
+    let mut parts: Vec<&str> = key.split('.').collect();
+    if parts.iter().any(|p| p.is_empty()) {
+        return Err(format!("invalid key `{}`", key).into());
+    }
+    let last = parts.pop().ok_or("empty key")?;
+    Ok((parts, last))
+}
+
+/// Something `statusline config validate` found wrong with a config file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigProblem {
+    /// Not TOML, or a value of the wrong type. The whole file is ignored.
+    Invalid { line: usize, message: String },
+    /// A key the statusline doesn't read, with the closest known key.
+    UnknownKey {
+        key: String,
+        suggestion: Option<String>,
+    },
+}
+
+impl std::fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigProblem::Invalid { line, message } => write!(f, "line {}: {}", line, message),
+            ConfigProblem::UnknownKey {
+                key,
+                suggestion: Some(suggestion),
+            } => write!(f, "unknown key `{}`, did you mean `{}`?", key, suggestion),
+            ConfigProblem::UnknownKey {
+                key,
+                suggestion: None,
+            } => write!(f, "unknown key `{}`", key),
+        }
+    }
+}
+
+/// Checks config file contents against the schema. Every unknown key is
+/// reported; parsing stops at the first invalid value.
+pub fn validate_str(data: &str) -> Vec<ConfigProblem> {
+    let schema = default_table();
+    let mut problems = Vec::new();
+    let result: Result<Config, _> =
+        serde_ignored::deserialize(toml::Deserializer::new(data), |path| {
+            let mut keys = Vec::new();
+            path_keys(&path, &mut keys);
+            problems.push(ConfigProblem::UnknownKey {
+                suggestion: suggest(&schema, &keys),
+                key: keys.join("."),
+            });
+        });
+    if let Err(e) = result {
+        problems.push(ConfigProblem::Invalid {
+            line: e
+                .span()
+                .map(|span| data[..span.start].matches('\n').count() + 1)
+                .unwrap_or(0),
+            message: e.message().to_string(),
+        });
+    }
+    problems
+}
+
+fn path_keys(path: &serde_ignored::Path, keys: &mut Vec<String>) {
+    match path {
+        serde_ignored::Path::Root => {}
+        serde_ignored::Path::Seq { parent, index } => {
+            path_keys(parent, keys);
+            keys.push(index.to_string());
+        }
+        serde_ignored::Path::Map { parent, key } => {
+            path_keys(parent, keys);
+            keys.push(key.clone());
+        }
+        serde_ignored::Path::Some { parent }
+        | serde_ignored::Path::NewtypeStruct { parent }
+        | serde_ignored::Path::NewtypeVariant { parent } => path_keys(parent, keys),
+    }
+}
+
+/// The known key closest to the last of `keys`, if any is within a couple
+/// of typos. Keys without a default (e.g. `layout.template`) aren't offered.
+fn suggest(schema: &toml::Table, keys: &[String]) -> Option<String> {
+    let (unknown, parents) = keys.split_last()?;
+    let mut table = schema;
+    for key in parents {
+        table = table.get(key)?.as_table()?;
+    }
+    table
+        .keys()
+        .map(|known| (strsim::damerau_levenshtein(unknown, known), known))
+        .filter(|(distance, _)| *distance <= (unknown.len() / 3).max(2))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, known)| {
+            parents
+                .iter()
+                .chain(std::iter::once(known))
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(".")
+        })
+}
+
+fn default_table() -> toml::Table {
+    toml::Table::try_from(Config::default()).unwrap_or_default()
+}
+
+/// Every default setting as a commented-out line under its section, for
+/// `statusline config init`.
+pub fn default_config_text() -> String {
+    let mut out = String::from(
+        "# cc-statusline configuration. Every key is optional and the commented\n\
+         # values are the defaults; uncomment a line to change it. Keys without a\n\
+         # default (e.g. layout.template) are described in the README.\n",
+    );
+    write_commented(&mut out, "", &default_table());
+    out
+}
+
+fn write_commented(out: &mut String, path: &str, table: &toml::Table) {
+    if !path.is_empty() {
+        out.push_str(&format!("\n[{}]\n", path));
+    }
+    for (key, value) in table.iter().filter(|(_, v)| !v.is_table()) {
+        out.push_str(&format!("# {} = {}\n", toml_key(key), value));
+    }
+    for (key, value) in table {
+        if let toml::Value::Table(inner) = value {
+            let path = match path {
+                "" => toml_key(key),
+                _ => format!("{}.{}", path, toml_key(key)),
+            };
+            write_commented(out, &path, inner);
+        }
+    }
+}
+
+fn toml_key(key: &str) -> String {
+    if !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        key.to_string()
+    } else {
+        toml::Value::String(key.to_string()).to_string()
+    }
+}
+
+/// `statusline config init [--force]`: writes [`default_config_text`] to the
+/// config path, refusing to replace an existing file without `--force`.
+fn init(force: bool) -> i32 {
+    let Some(path) = config_path() else {
+        eprintln!("config: no config path");
+        return 1;
+    };
+    if path.exists() && !force {
+        eprintln!(
+            "config: {} already exists; pass --force to replace it",
+            path.display()
+        );
+        return 1;
+    }
+    match crate::cache::write_atomic(&path, &default_config_text()) {
+        Ok(()) => {
+            println!("{}", path.display());
+            0
+        }
+        Err(e) => {
+            eprintln!("config: {}: {}", path.display(), e);
+            1
+        }
+    }
+}
+
+/// `statusline config validate`: prints every problem with the config file
+/// and exits 1 if there are any.
+fn validate() -> i32 {
+    let Some(path) = config_path() else {
+        eprintln!("config: no config path");
+        return 1;
+    };
+    let data = match fs::read_to_string(&path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            println!("{}: not present, using defaults", path.display());
+            return 0;
+        }
+        Err(e) => {
+            eprintln!("config: {}: {}", path.display(), e);
+            return 1;
+        }
+    };
+
+    let problems = validate_str(&data);
+    if problems.is_empty() {
+        println!("{}: ok", path.display());
+        return 0;
+    }
+    for problem in &problems {
+        println!("{}: {}", path.display(), problem);
+    }
+    1
+}
+
+/// `statusline config show`: the effective config after defaults, the file
+/// and `CC_STATUSLINE_*` overrides, as TOML.
+fn show() -> i32 {
+    let config = Config::load();
+    if let Some(e) = &config.load_error {
+        eprintln!("config: file ignored: {}", e);
+    }
+    match toml::to_string(&config) {
+        Ok(text) => {
+            print!("{}", text);
+            0
+        }
+        Err(e) => {
+            eprintln!("config: {}", e);
+            1
+        }
+    }
+}
+
+/// Runs `edit` on the config file, opened for editing.
+fn with_document(edit: impl FnOnce(&mut ConfigDocument) -> i32) -> i32 {
+    match ConfigDocument::open_default() {
+        Ok(mut doc) => edit(&mut doc),
+        Err(e) => {
+            eprintln!("config: {}", e);
+            1
+        }
+    }
+}
+
+/// `statusline config get|set|unset|path|init|validate|show`.
+pub fn run_cli(action: &ConfigAction) -> i32 {
+    match action {
+        ConfigAction::Init { force } => init(*force),
+        ConfigAction::Validate => validate(),
+        ConfigAction::Show => show(),
+        ConfigAction::Path => with_document(|doc| {
+            println!("{}", doc.path().display());
+            0
+        }),
+        ConfigAction::Get { key } => with_document(|doc| match doc.get(key) {
+            Some(value) => {
+                println!("{}", value);
+                0
+            }
+            None => 1,
+        }),
+        ConfigAction::Set { key, value } => with_document(|doc| {
+            let result = doc
+                .set_from_str(key, value)
+                .and_then(|_| doc.validate().map(|_| ()))
+                .and_then(|_| Ok(doc.save()?));
+            match result {
+                Ok(()) => 0,
+                Err(e) => {
+                    eprintln!("config: {}", e);
+                    1
+                }
+            }
+        }),
+        ConfigAction::Unset { key } => with_document(|doc| {
+            if doc.unset(key) {
+                if let Err(e) = doc.save() {
+                    eprintln!("config: {}", e);
+                    return 1;
+                }
+            }
+            0
+        }),
+    }
+}
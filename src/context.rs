@@ -0,0 +1,151 @@
+use crate::format::NumberFormat;
+use crate::transcript::Transcript;
+use crate::{as_u64_lenient, models, Config};
+
+/// Where a [`ContextUsage`] was read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextSource {
+    /// `context_window.current_usage` in the input (Claude Code 2.0+).
+    Input,
+    /// The usage of the last assistant message in the transcript.
+    Transcript,
+}
+
+/// What currently occupies the context window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContextUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub window_size: u64,
+    pub source: ContextSource,
+}
+
+impl ContextUsage {
+    /// Reads a `current_usage` object.
+    pub fn from_current_usage(
+        usage: &serde_json::Value,
+        window_size: u64,
+        source: ContextSource,
+    ) -> Self {
+        let tokens = |key: &str| usage.get(key).and_then(as_u64_lenient).unwrap_or(0);
+        ContextUsage {
+            input_tokens: tokens("input_tokens"),
+            output_tokens: tokens("output_tokens"),
+            cache_creation_tokens: tokens("cache_creation_input_tokens"),
+            cache_read_tokens: tokens("cache_read_input_tokens"),
+            window_size,
+            source,
+        }
+    }
+
+    /// Tokens that count against the window: the prompt including cache.
+    pub fn used_tokens(&self) -> u64 {
+        self.input_tokens + self.cache_creation_tokens + self.cache_read_tokens
+    }
+
+    /// Share of the window in use, 0–100.
+    pub fn percent(&self) -> f64 {
+        if self.window_size > 0 {
+            ((self.used_tokens() as f64 * 100.0) / self.window_size as f64).min(100.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Whether the window is larger than the standard 200k, i.e. the 1M
+    /// context beta.
+    pub fn is_extended(&self) -> bool {
+        self.window_size > models::DEFAULT_CONTEXT_WINDOW
+    }
+
+    /// `412k/1M`.
+    pub fn format_fraction(&self, numbers: &NumberFormat) -> String {
+        format!(
+            "{}/{}",
+            numbers.tokens(self.used_tokens()),
+            numbers.tokens(self.window_size)
+        )
+    }
+
+    /// `in 2.1k · out 0.6k · cr 10k · cw 3.9k`.
+    pub fn format_breakdown(&self, numbers: &NumberFormat) -> String {
+        format!(
+            "in {} · out {} · cr {} · cw {}",
+            numbers.tokens(self.input_tokens),
+            numbers.tokens(self.output_tokens),
+            numbers.tokens(self.cache_read_tokens),
+            numbers.tokens(self.cache_creation_tokens)
+        )
+    }
+}
+
+fn window_for(model_id: Option<&str>, config: &Config) -> u64 {
+    match model_id {
+        Some(id) => models::context_window_for(id, &config.models.aliases),
+        None => models::DEFAULT_CONTEXT_WINDOW,
+    }
+}
+
+/// From the input's `context_window`; `None` when it has none. A missing or
+/// zero `context_window_size` falls back to the model's window, and a `[1m]`
+/// model ID wins over a reported 200k so usage past 200k isn't pinned at
+/// 100%.
+pub fn from_input(
+    input: &serde_json::Value,
+    model_id: Option<&str>,
+    config: &Config,
+) -> Option<ContextUsage> {
+    let ctx = input.get("context_window")?;
+    let model_window = window_for(model_id, config);
+    let window_size = ctx
+        .get("context_window_size")
+        .and_then(as_u64_lenient)
+        .filter(|&size| size > 0)
+        .map_or(model_window, |size| {
+            if model_window > models::DEFAULT_CONTEXT_WINDOW {
+                size.max(model_window)
+            } else {
+                size
+            }
+        });
+    let empty = serde_json::Value::Null;
+    Some(ContextUsage::from_current_usage(
+        ctx.get("current_usage").unwrap_or(&empty),
+        window_size,
+        ContextSource::Input,
+    ))
+}
+
+/// From the last assistant message with usage in the transcript, for Claude
+/// Code versions that don't send `context_window`.
+pub fn from_transcript(
+    transcript: &Transcript,
+    model_id: Option<&str>,
+    config: &Config,
+) -> Option<ContextUsage> {
+    let entry = transcript.last_usage.as_ref()?;
+    Some(ContextUsage {
+        input_tokens: entry.input_tokens,
+        output_tokens: entry.output_tokens,
+        cache_creation_tokens: entry.cache_creation_tokens,
+        cache_read_tokens: entry.cache_read_tokens,
+        window_size: window_for(model_id.or(entry.model.as_deref()), config),
+        source: ContextSource::Transcript,
+    })
+}
+
+/// The context usage for this render: the input's own figures when present,
+/// else those of the render's `transcript`.
+pub fn usage<'a>(
+    input: &serde_json::Value,
+    config: &Config,
+    transcript: impl FnOnce() -> Option<&'a Transcript>,
+) -> Option<ContextUsage> {
+    let model_id = input
+        .get("model")
+        .and_then(|m| m.get("id"))
+        .and_then(|d| d.as_str());
+    from_input(input, model_id, config).or_else(|| from_transcript(transcript()?, model_id, config))
+}
@@ -0,0 +1,185 @@
+use crate::cache;
+use crate::deadline::Deadline;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use std::time::Duration;
+
+/// One `[[segments.custom]]` entry: an external command whose first line of
+/// output becomes a segment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CustomSegment {
+    /// Identifies the segment in `position` and in the cache.
+    pub name: String,
+    /// A string is run with `sh -c`; an array is run directly.
+    pub command: CustomCommand,
+    /// Seconds the output is reused before the command runs again.
+    pub ttl_secs: i64,
+    /// The command is killed after this long and shows nothing.
+    pub timeout_ms: u64,
+    /// `red`, `green`, `yellow`, `blue`, `magenta`, `cyan`, `gray`, a 256-color
+    /// index (`"208"`) or `#rrggbb`. Unset keeps the command's own colors.
+    pub color: Option<String>,
+    /// `end`, `start`, `before:<segment>` or `after:<segment>`, where
+    /// `<segment>` is a built-in (`model`, `context`, `cost`, `time`, ...) or
+    /// another custom segment.
+    pub position: String,
+}
+
+impl Default for CustomSegment {
+    fn default() -> Self {
+        CustomSegment {
+            name: String::new(),
+            command: CustomCommand::Shell(String::new()),
+            ttl_secs: 30,
+            timeout_ms: 500,
+            color: None,
+            position: "end".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CustomCommand {
+    Shell(String),
+    Argv(Vec<String>),
+}
+
+impl CustomCommand {
+    fn to_command(&self) -> Option<Command> {
+        match self {
+            CustomCommand::Shell(script) if !script.trim().is_empty() => {
+                let mut command = if cfg!(windows) {
+                    let mut c = Command::new("cmd");
+                    c.arg("/C");
+                    c
+                } else {
+                    let mut c = Command::new("sh");
+                    c.arg("-c");
+                    c
+                };
+                command.arg(script);
+                Some(command)
+            }
+            CustomCommand::Argv(argv) => {
+                let (program, args) = argv.split_first()?;
+                let mut command = Command::new(program);
+                command.args(args);
+                Some(command)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedOutput {
+    at: i64,
+    output: String,
+}
+
+/// Runs `command` in `dir`, returning its first non-empty output line, or
+/// `None` if it failed. `Err` when it outlived `timeout`.
+fn run(
+    command: &CustomCommand,
+    dir: Option<&str>,
+    timeout: Duration,
+) -> Result<Option<String>, ()> {
+    let Some(mut command) = command.to_command() else {
+        return Ok(None);
+    };
+    if let Some(dir) = dir.filter(|d| std::path::Path::new(d).is_dir()) {
+        command.current_dir(dir);
+    }
+    let Some(output) = crate::process::output_within(&mut command, timeout)? else {
+        return Ok(None);
+    };
+    Ok(String::from_utf8_lossy(&output)
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .map(|l| {
+            l.chars()
+                .filter(|c| *c == '\x1b' || !c.is_control())
+                .collect()
+        }))
+}
+
+/// The segment's output, from the cache while it is younger than `ttl_secs`.
+/// Failures are cached too, so a broken command doesn't slow every render.
+/// The command gets no longer than what is left of `deadline`; one cut
+/// short by that isn't cached, and runs again next time.
+pub fn output(segment: &CustomSegment, dir: Option<&str>, deadline: &Deadline) -> String {
+    let cache_name = format!(
+        "custom-{}-{}",
+        cache::cache_key(&segment.name),
+        cache::cache_key(dir.unwrap_or_default())
+    );
+    let now = chrono::Utc::now().timestamp();
+    if let Some(cached) = cache::read_cache(&cache_name)
+        .and_then(|data| serde_json::from_str::<CachedOutput>(&data).ok())
+    {
+        if now - cached.at < segment.ttl_secs {
+            return cached.output;
+        }
+    }
+
+    let timeout = Duration::from_millis(segment.timeout_ms);
+    let remaining = deadline.remaining().unwrap_or(timeout);
+    let output = match run(&segment.command, dir, timeout.min(remaining)) {
+        Err(()) if remaining < timeout => return String::new(),
+        output => output.ok().flatten().unwrap_or_default(),
+    };
+    if let Ok(data) = serde_json::to_string(&CachedOutput {
+        at: now,
+        output: output.clone(),
+    }) {
+        let _ = cache::write_cache(&cache_name, &data);
+    }
+    output
+}
+
+pub fn format_custom(segment: &CustomSegment, output: &str) -> String {
+    match segment.color.as_deref().and_then(crate::theme::color_code) {
+        Some(color) => format!("{}{}\x1b[0m", color, output),
+        None if output.contains('\x1b') => format!("{}\x1b[0m", output),
+        None => output.to_string(),
+    }
+}
+
+/// Inserts the custom segments into the named `components` at their
+/// configured positions; unknown anchors fall back to the end. Those left
+/// once `deadline` expires are skipped.
+pub fn splice<'a>(
+    components: &mut Vec<(&'a str, String)>,
+    segments: &'a [CustomSegment],
+    dir: Option<&str>,
+    deadline: &Deadline,
+) {
+    let mut at_start = 0;
+    for segment in segments {
+        let output = deadline.optional("custom", || {
+            crate::error::guarded(&segment.name, || output(segment, dir, deadline))
+        });
+        if output.is_empty() {
+            continue;
+        }
+        let entry = (segment.name.as_str(), format_custom(segment, &output));
+        let anchor = |name: &str| components.iter().position(|(n, _)| *n == name);
+
+        let index = match segment.position.split_once(':') {
+            Some(("before", name)) => anchor(name),
+            Some(("after", name)) => anchor(name).map(|i| i + 1),
+            _ if segment.position == "start" => {
+                at_start += 1;
+                Some(at_start - 1)
+            }
+            _ => None,
+        };
+        match index {
+            Some(i) => components.insert(i, entry),
+            None => components.push(entry),
+        }
+    }
+}
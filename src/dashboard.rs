@@ -0,0 +1,100 @@
+use crate::index::{self, SessionSnapshot};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::Constraint;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, Row, Table};
+use ratatui::{DefaultTerminal, Frame};
+use std::io;
+use std::time::Duration;
+
+/// Sessions idle for longer than this are not listed.
+const ACTIVE_WINDOW_SECS: i64 = 24 * 60 * 60;
+
+/// Runs the `dashboard` TUI until `q` or `Esc` is pressed, re-reading the
+/// session index and the config every second.
+pub fn run() -> io::Result<()> {
+    let mut terminal = ratatui::init();
+    let result = run_loop(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+fn run_loop(terminal: &mut DefaultTerminal) -> io::Result<()> {
+    loop {
+        let thresholds = crate::Config::load().thresholds.context;
+        let now = chrono::Utc::now().timestamp();
+        let sessions: Vec<SessionSnapshot> = index::load_sessions()
+            .into_iter()
+            .filter(|s| now - s.updated_at <= ACTIVE_WINDOW_SECS)
+            .collect();
+
+        terminal.draw(|frame| draw(frame, &sessions, &thresholds, now))?;
+
+        if event::poll(Duration::from_secs(1))? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press
+                    && matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+                {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, sessions: &[SessionSnapshot], thresholds: &[f64], now: i64) {
+    let header = Row::new(["Project", "Model", "Context", "Cost", "Idle", "Session"])
+        .style(Style::default().add_modifier(Modifier::BOLD));
+
+    let rows = sessions.iter().map(|s| {
+        let project = s
+            .project_dir
+            .as_deref()
+            .map(crate::fish_shorten_path)
+            .unwrap_or_else(|| "-".to_string());
+        let context = s
+            .context_pct
+            .map(|p| format!("{}%", p.round() as u32))
+            .unwrap_or_else(|| "-".to_string());
+        let cost = s
+            .cost_usd
+            .map(|c| format!("${}", crate::format_cost(c)))
+            .unwrap_or_else(|| "-".to_string());
+        let context_color = match s.context_pct {
+            Some(p) => {
+                let palette = [Color::Gray, Color::Yellow, Color::LightRed, Color::Red];
+                palette[crate::theme::band(p, thresholds).min(palette.len() - 1)]
+            }
+            None => Color::Gray,
+        };
+
+        Row::new([
+            project,
+            s.model.clone().unwrap_or_else(|| "-".to_string()),
+            context,
+            cost,
+            index::format_idle(now - s.updated_at),
+            s.session_id.chars().take(8).collect(),
+        ])
+        .style(Style::default().fg(context_color))
+    });
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Fill(3),
+            Constraint::Fill(2),
+            Constraint::Length(8),
+            Constraint::Length(10),
+            Constraint::Length(6),
+            Constraint::Length(10),
+        ],
+    )
+    .header(header)
+    .block(Block::bordered().title(format!(
+        " cc-statusline sessions ({}) — q to quit ",
+        sessions.len()
+    )));
+
+    frame.render_widget(table, frame.area());
+}
@@ -0,0 +1,46 @@
+use crate::debug;
+use std::time::{Duration, Instant};
+
+/// Time budget for one render. Optional segments are skipped once it is
+/// spent, so a slow repository or command costs those segments rather than
+/// a laggy prompt.
+#[derive(Debug, Clone, Copy)]
+pub struct Deadline {
+    started: Instant,
+    budget: Option<Duration>,
+}
+
+impl Deadline {
+    /// Starts the clock; `None` never expires.
+    pub fn start(budget_ms: Option<u64>) -> Self {
+        Deadline {
+            started: Instant::now(),
+            budget: budget_ms.map(Duration::from_millis),
+        }
+    }
+
+    pub fn expired(&self) -> bool {
+        self.budget
+            .is_some_and(|budget| self.started.elapsed() >= budget)
+    }
+
+    /// Time left in the budget; `None` without one.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.budget
+            .map(|budget| budget.saturating_sub(self.started.elapsed()))
+    }
+
+    /// Runs `f` under [`debug::profiled`], or returns the empty value
+    /// without running it once the budget is spent.
+    pub fn optional<T: Default>(&self, segment: &'static str, f: impl FnOnce() -> T) -> T {
+        if self.expired() {
+            debug::trace(&format!(
+                "skipped {}: render budget spent after {}ms",
+                segment,
+                self.started.elapsed().as_millis()
+            ));
+            return T::default();
+        }
+        debug::profiled(segment, f)
+    }
+}
@@ -0,0 +1,138 @@
+use std::cell::RefCell;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// Where `--debug` output goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sink {
+    Stderr,
+    /// [`log_path`].
+    File,
+}
+
+static SINK: OnceLock<Sink> = OnceLock::new();
+static PROFILE_SINK: OnceLock<Sink> = OnceLock::new();
+
+thread_local! {
+    /// Time spent per segment in the current render, in first-use order.
+    static TIMINGS: RefCell<Vec<(&'static str, Duration)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Turns on [`trace`] output for the rest of the process. Only the first call
+/// has an effect.
+pub fn enable(sink: Sink) {
+    let _ = SINK.set(sink);
+}
+
+pub fn enabled() -> bool {
+    SINK.get().is_some()
+}
+
+/// `$XDG_STATE_HOME/cc-statusline`, else the platform state directory
+/// (`~/.local/state` on Linux; the local data directory elsewhere).
+pub fn state_dir() -> PathBuf {
+    crate::base_dir(
+        "XDG_STATE_HOME",
+        dirs::state_dir().or_else(dirs::data_local_dir),
+        ".local/state",
+    )
+    .join("cc-statusline")
+}
+
+pub fn log_path() -> PathBuf {
+    state_dir().join("log")
+}
+
+/// Appends a timestamped line to the debug log. Failures are ignored: the
+/// log must never break a render.
+pub fn log(message: &str) {
+    let path = log_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(path) {
+        let _ = writeln!(file, "{} {}", chrono::Local::now().to_rfc3339(), message);
+    }
+}
+
+fn write(sink: Option<&Sink>, message: &str) {
+    match sink {
+        Some(Sink::Stderr) => eprintln!("[statusline] {}", message),
+        Some(Sink::File) => log(message),
+        None => {}
+    }
+}
+
+/// Writes `message` to the `--debug` sink; does nothing unless enabled.
+pub fn trace(message: &str) {
+    write(SINK.get(), message);
+}
+
+/// Runs `f` and traces how long it took as `label: 12.3ms`.
+pub fn timed<T>(label: &str, f: impl FnOnce() -> T) -> T {
+    if !enabled() {
+        return f();
+    }
+    let started = Instant::now();
+    let result = f();
+    trace(&format!(
+        "{}: {:.1}ms",
+        label,
+        started.elapsed().as_secs_f64() * 1000.0
+    ));
+    result
+}
+
+/// Turns on per-segment timings (`--profile`). Only the first call has an
+/// effect.
+pub fn enable_profile(sink: Sink) {
+    let _ = PROFILE_SINK.set(sink);
+}
+
+/// Runs `f` and, with `--profile`, adds its duration to `segment`'s total for
+/// this render. A segment may be timed in several pieces.
+pub fn profiled<T>(segment: &'static str, f: impl FnOnce() -> T) -> T {
+    if PROFILE_SINK.get().is_none() {
+        return f();
+    }
+    let started = Instant::now();
+    let result = f();
+    let elapsed = started.elapsed();
+    TIMINGS.with(|timings| {
+        let mut timings = timings.borrow_mut();
+        match timings.iter_mut().find(|(name, _)| *name == segment) {
+            Some((_, total)) => *total += elapsed,
+            None => timings.push((segment, elapsed)),
+        }
+    });
+    result
+}
+
+/// Writes the collected timings as one line, slowest first
+/// (`profile: git 12ms, transcript 30ms, ...`), and resets them.
+pub fn flush_profile() {
+    let Some(sink) = PROFILE_SINK.get() else {
+        return;
+    };
+    let mut timings = TIMINGS.with(|timings| std::mem::take(&mut *timings.borrow_mut()));
+    if timings.is_empty() {
+        return;
+    }
+    timings.sort_by_key(|(_, d)| std::cmp::Reverse(*d));
+    let total: Duration = timings.iter().map(|(_, d)| *d).sum();
+    let parts: Vec<String> = timings
+        .iter()
+        .map(|(name, d)| format!("{} {:.1}ms", name, d.as_secs_f64() * 1000.0))
+        .collect();
+    write(
+        Some(sink),
+        &format!(
+            "profile: {} (total {:.1}ms)",
+            parts.join(", "),
+            total.as_secs_f64() * 1000.0
+        ),
+    );
+}
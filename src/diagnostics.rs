@@ -0,0 +1,66 @@
+use std::fs;
+use std::path::Path;
+
+/// Diagnostics counts published by editor/LSP integrations or lint runs.
+///
+/// Each producer writes its own `<git-dir>/statusbar/diagnostics/<source>.json`
+/// (e.g. `rust-analyzer.json`, `clippy.json`) as
+/// `{"errors": N, "warnings": N}`, replacing the file on every update. Counts
+/// from all sources are summed; a single `statusbar/diagnostics.json` is read
+/// as well for integrations that only ever have one source.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Diagnostics {
+    pub errors: u64,
+    pub warnings: u64,
+}
+
+impl Diagnostics {
+    fn add(&mut self, other: Diagnostics) {
+        self.errors += other.errors;
+        self.warnings += other.warnings;
+    }
+}
+
+fn read_file(path: &Path) -> Option<Diagnostics> {
+    let data = fs::read_to_string(path).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&data).ok()?;
+    let count = |key: &str| json.get(key).and_then(crate::as_u64_lenient).unwrap_or(0);
+    Some(Diagnostics {
+        errors: count("errors"),
+        warnings: count("warnings"),
+    })
+}
+
+/// Sums every diagnostics state file under `statusbar_dir`; `None` when no
+/// integration has published anything.
+pub fn read_diagnostics(statusbar_dir: &Path) -> Option<Diagnostics> {
+    let mut files: Vec<_> = fs::read_dir(statusbar_dir.join("diagnostics"))
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.push(statusbar_dir.join("diagnostics.json"));
+
+    let mut total: Option<Diagnostics> = None;
+    for counts in files.iter().filter_map(|p| read_file(p)) {
+        total.get_or_insert_with(Diagnostics::default).add(counts);
+    }
+    total
+}
+
+/// `E3 W12`, errors in red and warnings in yellow; zero counts are left out
+/// and a clean result renders nothing.
+pub fn format_diagnostics(diagnostics: Diagnostics) -> String {
+    let mut parts = Vec::new();
+    if diagnostics.errors > 0 {
+        parts.push(format!("\x1b[31mE{}\x1b[0m", diagnostics.errors));
+    }
+    if diagnostics.warnings > 0 {
+        parts.push(format!("\x1b[33mW{}\x1b[0m", diagnostics.warnings));
+    }
+    parts.join(" ")
+}
@@ -0,0 +1,266 @@
+use crate::config::{config_path, Config};
+use std::process::Command;
+
+/// Pricing older than this many days is reported as possibly stale.
+const PRICING_MAX_AGE_DAYS: i64 = 180;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    Warn,
+    Fail,
+    Info,
+}
+
+/// One line of the doctor report, with a fix for anything not `Ok`.
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub status: Status,
+    pub name: &'static str,
+    pub detail: String,
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn new(status: Status, name: &'static str, detail: impl Into<String>) -> Check {
+        Check {
+            status,
+            name,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn fix(mut self, fix: impl Into<String>) -> Check {
+        self.fix = Some(fix.into());
+        self
+    }
+}
+
+fn command_version(program: &str) -> Option<String> {
+    let output = Command::new(program).arg("--version").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout.lines().next().map(|l| l.trim().to_string())
+}
+
+fn check_git() -> Check {
+    match command_version("git") {
+        Some(version) => Check::new(Status::Ok, "git", version),
+        None => Check::new(Status::Fail, "git", "not found on PATH")
+            .fix("install git; branch, diff and worktree segments need it"),
+    }
+}
+
+fn check_gh() -> Check {
+    match command_version("gh") {
+        Some(version) => Check::new(Status::Ok, "gh", version),
+        None => Check::new(Status::Warn, "gh", "not found on PATH (optional)")
+            .fix("install the GitHub CLI for repository-host integrations, or ignore"),
+    }
+}
+
+fn check_glyphs() -> Check {
+    let icons = crate::Config::load().icons;
+    let glyphs: Vec<&str> = ["branch", "model", "context", "cost", "time"]
+        .iter()
+        .map(|name| icons.icon(name))
+        .collect();
+    Check::new(
+        Status::Info,
+        "glyphs",
+        format!(
+            "{}  (branch, model, context, cost, time; {} icons)",
+            glyphs.join(" "),
+            icons.set.name()
+        ),
+    )
+    .fix("if these render as boxes, switch your terminal to a Nerd Font (https://www.nerdfonts.com) or set [icons] set = \"nerd-v2\", \"emoji\" or \"ascii\"")
+}
+
+fn check_config() -> Check {
+    let Some(path) = config_path() else {
+        return Check::new(
+            Status::Warn,
+            "config",
+            "no config location could be determined",
+        );
+    };
+    let data = match std::fs::read_to_string(&path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Check::new(
+                Status::Ok,
+                "config",
+                format!("{} not present, using defaults", path.display()),
+            )
+        }
+        Err(e) => {
+            return Check::new(Status::Fail, "config", format!("{}: {}", path.display(), e))
+                .fix("check the file's permissions")
+        }
+    };
+    match toml::from_str::<Config>(&data) {
+        Ok(_) => Check::new(Status::Ok, "config", path.display().to_string()),
+        Err(e) => {
+            let line = e
+                .span()
+                .map(|span| data[..span.start].matches('\n').count() + 1)
+                .unwrap_or(0);
+            Check::new(
+                Status::Fail,
+                "config",
+                format!(
+                    "{}:{} is invalid, so the whole file is ignored: {}",
+                    path.display(),
+                    line,
+                    e.message()
+                ),
+            )
+            .fix("fix the reported line, or inspect keys with `statusline config get <key>`")
+        }
+    }
+}
+
+fn check_transcripts() -> Check {
+    let dir = crate::expand_home("~/.claude/projects");
+    match std::fs::read_dir(&dir) {
+        Ok(_) => {
+            let count =
+                crate::aggregate::transcript_files(&[crate::report::LOCAL_TRANSCRIPTS.to_string()])
+                    .len();
+            Check::new(
+                Status::Ok,
+                "transcripts",
+                format!("{} ({} transcripts)", dir.display(), count),
+            )
+        }
+        Err(e) => Check::new(
+            Status::Warn,
+            "transcripts",
+            format!("{}: {}", dir.display(), e),
+        )
+        .fix("run Claude Code once; session time, rolling cost and reports read these files"),
+    }
+}
+
+/// The day this binary was built, from the build script.
+fn build_date() -> Option<chrono::NaiveDate> {
+    let secs = env!("CC_STATUSLINE_BUILT_AT").parse::<i64>().ok()?;
+    chrono::DateTime::from_timestamp(secs, 0).map(|at| at.date_naive())
+}
+
+/// The price table is as current as the build it shipped with, so its age
+/// counts from the later of its check date and the build date; a fresh build
+/// of an unchanged table isn't flagged.
+fn check_pricing() -> Check {
+    let Ok(updated) = chrono::NaiveDate::parse_from_str(crate::models::PRICING_UPDATED, "%Y-%m-%d")
+    else {
+        return Check::new(Status::Warn, "pricing", "unknown pricing date");
+    };
+    let since = build_date().map_or(updated, |built| built.max(updated));
+    let age = (chrono::Local::now().date_naive() - since).num_days();
+    if age > PRICING_MAX_AGE_DAYS {
+        Check::new(
+            Status::Warn,
+            "pricing",
+            format!("price table from {} ({} days without an update)", updated, age),
+        )
+        .fix("update cc-statusline; until then, costs of new models may be estimated wrong or missing")
+    } else {
+        Check::new(
+            Status::Ok,
+            "pricing",
+            format!("price table from {}", updated),
+        )
+    }
+}
+
+fn check_cache() -> Check {
+    let dir = crate::cache::cache_dir();
+    match crate::cache::write_cache("doctor", "ok") {
+        Ok(()) => {
+            let _ = std::fs::remove_file(dir.join("doctor"));
+            Check::new(Status::Ok, "cache", dir.display().to_string())
+        }
+        Err(e) => Check::new(Status::Fail, "cache", format!("{}: {}", dir.display(), e))
+            .fix("make the directory writable or set XDG_CACHE_HOME"),
+    }
+}
+
+fn check_settings() -> Check {
+    let path = crate::install::settings_path(false);
+    let command = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|data| serde_json::from_str::<serde_json::Value>(&data).ok())
+        .and_then(|settings| {
+            settings
+                .get("statusLine")
+                .and_then(|s| s.get("command"))
+                .and_then(|c| c.as_str())
+                .map(str::to_string)
+        });
+    match command {
+        Some(command) => {
+            let program = command.split_whitespace().next().unwrap_or_default();
+            if crate::expand_home(program).exists() || command_version(program).is_some() {
+                Check::new(
+                    Status::Ok,
+                    "settings",
+                    format!("statusLine runs `{}`", command),
+                )
+            } else {
+                Check::new(
+                    Status::Fail,
+                    "settings",
+                    format!("statusLine runs `{}`, which does not exist", command),
+                )
+                .fix("run `statusline install` to point it at this binary")
+            }
+        }
+        None => Check::new(
+            Status::Warn,
+            "settings",
+            format!("no statusLine command in {}", path.display()),
+        )
+        .fix("run `statusline install`"),
+    }
+}
+
+pub fn run_checks() -> Vec<Check> {
+    vec![
+        check_git(),
+        check_gh(),
+        check_config(),
+        check_settings(),
+        check_transcripts(),
+        check_cache(),
+        check_pricing(),
+        check_glyphs(),
+    ]
+}
+
+/// `statusline doctor`: exits 1 when any check fails.
+pub fn run_cli() -> i32 {
+    let checks = run_checks();
+    for check in &checks {
+        let mark = match check.status {
+            Status::Ok => "\x1b[32m\u{2713}\x1b[0m",
+            Status::Warn => "\x1b[33m!\x1b[0m",
+            Status::Fail => "\x1b[31m\u{2717}\x1b[0m",
+            Status::Info => "\x1b[36mi\x1b[0m",
+        };
+        println!("{} {:<12} {}", mark, check.name, check.detail);
+        if let Some(fix) = &check.fix {
+            println!("  {:<12} \x1b[90m\u{2192} {}\x1b[0m", "", fix);
+        }
+    }
+
+    if checks.iter().any(|c| c.status == Status::Fail) {
+        1
+    } else {
+        0
+    }
+}
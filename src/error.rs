@@ -0,0 +1,106 @@
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+/// Why a render could not use its input or a segment could not be built.
+#[derive(Debug)]
+pub enum StatuslineError {
+    /// Reading stdin failed.
+    Stdin(std::io::Error),
+    /// The `--input` file or FIFO could not be read.
+    InputFile(std::path::PathBuf, std::io::Error),
+    /// Stdin was empty (e.g. run by hand in a terminal).
+    EmptyInput,
+    /// Stdin was not valid JSON.
+    Json(serde_json::Error),
+    /// A segment panicked; the rest of the line is still rendered.
+    Segment { name: String, message: String },
+    /// The layout script failed to load, compile or run; the default line is
+    /// used.
+    Script(String),
+    /// `layout.template` could not be parsed; the default line is used.
+    Template(String),
+    /// The config file is not valid TOML or doesn't fit the schema; the
+    /// defaults are used.
+    Config(String),
+}
+
+impl StatuslineError {
+    /// Short dim-red marker shown in place of what failed.
+    pub fn placeholder(&self) -> String {
+        let what = match self {
+            StatuslineError::Stdin(_) | StatuslineError::EmptyInput => "stdin",
+            StatuslineError::InputFile(..) => "input",
+            StatuslineError::Json(_) => "json",
+            StatuslineError::Segment { name, .. } => name,
+            StatuslineError::Script(_) => "script",
+            StatuslineError::Template(_) => "template",
+            StatuslineError::Config(_) => "config",
+        };
+        format!("\x1b[2;31m\u{26a0} {}\x1b[0m", what)
+    }
+}
+
+impl fmt::Display for StatuslineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StatuslineError::Stdin(e) => write!(f, "cannot read stdin: {}", e),
+            StatuslineError::InputFile(path, e) => {
+                write!(f, "cannot read {}: {}", path.display(), e)
+            }
+            StatuslineError::EmptyInput => write!(f, "no input on stdin"),
+            StatuslineError::Json(e) => write!(f, "invalid JSON on stdin: {}", e),
+            StatuslineError::Segment { name, message } => {
+                write!(f, "segment {} failed: {}", name, message)
+            }
+            StatuslineError::Script(message) => write!(f, "script failed: {}", message),
+            StatuslineError::Template(message) => write!(f, "invalid template: {}", message),
+            StatuslineError::Config(message) => write!(f, "invalid config: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for StatuslineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StatuslineError::Stdin(e) | StatuslineError::InputFile(_, e) => Some(e),
+            StatuslineError::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for StatuslineError {
+    fn from(e: std::io::Error) -> Self {
+        StatuslineError::Stdin(e)
+    }
+}
+
+impl From<serde_json::Error> for StatuslineError {
+    fn from(e: serde_json::Error) -> Self {
+        StatuslineError::Json(e)
+    }
+}
+
+/// Runs one segment's code, turning a panic into [`StatuslineError::Segment`]
+/// (logged with `--debug`) so a single broken segment can't take down the
+/// whole line.
+pub fn guard<T>(name: &str, f: impl FnOnce() -> T) -> Result<T, StatuslineError> {
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "panic".to_string());
+        let error = StatuslineError::Segment {
+            name: name.to_string(),
+            message,
+        };
+        crate::debug::trace(&error.to_string());
+        error
+    })
+}
+
+/// The segment's output, or its placeholder if it panicked.
+pub fn guarded(name: &str, f: impl FnOnce() -> String) -> String {
+    guard(name, f).unwrap_or_else(|e| e.placeholder())
+}
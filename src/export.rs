@@ -0,0 +1,288 @@
+use crate::alerts::AlertMetrics;
+use serde::{Deserialize, Serialize};
+use std::net::UdpSocket;
+use std::time::Duration;
+
+/// `[export]` config section: pushes each render's metrics to a StatsD
+/// daemon and/or an OpenTelemetry collector for org-wide dashboards.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportConfig {
+    /// `host:port` of a StatsD daemon (UDP), e.g. `127.0.0.1:8125`.
+    pub statsd: Option<String>,
+    /// Append DogStatsD-style `|#key:value` tags to StatsD lines.
+    pub statsd_tags: bool,
+    /// OTLP/HTTP metrics endpoint, e.g. `http://localhost:4318/v1/metrics`.
+    pub otlp_endpoint: Option<String>,
+    /// Metric name prefix (`claude.cost_usd`).
+    pub prefix: String,
+    /// Minimum seconds between exports of the same session, so frequent
+    /// redraws don't flood the endpoint.
+    pub interval_secs: i64,
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        ExportConfig {
+            statsd: None,
+            statsd_tags: false,
+            otlp_endpoint: None,
+            prefix: "claude".to_string(),
+            interval_secs: 10,
+        }
+    }
+}
+
+impl ExportConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.statsd.is_some() || self.otlp_endpoint.is_some()
+    }
+}
+
+/// Labels attached to every exported metric (`session_id`, `model`, ...).
+pub type ExportAttributes<'a> = Vec<(&'static str, &'a str)>;
+
+/// Sends `metrics` to the configured endpoints, at most once per
+/// `interval_secs` per session. Failures are ignored.
+pub fn export(
+    config: &ExportConfig,
+    metrics: &AlertMetrics,
+    attributes: &ExportAttributes,
+    session_id: &str,
+) {
+    if !config.is_enabled() {
+        return;
+    }
+
+    let state_name = format!("export-{}", crate::cache::cache_key(session_id));
+    let now = chrono::Utc::now().timestamp();
+    let last = crate::cache::read_cache(&state_name)
+        .and_then(|data| data.trim().parse::<i64>().ok())
+        .unwrap_or(0);
+    if now - last < config.interval_secs {
+        return;
+    }
+    let _ = crate::cache::write_cache(&state_name, &now.to_string());
+
+    if let Some(addr) = &config.statsd {
+        send_statsd(addr, &statsd_lines(config, metrics, attributes));
+    }
+    if let Some(endpoint) = &config.otlp_endpoint {
+        let payload = otlp_payload(config, metrics, attributes);
+        crate::post::in_background(endpoint, &payload, Duration::from_millis(500));
+    }
+}
+
+/// One gauge per metric: `claude.cost_usd:7.5|g`, plus `|#model:Opus,...`
+/// with `statsd_tags`.
+pub fn statsd_lines(
+    config: &ExportConfig,
+    metrics: &AlertMetrics,
+    attributes: &ExportAttributes,
+) -> Vec<String> {
+    let tags = if config.statsd_tags && !attributes.is_empty() {
+        let tags: Vec<String> = attributes
+            .iter()
+            .map(|(key, value)| format!("{}:{}", key, value.replace([',', '|', ':'], "_")))
+            .collect();
+        format!("|#{}", tags.join(","))
+    } else {
+        String::new()
+    };
+
+    let mut names: Vec<&&str> = metrics.keys().collect();
+    names.sort();
+    names
+        .into_iter()
+        .map(|name| format!("{}.{}:{}|g{}", config.prefix, name, metrics[*name], tags))
+        .collect()
+}
+
+fn send_statsd(addr: &str, lines: &[String]) {
+    let Ok(socket) = UdpSocket::bind("0.0.0.0:0") else {
+        return;
+    };
+    // One datagram per metric keeps each under any MTU.
+    for line in lines {
+        let _ = socket.send_to(line.as_bytes(), addr);
+    }
+}
+
+/// An OTLP/JSON `ExportMetricsServiceRequest` with one gauge per metric.
+pub fn otlp_payload(
+    config: &ExportConfig,
+    metrics: &AlertMetrics,
+    attributes: &ExportAttributes,
+) -> serde_json::Value {
+    let now_ns = chrono::Utc::now()
+        .timestamp_nanos_opt()
+        .unwrap_or_default()
+        .to_string();
+    let attributes: Vec<serde_json::Value> = attributes
+        .iter()
+        .map(|(key, value)| serde_json::json!({"key": key, "value": {"stringValue": value}}))
+        .collect();
+
+    let mut names: Vec<&&str> = metrics.keys().collect();
+    names.sort();
+    let gauges: Vec<serde_json::Value> = names
+        .into_iter()
+        .map(|name| {
+            serde_json::json!({
+                "name": format!("{}.{}", config.prefix, name),
+                "gauge": {"dataPoints": [{
+                    "asDouble": metrics[*name],
+                    "timeUnixNano": now_ns,
+                    "attributes": attributes,
+                }]},
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "resourceMetrics": [{
+            "resource": {"attributes": [
+                {"key": "service.name", "value": {"stringValue": "cc-statusline"}}
+            ]},
+            "scopeMetrics": [{
+                "scope": {"name": "cc-statusline", "version": env!("CARGO_PKG_VERSION")},
+                "metrics": gauges,
+            }],
+        }]
+    })
+}
+
+/// Prometheus names and help text for the render metrics. `cost_usd` is
+/// renamed so it reads unambiguously next to `daily_cost_usd` on a
+/// dashboard; anything not listed keeps its own name.
+const PROMETHEUS_METRICS: &[(&str, &str, &str)] = &[
+    (
+        "context_pct",
+        "context_pct",
+        "Context window used, in percent.",
+    ),
+    (
+        "cost_usd",
+        "session_cost_usd",
+        "Session cost in US dollars.",
+    ),
+    (
+        "daily_cost_usd",
+        "daily_cost_usd",
+        "Cost of all sessions today in US dollars.",
+    ),
+    (
+        "tokens_total",
+        "tokens_total",
+        "Input plus output tokens of the session.",
+    ),
+    (
+        "input_tokens",
+        "input_tokens",
+        "Input tokens of the session.",
+    ),
+    (
+        "output_tokens",
+        "output_tokens",
+        "Output tokens of the session.",
+    ),
+    (
+        "duration_ms",
+        "session_duration_ms",
+        "Session duration in milliseconds.",
+    ),
+    (
+        "lines_added",
+        "lines_added",
+        "Lines added during the session.",
+    ),
+    (
+        "lines_removed",
+        "lines_removed",
+        "Lines removed during the session.",
+    ),
+    (
+        "git_dirty_files",
+        "git_dirty_files",
+        "Changed and untracked files in the checkout.",
+    ),
+    (
+        "shared_cost_today",
+        "shared_cost_today_usd",
+        "Cost of all users' sessions today in US dollars.",
+    ),
+    ("over_budget", "over_budget", "1 when a budget is exceeded."),
+    (
+        "build_failing",
+        "build_failing",
+        "1 when the latest CI build failed.",
+    ),
+];
+
+/// The metrics in Prometheus text exposition format, one gauge per metric
+/// labelled with `attributes`, for a node_exporter textfile collector:
+///
+/// ```text
+/// # HELP claude_context_pct Context window used, in percent.
+/// # TYPE claude_context_pct gauge
+/// claude_context_pct{model="claude-opus-4-1",branch="main"} 42
+/// ```
+pub fn prometheus_exposition(
+    config: &ExportConfig,
+    metrics: &AlertMetrics,
+    attributes: &ExportAttributes,
+) -> String {
+    let labels = if attributes.is_empty() {
+        String::new()
+    } else {
+        let labels: Vec<String> = attributes
+            .iter()
+            .map(|(key, value)| format!("{}=\"{}\"", key, escape_label(value)))
+            .collect();
+        format!("{{{}}}", labels.join(","))
+    };
+
+    let mut gauges: Vec<(String, &str, f64)> = metrics
+        .iter()
+        .map(|(metric, value)| {
+            let (name, help) = PROMETHEUS_METRICS
+                .iter()
+                .find(|(key, _, _)| key == metric)
+                .map(|(_, name, help)| (*name, *help))
+                .unwrap_or((metric, ""));
+            let name = prometheus_name(&format!("{}_{}", config.prefix, name));
+            (name, help, *value)
+        })
+        .collect();
+    gauges.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut out = String::new();
+    for (name, help, value) in gauges {
+        if !help.is_empty() {
+            out.push_str(&format!("# HELP {} {}\n", name, help));
+        }
+        out.push_str(&format!("# TYPE {} gauge\n", name));
+        out.push_str(&format!("{}{} {}\n", name, labels, value));
+    }
+    out
+}
+
+/// Replaces characters Prometheus doesn't allow in metric names (`.`, `-`)
+/// with `_`.
+fn prometheus_name(name: &str) -> String {
+    name.chars()
+        .enumerate()
+        .map(|(i, c)| match c {
+            'a'..='z' | 'A'..='Z' | '_' | ':' => c,
+            '0'..='9' if i > 0 => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,77 @@
+/// A Claude Code version as reported in the input's `version` field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ClaudeVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl ClaudeVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        ClaudeVersion {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Parses `1.0.80`, `2.0.14 (Claude Code)` and similar.
+    pub fn parse(version: &str) -> Option<ClaudeVersion> {
+        let core = version.split_whitespace().next()?;
+        let core = core.split(['-', '+']).next()?;
+        let mut parts = core.split('.').map(|p| p.parse::<u32>().ok());
+        Some(ClaudeVersion {
+            major: parts.next()??,
+            minor: parts.next().flatten().unwrap_or(0),
+            patch: parts.next().flatten().unwrap_or(0),
+        })
+    }
+}
+
+/// First versions whose statusline input carries each field.
+const OUTPUT_STYLE_SINCE: ClaudeVersion = ClaudeVersion::new(1, 0, 80);
+const COST_DURATION_SINCE: ClaudeVersion = ClaudeVersion::new(1, 0, 85);
+const CONTEXT_WINDOW_SINCE: ClaudeVersion = ClaudeVersion::new(2, 0, 0);
+
+/// Which fields the running Claude Code is expected to send, so the renderer
+/// can read them directly instead of reconstructing them from the transcript.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FeatureLevel {
+    pub version: Option<ClaudeVersion>,
+    /// `output_style.name`.
+    pub output_style: bool,
+    /// `cost.total_duration_ms`, which makes the transcript scan for the
+    /// session duration unnecessary.
+    pub cost_duration: bool,
+    /// `context_window` with `current_usage`.
+    pub context_window: bool,
+}
+
+/// Detects the feature level from `version`, falling back to the presence of
+/// the fields themselves when the version is missing or unparseable.
+pub fn detect(input: &serde_json::Value) -> FeatureLevel {
+    let version = input
+        .get("version")
+        .and_then(|v| v.as_str())
+        .and_then(ClaudeVersion::parse);
+
+    let has_duration = input
+        .get("cost")
+        .and_then(|c| c.get("total_duration_ms"))
+        .is_some();
+
+    match version {
+        Some(v) => FeatureLevel {
+            version,
+            output_style: v >= OUTPUT_STYLE_SINCE,
+            cost_duration: v >= COST_DURATION_SINCE && has_duration,
+            context_window: v >= CONTEXT_WINDOW_SINCE,
+        },
+        None => FeatureLevel {
+            version: None,
+            output_style: input.get("output_style").is_some(),
+            cost_duration: has_duration,
+            context_window: input.get("context_window").is_some(),
+        },
+    }
+}
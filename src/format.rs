@@ -0,0 +1,216 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Fetched rates are reused this long; a failed fetch is retried after
+/// [`RATE_RETRY_SECS`].
+const RATE_RETRY_SECS: i64 = 3600;
+
+/// `[format]` config section: how numbers and money are written.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FormatConfig {
+    /// ISO 4217 code to show costs in, e.g. `EUR`. Costs stay in US dollars
+    /// when unset.
+    pub currency: Option<String>,
+    /// Overrides the symbol derived from `currency` (`€`, `£`, ...).
+    pub currency_symbol: Option<String>,
+    /// Write the symbol after the amount (`12,30 €`).
+    pub symbol_after: bool,
+    /// US dollars to `currency`. Fetched from the European Central Bank's
+    /// reference rates (via frankfurter.app) when unset.
+    pub rate: Option<f64>,
+    /// How long a fetched rate is reused.
+    pub rate_refresh_secs: i64,
+    pub decimal_separator: String,
+    /// Between groups of three digits in amounts, e.g. `" "` or `"."`.
+    pub thousands_separator: String,
+}
+
+impl Default for FormatConfig {
+    fn default() -> Self {
+        FormatConfig {
+            currency: None,
+            currency_symbol: None,
+            symbol_after: false,
+            rate: None,
+            rate_refresh_secs: 24 * 60 * 60,
+            decimal_separator: ".".to_string(),
+            thousands_separator: String::new(),
+        }
+    }
+}
+
+/// A currency costs are converted to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Currency {
+    pub symbol: String,
+    pub after: bool,
+    /// US dollars to this currency.
+    pub rate: f64,
+}
+
+/// Number formatting for one render. The default writes US-style numbers
+/// and leaves costs in dollars.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumberFormat {
+    pub decimal_separator: String,
+    pub thousands_separator: String,
+    /// `None` keeps US dollars, shown with the `cost` icon.
+    pub currency: Option<Currency>,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        NumberFormat {
+            decimal_separator: ".".to_string(),
+            thousands_separator: String::new(),
+            currency: None,
+        }
+    }
+}
+
+impl NumberFormat {
+    /// `rate` is the exchange rate to use when `config.rate` is unset. Without
+    /// either, costs stay in US dollars rather than being mislabeled.
+    pub fn new(config: &FormatConfig, rate: Option<f64>) -> NumberFormat {
+        let currency = config.currency.as_deref().and_then(|code| {
+            let rate = if code.eq_ignore_ascii_case("USD") {
+                1.0
+            } else {
+                config.rate.or(rate)?
+            };
+            Some(Currency {
+                symbol: config
+                    .currency_symbol
+                    .clone()
+                    .unwrap_or_else(|| currency_symbol(code)),
+                after: config.symbol_after,
+                rate,
+            })
+        });
+        NumberFormat {
+            decimal_separator: config.decimal_separator.clone(),
+            thousands_separator: config.thousands_separator.clone(),
+            currency,
+        }
+    }
+
+    /// Rewrites a number formatted the US way (`1234.56`) with these
+    /// separators (`1 234,56`).
+    pub fn localize(&self, number: &str) -> String {
+        let (sign, number) = match number.strip_prefix('-') {
+            Some(rest) => ("-", rest),
+            None => ("", number),
+        };
+        let (integer, fraction) = match number.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (number, None),
+        };
+
+        let mut out = sign.to_string();
+        // Token counts such as `412k` are left alone.
+        let grouped = integer.bytes().all(|b| b.is_ascii_digit());
+        let digits = integer.len();
+        for (i, c) in integer.chars().enumerate() {
+            if grouped && i > 0 && (digits - i) % 3 == 0 {
+                out.push_str(&self.thousands_separator);
+            }
+            out.push(c);
+        }
+        if let Some(fraction) = fraction {
+            out.push_str(&self.decimal_separator);
+            out.push_str(fraction);
+        }
+        out
+    }
+
+    /// A cost given in US dollars, converted and without a symbol: `12,30`.
+    pub fn amount(&self, usd: f64) -> String {
+        let rate = self.currency.as_ref().map_or(1.0, |c| c.rate);
+        self.localize(&crate::format_cost(usd * rate))
+    }
+
+    /// A cost with its currency symbol: `$12.30`, `€12,30` or `12,30 €`.
+    pub fn money(&self, usd: f64) -> String {
+        match &self.currency {
+            Some(currency) if currency.after => format!("{} {}", self.amount(usd), currency.symbol),
+            Some(currency) => format!("{}{}", currency.symbol, self.amount(usd)),
+            None => format!("${}", self.amount(usd)),
+        }
+    }
+
+    /// [`crate::format_tokens`] with this decimal separator: `1,2M`.
+    pub fn tokens(&self, tokens: u64) -> String {
+        self.localize(&crate::format_tokens(tokens))
+    }
+}
+
+/// The usual symbol for an ISO 4217 code, else the code itself.
+pub fn currency_symbol(code: &str) -> String {
+    match code.to_ascii_uppercase().as_str() {
+        "USD" => "$",
+        "EUR" => "€",
+        "GBP" => "£",
+        "JPY" | "CNY" => "¥",
+        "INR" => "₹",
+        "KRW" => "₩",
+        "BRL" => "R$",
+        "CAD" => "CA$",
+        "AUD" => "A$",
+        "PLN" => "zł",
+        "SEK" | "NOK" | "DKK" => "kr",
+        other => return other.to_string(),
+    }
+    .to_string()
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedRate {
+    at: i64,
+    rate: Option<f64>,
+}
+
+/// US dollars to `config.currency` from frankfurter.app, cached for
+/// `rate_refresh_secs`. `None` without a currency or when the rate can't be
+/// fetched.
+pub fn exchange_rate(config: &FormatConfig) -> Option<f64> {
+    let code = config.currency.as_deref()?.to_ascii_uppercase();
+    if config.rate.is_some() || code == "USD" {
+        return config.rate;
+    }
+
+    let name = format!("exchange-rate-{}", crate::cache::cache_key(&code));
+    let now = chrono::Utc::now().timestamp();
+    if let Some(cached) = crate::cache::read_cache(&name)
+        .and_then(|data| serde_json::from_str::<CachedRate>(&data).ok())
+    {
+        let max_age = if cached.rate.is_some() {
+            config.rate_refresh_secs
+        } else {
+            RATE_RETRY_SECS
+        };
+        if now - cached.at < max_age {
+            return cached.rate;
+        }
+    }
+
+    let rate = fetch_rate(&code);
+    if let Ok(data) = serde_json::to_string(&CachedRate { at: now, rate }) {
+        let _ = crate::cache::write_cache(&name, &data);
+    }
+    rate
+}
+
+fn fetch_rate(code: &str) -> Option<f64> {
+    let url = format!("https://api.frankfurter.app/latest?from=USD&to={}", code);
+    let response: serde_json::Value = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_millis(1000))
+        .build()
+        .ok()?
+        .get(url)
+        .send()
+        .ok()?
+        .json()
+        .ok()?;
+    response.get("rates")?.get(code)?.as_f64()
+}
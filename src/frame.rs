@@ -0,0 +1,113 @@
+use crate::width::char_width;
+
+/// One terminal column of a rendered line: the SGR sequences in effect and the
+/// character drawn there. The trailing half of a wide character is `None`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Cell {
+    style: String,
+    ch: Option<char>,
+}
+
+fn cells(line: &str) -> Vec<Cell> {
+    let mut out = Vec::new();
+    let mut style = String::new();
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == '\x1b' && chars.peek() == Some(&'[') {
+            let mut seq = String::from("\x1b");
+            for c in chars.by_ref() {
+                seq.push(c);
+                if ('\x40'..='\x7e').contains(&c) && c != '[' {
+                    break;
+                }
+            }
+            if seq == "\x1b[0m" || seq == "\x1b[m" {
+                style.clear();
+            } else if seq.ends_with('m') {
+                style.push_str(&seq);
+            }
+            continue;
+        }
+
+        let w = char_width(c);
+        if w == 0 {
+            continue;
+        }
+        out.push(Cell {
+            style: style.clone(),
+            ch: Some(c),
+        });
+        for _ in 1..w {
+            out.push(Cell {
+                style: style.clone(),
+                ch: None,
+            });
+        }
+    }
+
+    out
+}
+
+/// Produces the escape sequence that turns the already-displayed `prev` line
+/// into `next` by repositioning the cursor (`CSI n G`) and redrawing only the
+/// changed runs of cells, then erasing anything left past the new end.
+/// Returns an empty string when nothing changed.
+pub fn diff_frames(prev: &str, next: &str) -> String {
+    let old = cells(prev);
+    let new = cells(next);
+    let mut out = String::new();
+    let mut col = 0;
+
+    while col < new.len() {
+        if old.get(col) == Some(&new[col]) {
+            col += 1;
+            continue;
+        }
+
+        // Start the run on the leading half of a wide character.
+        let start = if new[col].ch.is_none() && col > 0 {
+            col - 1
+        } else {
+            col
+        };
+        let mut end = col + 1;
+        while end < new.len() && (old.get(end) != Some(&new[end]) || new[end].ch.is_none()) {
+            end += 1;
+        }
+
+        out.push_str(&format!("\x1b[{}G", start + 1));
+        let mut style: Option<&str> = None;
+        for cell in &new[start..end] {
+            if let Some(ch) = cell.ch {
+                if style != Some(cell.style.as_str()) {
+                    out.push_str("\x1b[0m");
+                    out.push_str(&cell.style);
+                    style = Some(&cell.style);
+                }
+                out.push(ch);
+            }
+        }
+        out.push_str("\x1b[0m");
+        col = end;
+    }
+
+    if new.len() < old.len() {
+        out.push_str(&format!("\x1b[{}G\x1b[K", new.len() + 1));
+    }
+
+    out
+}
+
+/// Frame-diff output for `session_id`: the full line the first time, then
+/// only the changes relative to the previously emitted frame.
+pub fn render_frame(session_id: &str, line: &str) -> String {
+    let name = format!("frame-{}", crate::cache::cache_key(session_id));
+    let prev = crate::cache::read_cache(&name);
+    let _ = crate::cache::write_cache(&name, line);
+
+    match prev {
+        Some(prev) => diff_frames(&prev, line),
+        None => line.to_string(),
+    }
+}
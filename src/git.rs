@@ -0,0 +1,721 @@
+use crate::remote_workspace::RemoteWorkspaceConfig;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// `[git]` config section.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GitConfig {
+    /// Show how far the branch has drifted from the default branch
+    /// (`main▲14▼3`).
+    pub divergence: bool,
+    /// The branch to compare against, e.g. `origin/develop`. Detected from
+    /// the origin's `HEAD`, then `main`/`master`, when unset.
+    pub default_branch: Option<String>,
+    /// How long a detected default branch is reused before detecting again.
+    pub default_branch_refresh_secs: i64,
+    /// Show the short `HEAD` SHA and the age of the last commit
+    /// (`a1b2c3d 3h ago`).
+    pub commit: bool,
+    /// Show staged, modified and untracked file counts (`+2 ~3 ?1`).
+    pub status: bool,
+    /// Index entries above which untracked files are no longer scanned.
+    pub status_large_repo_files: u64,
+    /// How long `git status` may take before the repository drops to a
+    /// cheaper mode for the next `status_mode_refresh_secs`.
+    pub status_timeout_ms: u64,
+    pub status_mode_refresh_secs: i64,
+    /// How long any other git command may run before it is killed. After
+    /// one timeout, the render's remaining git commands are skipped, so a
+    /// repository on a hung network mount can't freeze the prompt.
+    pub timeout_ms: u64,
+}
+
+impl Default for GitConfig {
+    fn default() -> Self {
+        GitConfig {
+            divergence: false,
+            default_branch: None,
+            default_branch_refresh_secs: 3600,
+            commit: false,
+            status: false,
+            status_large_repo_files: 100_000,
+            status_timeout_ms: 300,
+            status_mode_refresh_secs: 3600,
+            timeout_ms: 1000,
+        }
+    }
+}
+
+/// The git settings of one render, and whether one of its git commands has
+/// timed out. Every render builds its own, so renders running side by side
+/// in one process don't trip over each other's timeouts.
+#[derive(Debug)]
+pub struct GitContext {
+    /// `[git] timeout_ms`.
+    timeout: Duration,
+    remote_workspace: RemoteWorkspaceConfig,
+    /// Set once a git command has been killed; the ones after it are skipped.
+    timed_out: AtomicBool,
+}
+
+impl GitContext {
+    /// Applies `[git] timeout_ms` and `[remote_workspace]` to the git
+    /// commands run with this context.
+    pub fn new(config: &GitConfig, remote_workspace: &RemoteWorkspaceConfig) -> Self {
+        GitContext {
+            timeout: Duration::from_millis(config.timeout_ms),
+            remote_workspace: remote_workspace.clone(),
+            timed_out: AtomicBool::new(false),
+        }
+    }
+
+    /// The remote workspace config when git for `dir` is delegated to it.
+    fn delegate(&self, dir: &Path) -> Option<&RemoteWorkspaceConfig> {
+        Some(&self.remote_workspace).filter(|remote| remote.applies_to(dir))
+    }
+}
+
+impl Default for GitContext {
+    fn default() -> Self {
+        GitContext::new(&GitConfig::default(), &RemoteWorkspaceConfig::default())
+    }
+}
+
+/// Runs an in-process query, unless git for `dir` is delegated to a remote
+/// machine, where the local path means nothing.
+#[cfg(feature = "native-git")]
+fn native<T>(dir: &Path, git: &GitContext, query: impl FnOnce(&Path) -> Option<T>) -> Option<T> {
+    match git.delegate(dir) {
+        Some(_) => None,
+        None => query(dir),
+    }
+}
+
+/// Variables that would point git at a repository other than the one in
+/// the working directory, e.g. when inherited from a hook.
+const REPOSITORY_ENV: &[&str] = &[
+    "GIT_DIR",
+    "GIT_WORK_TREE",
+    "GIT_COMMON_DIR",
+    "GIT_INDEX_FILE",
+    "GIT_OBJECT_DIRECTORY",
+    "GIT_ALTERNATE_OBJECT_DIRECTORIES",
+    "GIT_NAMESPACE",
+];
+
+/// `git` with an environment safe for a read-only query: no repository
+/// overrides, no optional locks that would contend with the user's own git
+/// commands, and no credential prompts.
+pub(crate) fn command() -> Command {
+    let mut command = Command::new("git");
+    for name in REPOSITORY_ENV {
+        command.env_remove(name);
+    }
+    command
+        .env("GIT_OPTIONAL_LOCKS", "0")
+        .env("GIT_TERMINAL_PROMPT", "0");
+    command
+}
+
+/// `git <args>` for `working_dir` and how long it may take: run locally
+/// with `timeout`, or through `[remote_workspace] command`.
+fn invocation(
+    working_dir: &Path,
+    args: &[&str],
+    timeout: Duration,
+    git: &GitContext,
+) -> (Command, Duration) {
+    match git.delegate(working_dir) {
+        Some(remote) => (
+            remote.git_command(working_dir, args),
+            Duration::from_millis(remote.timeout_ms),
+        ),
+        None => {
+            let mut command = command();
+            command.args(args).current_dir(working_dir);
+            (command, timeout)
+        }
+    }
+}
+
+/// Stdout of a successful `git <args>` in `working_dir`, killed after
+/// `[git] timeout_ms`.
+fn run(working_dir: impl AsRef<Path>, args: &[&str], git: &GitContext) -> Option<Vec<u8>> {
+    if git.timed_out.load(Ordering::Relaxed) {
+        return None;
+    }
+    let (mut command, timeout) = invocation(working_dir.as_ref(), args, git.timeout, git);
+    match crate::process::output_within(&mut command, timeout) {
+        Ok(output) => output,
+        Err(()) => {
+            git.timed_out.store(true, Ordering::Relaxed);
+            crate::debug::trace(&format!(
+                "git {}: timed out after {:?}",
+                args.join(" "),
+                timeout
+            ));
+            None
+        }
+    }
+}
+
+/// Current branch of the repository containing `working_dir` (`HEAD` when
+/// detached), or `None` outside a repository.
+pub fn git_branch(working_dir: impl AsRef<Path>, git: &GitContext) -> Option<String> {
+    #[cfg(feature = "native-git")]
+    if let Some(branch) = native(working_dir.as_ref(), git, crate::native_git::branch) {
+        return Some(branch);
+    }
+
+    git_output(working_dir, &["rev-parse", "--abbrev-ref", "HEAD"], git)
+}
+
+/// String form of [`git_branch`]: empty outside a repository.
+pub fn get_git_branch(working_dir: &str, git: &GitContext) -> String {
+    git_branch(working_dir, git).unwrap_or_default()
+}
+
+pub fn get_git_toplevel(working_dir: impl AsRef<Path>, git: &GitContext) -> Option<PathBuf> {
+    #[cfg(feature = "native-git")]
+    if let Some(toplevel) = native(working_dir.as_ref(), git, crate::native_git::toplevel) {
+        return Some(toplevel);
+    }
+
+    run(working_dir, &["rev-parse", "--show-toplevel"], git).map(|out| path_from_stdout(&out))
+}
+
+/// Trimmed stdout of a successful, non-empty `git <args>`.
+fn git_output(working_dir: impl AsRef<Path>, args: &[&str], git: &GitContext) -> Option<String> {
+    let out = run(working_dir, args, git)?;
+    Some(String::from_utf8_lossy(&out).trim().to_string()).filter(|s| !s.is_empty())
+}
+
+/// URL of `remote` as git would use it, after `insteadOf` rewrites.
+pub fn remote_url(working_dir: impl AsRef<Path>, remote: &str, git: &GitContext) -> Option<String> {
+    git_output(working_dir, &["remote", "get-url", remote], git)
+}
+
+/// The repository's default branch: `origin`'s, else a local `main` or
+/// `master`.
+pub fn detect_default_branch(working_dir: impl AsRef<Path>, git: &GitContext) -> Option<String> {
+    let working_dir = working_dir.as_ref();
+    remote_default_branch(working_dir, "origin", git).or_else(|| {
+        ["main", "master"]
+            .iter()
+            .find(|name| {
+                git_output(
+                    working_dir,
+                    &[
+                        "rev-parse",
+                        "--verify",
+                        "--quiet",
+                        &format!("refs/heads/{}", name),
+                    ],
+                    git,
+                )
+                .is_some()
+            })
+            .map(|name| name.to_string())
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedDefaultBranch {
+    at: i64,
+    branch: Option<String>,
+}
+
+/// [`detect_default_branch`] for the repository containing `working_dir`,
+/// cached for `refresh_secs`. The cache is keyed by the common dir, so all
+/// worktrees of a repository share it.
+pub fn default_branch(
+    working_dir: &Path,
+    dirs: &GitDirs,
+    refresh_secs: i64,
+    git: &GitContext,
+) -> Option<String> {
+    let name = format!(
+        "default-branch-{}",
+        crate::cache::cache_key(&dirs.common_dir.to_string_lossy())
+    );
+    let now = chrono::Utc::now().timestamp();
+    if let Some(cached) = crate::cache::read_cache(&name)
+        .and_then(|data| serde_json::from_str::<CachedDefaultBranch>(&data).ok())
+    {
+        if now - cached.at < refresh_secs {
+            return cached.branch;
+        }
+    }
+    let branch = detect_default_branch(working_dir, git);
+    if let Ok(data) = serde_json::to_string(&CachedDefaultBranch {
+        at: now,
+        branch: branch.clone(),
+    }) {
+        let _ = crate::cache::write_cache(&name, &data);
+    }
+    branch
+}
+
+/// How far `HEAD` has drifted from the default branch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// The default branch without its remote, `main`.
+    pub base: String,
+    /// Commits on `HEAD` missing from the default branch.
+    pub ahead: u64,
+    /// Commits on the default branch missing from `HEAD`.
+    pub behind: u64,
+}
+
+/// `None` when `branch` is the default branch itself.
+pub fn divergence(
+    working_dir: impl AsRef<Path>,
+    branch: &str,
+    default_branch: &str,
+    git: &GitContext,
+) -> Option<Divergence> {
+    let base = default_branch
+        .split_once('/')
+        .map(|(_, name)| name)
+        .unwrap_or(default_branch);
+    if branch == base || branch == default_branch {
+        return None;
+    }
+    let (ahead, behind) = ahead_behind(working_dir, "HEAD", default_branch, git)?;
+    Some(Divergence {
+        base: base.to_string(),
+        ahead,
+        behind,
+    })
+}
+
+/// `main▲14▼3`, in yellow rather than gray when `changed`. Empty when the
+/// branches match.
+pub fn format_divergence(divergence: &Divergence, changed: bool) -> String {
+    let mut counts = String::new();
+    if divergence.ahead > 0 {
+        counts.push_str(&format!("\u{25b2}{}", divergence.ahead));
+    }
+    if divergence.behind > 0 {
+        counts.push_str(&format!("\u{25bc}{}", divergence.behind));
+    }
+    if counts.is_empty() {
+        return String::new();
+    }
+    let color = if changed { "\x1b[33m" } else { "\x1b[90m" };
+    format!("\x1b[90m{}{}{}\x1b[0m", divergence.base, color, counts)
+}
+
+/// The commit `HEAD` points at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeadCommit {
+    /// Abbreviated SHA, as long as git needs to keep it unambiguous.
+    pub sha: String,
+    /// Unix seconds of the committer date.
+    pub timestamp: i64,
+}
+
+/// `None` outside a repository or before the first commit.
+pub fn head_commit(working_dir: impl AsRef<Path>, git: &GitContext) -> Option<HeadCommit> {
+    #[cfg(feature = "native-git")]
+    if let Some(commit) = native(working_dir.as_ref(), git, crate::native_git::head_commit) {
+        return Some(commit);
+    }
+
+    let out = git_output(working_dir, &["log", "-1", "--format=%h %ct"], git)?;
+    let (sha, timestamp) = out.split_once(' ')?;
+    Some(HeadCommit {
+        sha: sha.to_string(),
+        timestamp: timestamp.parse().ok()?,
+    })
+}
+
+/// `a1b2c3d 3h ago`.
+pub fn format_commit(icon: &str, commit: &HeadCommit, now: i64) -> String {
+    format!(
+        "\x1b[90m{}{} {} ago\x1b[0m",
+        icon,
+        commit.sha,
+        crate::index::format_idle(now - commit.timestamp)
+    )
+}
+
+/// Full messages of the commits reachable from `HEAD` but not from the
+/// nearest tag, or of every commit when there is no tag yet.
+pub fn messages_since_last_tag(working_dir: impl AsRef<Path>, git: &GitContext) -> Vec<String> {
+    let working_dir = working_dir.as_ref();
+    let range = match git_output(working_dir, &["describe", "--tags", "--abbrev=0"], git) {
+        Some(tag) => format!("{}..HEAD", tag),
+        None => "HEAD".to_string(),
+    };
+    git_output(working_dir, &["log", "--format=%B%x00", &range], git)
+        .map(|out| {
+            out.split('\0')
+                .map(|m| m.trim().to_string())
+                .filter(|m| !m.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Names of the configured remotes.
+pub fn remotes(working_dir: impl AsRef<Path>, git: &GitContext) -> Vec<String> {
+    git_output(working_dir, &["remote"], git)
+        .map(|out| out.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// The branch's upstream as `remote/branch`, e.g. `origin/feature`.
+pub fn tracking_ref(working_dir: impl AsRef<Path>, git: &GitContext) -> Option<String> {
+    git_output(
+        working_dir,
+        &["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"],
+        git,
+    )
+}
+
+/// `remote/main`: the remote's `HEAD` as recorded by clone or
+/// `git remote set-head`, else whichever of `main` and `master` it has.
+pub fn remote_default_branch(
+    working_dir: impl AsRef<Path>,
+    remote: &str,
+    git: &GitContext,
+) -> Option<String> {
+    let working_dir = working_dir.as_ref();
+    let head = format!("refs/remotes/{}/HEAD", remote);
+    if let Some(branch) = git_output(working_dir, &["symbolic-ref", "--short", &head], git) {
+        return Some(branch);
+    }
+    ["main", "master"].iter().find_map(|name| {
+        let branch = format!("{}/{}", remote, name);
+        git_output(
+            working_dir,
+            &[
+                "rev-parse",
+                "--verify",
+                "--quiet",
+                &format!("refs/remotes/{}", branch),
+            ],
+            git,
+        )
+        .map(|_| branch)
+    })
+}
+
+/// Commits in `left` but not `right`, and in `right` but not `left`.
+pub fn ahead_behind(
+    working_dir: impl AsRef<Path>,
+    left: &str,
+    right: &str,
+    git: &GitContext,
+) -> Option<(u64, u64)> {
+    #[cfg(feature = "native-git")]
+    if let Some(counts) = native(working_dir.as_ref(), git, |dir| {
+        crate::native_git::ahead_behind(dir, left, right)
+    }) {
+        return Some(counts);
+    }
+
+    let out = git_output(
+        working_dir,
+        &[
+            "rev-list",
+            "--left-right",
+            "--count",
+            &format!("{}...{}", left, right),
+        ],
+        git,
+    )?;
+    let mut counts = out.split_whitespace().map(|n| n.parse().ok());
+    Some((counts.next()??, counts.next()??))
+}
+
+pub fn is_git_repo(dir: impl AsRef<Path>, git: &GitContext) -> bool {
+    #[cfg(feature = "native-git")]
+    if let Some(is_repo) = native(dir.as_ref(), git, crate::native_git::is_git_repo) {
+        return is_repo;
+    }
+
+    git_output(dir, &["rev-parse", "--is-inside-work-tree"], git).as_deref() == Some("true")
+}
+
+/// Per-worktree and shared git directories of the repository containing
+/// `working_dir`, from a single `git rev-parse` call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitDirs {
+    /// `.git`, or `.git/worktrees/<name>` inside a linked worktree.
+    pub git_dir: PathBuf,
+    /// `.git` of the main checkout, shared by all worktrees.
+    pub common_dir: PathBuf,
+}
+
+impl GitDirs {
+    /// Name of the linked worktree, or `None` in the main checkout.
+    pub fn worktree_name(&self) -> Option<String> {
+        if self.git_dir == self.common_dir {
+            return None;
+        }
+        self.git_dir
+            .parent()
+            .filter(|p| p.ends_with("worktrees"))
+            .and(self.git_dir.file_name())
+            .map(|n| n.to_string_lossy().into_owned())
+    }
+
+    /// Where state that belongs to one checkout (e.g. `build.json`) lives.
+    pub fn statusbar_dir(&self) -> PathBuf {
+        self.git_dir.join("statusbar")
+    }
+}
+
+pub fn get_git_dirs(working_dir: impl AsRef<Path>, git: &GitContext) -> Option<GitDirs> {
+    let working_dir = working_dir.as_ref();
+    let output = run(
+        working_dir,
+        &["rev-parse", "--absolute-git-dir", "--git-common-dir"],
+        git,
+    )?;
+    let mut lines = output.split(|&b| b == b'\n');
+    let git_dir = path_from_stdout(lines.next()?);
+    let common_dir = path_from_stdout(lines.next()?);
+    let common_dir = if common_dir.is_absolute() {
+        common_dir
+    } else {
+        working_dir.join(common_dir)
+    };
+
+    Some(GitDirs {
+        git_dir: normalize(&git_dir),
+        common_dir: normalize(&common_dir),
+    })
+}
+
+/// A path printed by git, without the trailing newline. On Unix the raw
+/// bytes are kept, so non-UTF-8 directory names survive.
+fn path_from_stdout(bytes: &[u8]) -> PathBuf {
+    let bytes = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+    let bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+    }
+    #[cfg(not(unix))]
+    {
+        PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+    }
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// True while a merge, rebase, cherry-pick or revert is stopped in `git_dir`.
+pub fn operation_in_progress(git_dir: &Path) -> bool {
+    [
+        "MERGE_HEAD",
+        "REBASE_HEAD",
+        "CHERRY_PICK_HEAD",
+        "REVERT_HEAD",
+        "rebase-merge",
+        "rebase-apply",
+    ]
+    .iter()
+    .any(|name| git_dir.join(name).exists())
+}
+
+/// Number of paths with unresolved conflicts.
+pub fn count_unmerged_paths(working_dir: impl AsRef<Path>, git: &GitContext) -> usize {
+    run(
+        working_dir,
+        &["diff", "--name-only", "--diff-filter=U"],
+        git,
+    )
+    .map(|out| {
+        String::from_utf8_lossy(&out)
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .count()
+    })
+    .unwrap_or(0)
+}
+
+/// How much of the working tree [`working_tree_status`] looks at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StatusMode {
+    /// Tracked changes and untracked files.
+    Full,
+    /// `--untracked-files=no`: untracked files are not counted.
+    TrackedOnly,
+    /// No status at all, only the branch.
+    BranchOnly,
+}
+
+impl StatusMode {
+    fn cheaper(self) -> StatusMode {
+        match self {
+            StatusMode::Full => StatusMode::TrackedOnly,
+            _ => StatusMode::BranchOnly,
+        }
+    }
+}
+
+/// File counts from `git status --porcelain`. Counts are approximate unless
+/// `mode` is [`StatusMode::Full`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkingTreeStatus {
+    pub staged: usize,
+    pub modified: usize,
+    pub untracked: usize,
+    /// Tracked files with staged or unstaged changes, each counted once.
+    pub changed: usize,
+    pub mode: StatusMode,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedStatusMode {
+    at: i64,
+    mode: StatusMode,
+}
+
+/// Number of entries in the index, read from its header without loading
+/// the rest of it.
+pub fn index_entries(git_dir: &Path) -> Option<u64> {
+    let mut header = [0u8; 12];
+    std::fs::File::open(git_dir.join("index"))
+        .and_then(|mut file| file.read_exact(&mut header))
+        .ok()?;
+    if &header[..4] != b"DIRC" {
+        return None;
+    }
+    Some(u32::from_be_bytes([header[8], header[9], header[10], header[11]]) as u64)
+}
+
+fn parse_porcelain(output: &str, mode: StatusMode) -> WorkingTreeStatus {
+    let mut status = WorkingTreeStatus {
+        staged: 0,
+        modified: 0,
+        untracked: 0,
+        changed: 0,
+        mode,
+    };
+    for line in output.lines() {
+        let mut codes = line.chars();
+        let (Some(x), Some(y)) = (codes.next(), codes.next()) else {
+            continue;
+        };
+        // Conflicts are counted separately by `count_unmerged_paths`.
+        if x == 'U' || y == 'U' || matches!((x, y), ('A', 'A') | ('D', 'D')) {
+            continue;
+        }
+        if x == '?' {
+            status.untracked += 1;
+            continue;
+        }
+        status.changed += 1;
+        if x != ' ' {
+            status.staged += 1;
+        }
+        if y != ' ' {
+            status.modified += 1;
+        }
+    }
+    status
+}
+
+/// Working tree counts for the checkout at `working_dir`. Repositories with
+/// more than `status_large_repo_files` index entries skip untracked files,
+/// and a status slower than `status_timeout_ms` drops to the next cheaper
+/// mode, which is remembered per checkout for `status_mode_refresh_secs`.
+/// `None` when the status failed.
+pub fn working_tree_status(
+    working_dir: &Path,
+    dirs: &GitDirs,
+    config: &GitConfig,
+    git: &GitContext,
+) -> Option<WorkingTreeStatus> {
+    let name = format!(
+        "status-mode-{}",
+        crate::cache::cache_key(&dirs.git_dir.to_string_lossy())
+    );
+    let now = chrono::Utc::now().timestamp();
+    let remembered = crate::cache::read_cache(&name)
+        .and_then(|data| serde_json::from_str::<CachedStatusMode>(&data).ok())
+        .filter(|cached| now - cached.at < config.status_mode_refresh_secs)
+        .map(|cached| cached.mode);
+
+    let mut mode = remembered.unwrap_or_else(|| match index_entries(&dirs.git_dir) {
+        Some(n) if n > config.status_large_repo_files => StatusMode::TrackedOnly,
+        _ => StatusMode::Full,
+    });
+    let timeout = Duration::from_millis(config.status_timeout_ms);
+    let mut timed_out = false;
+
+    loop {
+        let untracked = match mode {
+            StatusMode::Full => "--untracked-files=normal",
+            StatusMode::TrackedOnly => "--untracked-files=no",
+            StatusMode::BranchOnly => break,
+        };
+        let args = [
+            "status",
+            "--porcelain",
+            "--ignore-submodules=dirty",
+            untracked,
+        ];
+        let (mut command, timeout) = invocation(working_dir, &args, timeout, git);
+        match crate::process::output_within(&mut command, timeout) {
+            Ok(output) => {
+                if timed_out {
+                    remember_status_mode(&name, now, mode);
+                }
+                return output.map(|out| parse_porcelain(&String::from_utf8_lossy(&out), mode));
+            }
+            Err(()) => {
+                timed_out = true;
+                mode = mode.cheaper();
+            }
+        }
+    }
+
+    if timed_out {
+        remember_status_mode(&name, now, mode);
+    }
+    Some(WorkingTreeStatus {
+        staged: 0,
+        modified: 0,
+        untracked: 0,
+        changed: 0,
+        mode,
+    })
+}
+
+fn remember_status_mode(name: &str, at: i64, mode: StatusMode) {
+    if let Ok(data) = serde_json::to_string(&CachedStatusMode { at, mode }) {
+        let _ = crate::cache::write_cache(name, &data);
+    }
+}
+
+/// `+2 ~3 ?1`, led by a dim `≈` when the counts are approximate. Empty for
+/// a clean tree with full counts.
+pub fn format_status(status: &WorkingTreeStatus) -> String {
+    let mut parts = Vec::new();
+    if status.mode != StatusMode::Full {
+        parts.push("\x1b[90m\u{2248}\x1b[0m".to_string());
+    }
+    if status.staged > 0 {
+        parts.push(format!("\x1b[32m+{}\x1b[0m", status.staged));
+    }
+    if status.modified > 0 {
+        parts.push(format!("\x1b[33m~{}\x1b[0m", status.modified));
+    }
+    if status.untracked > 0 {
+        parts.push(format!("\x1b[90m?{}\x1b[0m", status.untracked));
+    }
+    parts.join(" ")
+}
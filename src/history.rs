@@ -0,0 +1,231 @@
+use crate::report::{Grouping, ReportRow};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// `[history]` config section: appends each render's session metrics to a
+/// SQLite database, which `statusline report` then queries instead of
+/// re-reading every transcript. Needs the `history` cargo feature.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    pub enabled: bool,
+    /// Database file; `~` is expanded. Defaults to `history.sqlite` in the
+    /// state directory.
+    pub path: Option<String>,
+    /// Minimum seconds between two rows of the same session, so frequent
+    /// redraws don't bloat the database.
+    pub interval_secs: i64,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        HistoryConfig {
+            enabled: false,
+            path: None,
+            interval_secs: 10,
+        }
+    }
+}
+
+impl HistoryConfig {
+    pub fn database_path(&self) -> PathBuf {
+        match &self.path {
+            Some(path) => crate::expand_home(path),
+            None => crate::debug::state_dir().join("history.sqlite"),
+        }
+    }
+}
+
+/// One row of the `renders` table: the session's running totals at the
+/// time of a render.
+#[derive(Debug, Clone, Default)]
+pub struct HistoryRow {
+    pub session_id: String,
+    /// Unix seconds.
+    pub at: i64,
+    pub project_dir: Option<String>,
+    pub model: Option<String>,
+    pub branch: Option<String>,
+    pub cost_usd: Option<f64>,
+    pub context_pct: Option<f64>,
+    pub input_tokens: Option<u64>,
+    pub output_tokens: Option<u64>,
+    pub duration_ms: Option<i64>,
+    pub lines_added: u64,
+    pub lines_removed: u64,
+}
+
+#[cfg(feature = "history")]
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS renders (
+    id INTEGER PRIMARY KEY,
+    session_id TEXT NOT NULL,
+    at INTEGER NOT NULL,
+    project_dir TEXT,
+    model TEXT,
+    branch TEXT,
+    cost_usd REAL,
+    context_pct REAL,
+    input_tokens INTEGER,
+    output_tokens INTEGER,
+    duration_ms INTEGER,
+    lines_added INTEGER NOT NULL DEFAULT 0,
+    lines_removed INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX IF NOT EXISTS renders_session_at ON renders (session_id, at);
+";
+
+#[cfg(feature = "history")]
+fn open(config: &HistoryConfig) -> rusqlite::Result<rusqlite::Connection> {
+    let path = config.database_path();
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let conn = rusqlite::Connection::open(path)?;
+    // Concurrent sessions render at the same time; wait briefly for the
+    // writer lock rather than failing.
+    conn.busy_timeout(std::time::Duration::from_millis(200))?;
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    conn.execute_batch(SCHEMA)?;
+    Ok(conn)
+}
+
+/// Appends `row`, unless the session already has a row from the last
+/// `interval_secs`.
+#[cfg(feature = "history")]
+pub fn record(config: &HistoryConfig, row: &HistoryRow) -> Result<(), String> {
+    use rusqlite::params;
+
+    let conn = open(config).map_err(|e| e.to_string())?;
+    let last: Option<i64> = conn
+        .query_row(
+            "SELECT MAX(at) FROM renders WHERE session_id = ?1",
+            params![row.session_id],
+            |r| r.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if last.is_some_and(|last| row.at - last < config.interval_secs) {
+        return Ok(());
+    }
+
+    conn.execute(
+        "INSERT INTO renders (session_id, at, project_dir, model, branch, cost_usd,
+             context_pct, input_tokens, output_tokens, duration_ms, lines_added, lines_removed)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        params![
+            row.session_id,
+            row.at,
+            row.project_dir,
+            row.model,
+            row.branch,
+            row.cost_usd,
+            row.context_pct,
+            row.input_tokens.map(|t| t as i64),
+            row.output_tokens.map(|t| t as i64),
+            row.duration_ms,
+            row.lines_added as i64,
+            row.lines_removed as i64,
+        ],
+    )
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "history"))]
+pub fn record(_config: &HistoryConfig, _row: &HistoryRow) -> Result<(), String> {
+    Err("built without the `history` feature".to_string())
+}
+
+/// Report rows from the history database, grouped like
+/// [`crate::report::from_transcripts`]; `None` groups by `project:branch`.
+///
+/// Rows hold running totals, so each render contributes the cost and tokens
+/// accrued since the session's previous row to its day, model or branch.
+#[cfg(feature = "history")]
+pub fn report(
+    config: &HistoryConfig,
+    grouping: Option<Grouping>,
+    aliases: &HashMap<String, String>,
+) -> Result<Vec<ReportRow>, String> {
+    use std::collections::BTreeMap;
+
+    let key = match grouping {
+        Some(Grouping::Day) => "date(at, 'unixepoch', 'localtime')",
+        Some(Grouping::Project) => "project_dir",
+        Some(Grouping::Model) => "model",
+        None => "branch",
+    };
+    // Per session and key: the accrued cost and tokens, and the span
+    // between the first and last render.
+    let sql = format!(
+        "WITH deltas AS (
+             SELECT session_id, at, project_dir, {key} AS key,
+                 COALESCE(cost_usd, 0) - COALESCE(LAG(cost_usd) OVER w, 0) AS cost,
+                 COALESCE(input_tokens, 0) + COALESCE(output_tokens, 0)
+                     - COALESCE(LAG(input_tokens) OVER w, 0)
+                     - COALESCE(LAG(output_tokens) OVER w, 0) AS tokens
+             FROM renders
+             WINDOW w AS (PARTITION BY session_id ORDER BY at, id)
+         )
+         SELECT key, MAX(project_dir), SUM(MAX(cost, 0)), SUM(MAX(tokens, 0)),
+             (MAX(at) - MIN(at)) * 1000
+         FROM deltas
+         WHERE key IS NOT NULL
+         GROUP BY session_id, key",
+        key = key
+    );
+
+    let conn = open(config).map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let sessions = stmt
+        .query_map([], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, Option<String>>(1)?,
+                r.get::<_, f64>(2)?,
+                r.get::<_, i64>(3)?,
+                r.get::<_, i64>(4)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut rows: BTreeMap<String, ReportRow> = BTreeMap::new();
+    for session in sessions {
+        let (key, project_dir, cost, tokens, duration_ms) = session.map_err(|e| e.to_string())?;
+        let key = match grouping {
+            Some(Grouping::Day) => key,
+            Some(Grouping::Model) => crate::models::resolve_model_id(&key, aliases),
+            Some(Grouping::Project) => crate::report::project_name(Some(&key)),
+            None => format!(
+                "{}:{}",
+                crate::report::project_name(project_dir.as_deref()),
+                key
+            ),
+        };
+        let row = rows.entry(key.clone()).or_insert_with(|| ReportRow {
+            key,
+            ..Default::default()
+        });
+        row.sessions += 1;
+        row.cost_usd += cost;
+        row.tokens += tokens as u64;
+        row.duration_ms += duration_ms;
+    }
+
+    let mut rows: Vec<ReportRow> = rows.into_values().collect();
+    match grouping {
+        Some(Grouping::Day) => rows.reverse(),
+        _ => rows.sort_by(|a, b| b.cost_usd.total_cmp(&a.cost_usd)),
+    }
+    Ok(rows)
+}
+
+#[cfg(not(feature = "history"))]
+pub fn report(
+    _config: &HistoryConfig,
+    _grouping: Option<Grouping>,
+    _aliases: &HashMap<String, String>,
+) -> Result<Vec<ReportRow>, String> {
+    Err("built without the `history` feature".to_string())
+}
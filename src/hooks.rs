@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Once a log grows past this, it is cut back to its last [`TAIL_BYTES`].
+const MAX_LOG_BYTES: u64 = 256 * 1024;
+/// How much of the end of a log is read, far more than the latest tool
+/// needs.
+const TAIL_BYTES: u64 = 16 * 1024;
+
+/// One line of a session's hook event log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookEvent {
+    /// `PreToolUse`, `PostToolUse`, `Stop`, ...
+    pub event: String,
+    #[serde(default)]
+    pub tool: Option<String>,
+    /// Unix milliseconds when the hook ran.
+    pub ts_ms: i64,
+}
+
+/// `<cache dir>/events/<session_id>.jsonl`.
+pub fn events_path(session_id: &str) -> PathBuf {
+    crate::cache::cache_dir()
+        .join("events")
+        .join(format!("{}.jsonl", crate::cache::cache_key(session_id)))
+}
+
+pub fn append_event(session_id: &str, event: &HookEvent) -> io::Result<()> {
+    let path = events_path(session_id);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    writeln!(file, "{}", serde_json::to_string(event)?)?;
+    if file.metadata()?.len() > MAX_LOG_BYTES {
+        crate::cache::write_atomic(&path, &read_tail(&path)?)?;
+    }
+    Ok(())
+}
+
+/// The complete lines in the last [`TAIL_BYTES`] of the log at `path`.
+fn read_tail(path: &Path) -> io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let start = file.metadata()?.len().saturating_sub(TAIL_BYTES);
+    file.seek(SeekFrom::Start(start))?;
+    let mut data = Vec::new();
+    file.read_to_end(&mut data)?;
+    if start > 0 {
+        // The cut most likely landed inside a line.
+        let first_line = data
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(data.len(), |i| i + 1);
+        data.drain(..first_line);
+    }
+    Ok(String::from_utf8_lossy(&data).into_owned())
+}
+
+/// The session's latest events, from the end of its log, so a long session
+/// doesn't make every render read its whole history.
+pub fn read_events(session_id: &str) -> Vec<HookEvent> {
+    read_tail(&events_path(session_id))
+        .map(|data| {
+            data.lines()
+                .filter_map(|l| serde_json::from_str(l).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// `statusline hook`: reads a Claude Code hook payload from stdin and appends
+/// it to the session's event log. Always exits 0 so a statusline problem can
+/// never block a tool call.
+pub fn run_cli() -> i32 {
+    let mut buffer = String::new();
+    if io::stdin().read_to_string(&mut buffer).is_err() {
+        return 0;
+    }
+    let Ok(payload) = serde_json::from_str::<serde_json::Value>(&buffer) else {
+        return 0;
+    };
+    let Some(session_id) = payload.get("session_id").and_then(|s| s.as_str()) else {
+        return 0;
+    };
+
+    let event = HookEvent {
+        event: payload
+            .get("hook_event_name")
+            .and_then(|e| e.as_str())
+            .unwrap_or("unknown")
+            .to_string(),
+        tool: payload
+            .get("tool_name")
+            .and_then(|t| t.as_str())
+            .map(str::to_string),
+        ts_ms: chrono::Utc::now().timestamp_millis(),
+    };
+    let _ = append_event(session_id, &event);
+    0
+}
+
+/// `Bash 2s ago` after the last tool finished, `Bash running 3s` while a
+/// tool is in flight, nothing once the turn has stopped or without events.
+pub fn format_last_tool(icon: &str, events: &[HookEvent], now_ms: i64) -> String {
+    let Some(last) = events
+        .iter()
+        .rev()
+        .find(|e| e.tool.is_some() || e.event == "Stop")
+    else {
+        return String::new();
+    };
+    let Some(tool) = &last.tool else {
+        return String::new();
+    };
+
+    let elapsed = crate::index::format_idle((now_ms - last.ts_ms) / 1000);
+    if last.event == "PreToolUse" {
+        format!("\x1b[33m{}{} running {}\x1b[0m", icon, tool, elapsed)
+    } else {
+        format!("\x1b[90m{}{} {} ago\x1b[0m", icon, tool, elapsed)
+    }
+}
@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// `[host]` config section: a badge when the agent's commands run somewhere
+/// other than the local machine.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HostConfig {
+    pub enabled: bool,
+    /// Append the hostname to the badge (`ssh devbox`).
+    pub show_hostname: bool,
+}
+
+impl Default for HostConfig {
+    fn default() -> Self {
+        HostConfig {
+            enabled: false,
+            show_hostname: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Container {
+    Codespace,
+    Devcontainer,
+    /// Docker or Podman without an editor integration.
+    Plain,
+}
+
+impl Container {
+    pub fn label(self) -> &'static str {
+        match self {
+            Container::Codespace => "codespace",
+            Container::Devcontainer => "devcontainer",
+            Container::Plain => "container",
+        }
+    }
+}
+
+/// Where this process runs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HostEnvironment {
+    pub container: Option<Container>,
+    pub ssh: bool,
+    pub hostname: Option<String>,
+}
+
+impl HostEnvironment {
+    pub fn is_remote(&self) -> bool {
+        self.container.is_some() || self.ssh
+    }
+}
+
+fn env_set(name: &str) -> bool {
+    std::env::var_os(name).is_some_and(|v| !v.is_empty())
+}
+
+pub fn detect_container() -> Option<Container> {
+    if std::env::var("CODESPACES").as_deref() == Ok("true") {
+        Some(Container::Codespace)
+    } else if std::env::var("REMOTE_CONTAINERS").as_deref() == Ok("true") {
+        Some(Container::Devcontainer)
+    } else if Path::new("/.dockerenv").exists() || Path::new("/run/.containerenv").exists() {
+        Some(Container::Plain)
+    } else {
+        None
+    }
+}
+
+pub fn hostname() -> Option<String> {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/proc/sys/kernel/hostname").ok())
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .or_else(|| {
+            std::process::Command::new("hostname")
+                .output()
+                .ok()
+                .filter(|o| o.status.success())
+                .map(|o| String::from_utf8_lossy(&o.stdout).into_owned())
+        })
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty())
+}
+
+pub fn detect() -> HostEnvironment {
+    let mut host = HostEnvironment {
+        container: detect_container(),
+        ssh: env_set("SSH_CONNECTION") || env_set("SSH_TTY") || env_set("SSH_CLIENT"),
+        hostname: None,
+    };
+    if host.is_remote() {
+        host.hostname = hostname();
+    }
+    host
+}
+
+/// `ssh devbox`, `container 3f2a9c`, `ssh+devcontainer`; nothing when local.
+pub fn format_host(config: &HostConfig, host: &HostEnvironment) -> String {
+    if !host.is_remote() {
+        return String::new();
+    }
+    let mut kinds = Vec::new();
+    if host.ssh {
+        kinds.push("ssh");
+    }
+    if let Some(container) = host.container {
+        kinds.push(container.label());
+    }
+    let mut badge = kinds.join("+");
+    if let Some(name) = host.hostname.as_ref().filter(|_| config.show_hostname) {
+        badge.push(' ');
+        badge.push_str(name);
+    }
+    format!("\x1b[35m{}\x1b[0m", badge)
+}
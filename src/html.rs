@@ -0,0 +1,277 @@
+use crate::theme::{ansi256_to_rgb, Rgb};
+use crate::width::display_width;
+
+/// Cell width and line height of the SVG's monospace text, in pixels at the
+/// 14px font size.
+const SVG_CELL_WIDTH: f64 = 8.4;
+const SVG_LINE_HEIGHT: f64 = 24.0;
+const SVG_PADDING: f64 = 12.0;
+
+/// Text attributes in effect at one point of a rendered line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Style {
+    pub fg: Option<Rgb>,
+    pub bg: Option<Rgb>,
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underline: bool,
+}
+
+impl Style {
+    /// Applies the parameters of one SGR sequence (`1;38;5;208`).
+    fn apply(&mut self, params: &str) {
+        let codes: Vec<u32> = params
+            .split([';', ':'])
+            .map(|p| p.parse().unwrap_or(0))
+            .collect();
+        let mut i = 0;
+        while i < codes.len() {
+            match codes[i] {
+                0 => *self = Style::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                22 => (self.bold, self.dim) = (false, false),
+                23 => self.italic = false,
+                24 => self.underline = false,
+                code @ 30..=37 => self.fg = Some(ansi256_to_rgb((code - 30) as u8)),
+                code @ 90..=97 => self.fg = Some(ansi256_to_rgb((code - 90 + 8) as u8)),
+                code @ 40..=47 => self.bg = Some(ansi256_to_rgb((code - 40) as u8)),
+                code @ 100..=107 => self.bg = Some(ansi256_to_rgb((code - 100 + 8) as u8)),
+                39 => self.fg = None,
+                49 => self.bg = None,
+                code @ (38 | 48) => {
+                    let (color, used) = match codes.get(i + 1) {
+                        Some(5) => (
+                            codes.get(i + 2).map(|&n| ansi256_to_rgb(n.min(255) as u8)),
+                            2,
+                        ),
+                        Some(2) => match codes.get(i + 2..i + 5) {
+                            Some(&[r, g, b]) => (
+                                Some(Rgb(r.min(255) as u8, g.min(255) as u8, b.min(255) as u8)),
+                                4,
+                            ),
+                            _ => (None, 4),
+                        },
+                        _ => (None, 0),
+                    };
+                    if code == 38 {
+                        self.fg = color;
+                    } else {
+                        self.bg = color;
+                    }
+                    i += used;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+
+    fn css(&self) -> String {
+        let mut css = Vec::new();
+        if let Some(fg) = self.fg {
+            css.push(format!("color:{}", hex(fg)));
+        }
+        if let Some(bg) = self.bg {
+            css.push(format!("background:{}", hex(bg)));
+        }
+        if self.bold {
+            css.push("font-weight:bold".to_string());
+        }
+        if self.dim {
+            css.push("opacity:0.6".to_string());
+        }
+        if self.italic {
+            css.push("font-style:italic".to_string());
+        }
+        if self.underline {
+            css.push("text-decoration:underline".to_string());
+        }
+        css.join(";")
+    }
+
+    fn svg_attributes(&self, default_fg: Rgb) -> String {
+        let mut attrs = format!(r#" fill="{}""#, hex(self.fg.unwrap_or(default_fg)));
+        if self.bold {
+            attrs.push_str(r#" font-weight="bold""#);
+        }
+        if self.dim {
+            attrs.push_str(r#" opacity="0.6""#);
+        }
+        if self.italic {
+            attrs.push_str(r#" font-style="italic""#);
+        }
+        if self.underline {
+            attrs.push_str(r#" text-decoration="underline""#);
+        }
+        attrs
+    }
+}
+
+/// A run of text in one style, inside an OSC 8 link when `link` is set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub text: String,
+    pub style: Style,
+    pub link: Option<String>,
+}
+
+/// Splits a rendered line into styled runs. SGR and OSC 8 sequences are
+/// interpreted; cursor movement, bells and other controls are dropped.
+pub fn spans(line: &str) -> Vec<Span> {
+    let mut spans: Vec<Span> = Vec::new();
+    let mut style = Style::default();
+    let mut link: Option<String> = None;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' if chars.peek() == Some(&'[') => {
+                chars.next();
+                let mut params = String::new();
+                for c in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&c) {
+                        if c == 'm' {
+                            style.apply(&params);
+                        }
+                        break;
+                    }
+                    params.push(c);
+                }
+            }
+            '\x1b' if chars.peek() == Some(&']') => {
+                chars.next();
+                let mut body = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\x07' => break,
+                        '\x1b' if chars.peek() == Some(&'\\') => {
+                            chars.next();
+                            break;
+                        }
+                        c => body.push(c),
+                    }
+                }
+                // `8;params;url`, with an empty url closing the link.
+                if let Some(rest) = body.strip_prefix("8;") {
+                    let url = rest.split_once(';').map_or("", |(_, url)| url);
+                    link = (!url.is_empty()).then(|| url.to_string());
+                }
+            }
+            c if c.is_control() => {}
+            c => match spans.last_mut() {
+                Some(last) if last.style == style && last.link == link => last.text.push(c),
+                _ => spans.push(Span {
+                    text: c.to_string(),
+                    style: style.clone(),
+                    link: link.clone(),
+                }),
+            },
+        }
+    }
+    spans
+}
+
+fn hex(rgb: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", rgb.0, rgb.1, rgb.2)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Light gray on dark backgrounds, near-black on light ones.
+fn default_foreground(background: Rgb) -> Rgb {
+    if background.luminance() < 0.5 {
+        Rgb(229, 229, 229)
+    } else {
+        Rgb(30, 30, 30)
+    }
+}
+
+/// The line as a self-contained `<pre>` snippet on `background`, for docs
+/// and bug reports.
+pub fn to_html(line: &str, background: Rgb) -> String {
+    let mut out = format!(
+        r#"<pre class="cc-statusline" style="background:{};color:{};padding:0.5em 1em;font-family:monospace">"#,
+        hex(background),
+        hex(default_foreground(background))
+    );
+    for span in spans(line) {
+        let css = span.style.css();
+        let text = escape(&span.text);
+        let text = if css.is_empty() {
+            text
+        } else {
+            format!(r#"<span style="{}">{}</span>"#, css, text)
+        };
+        match &span.link {
+            Some(url) => out.push_str(&format!(
+                r#"<a href="{}" style="color:inherit">{}</a>"#,
+                escape(url),
+                text
+            )),
+            None => out.push_str(&text),
+        }
+    }
+    out.push_str("</pre>\n");
+    out
+}
+
+/// The line as a standalone SVG image on `background`. Each run is placed
+/// at its terminal column, so wide glyphs don't shift what follows.
+pub fn to_svg(line: &str, background: Rgb) -> String {
+    let spans = spans(line);
+    let columns: usize = spans.iter().map(|s| display_width(&s.text)).sum();
+    let width = columns as f64 * SVG_CELL_WIDTH + 2.0 * SVG_PADDING;
+    let default_fg = default_foreground(background);
+
+    let mut out = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = width.ceil(),
+        h = SVG_LINE_HEIGHT
+    );
+    out.push_str(&format!(
+        r#"<rect width="100%" height="100%" fill="{}"/>"#,
+        hex(background)
+    ));
+
+    let mut column = 0;
+    let mut text = String::new();
+    for span in &spans {
+        let x = SVG_PADDING + column as f64 * SVG_CELL_WIDTH;
+        let span_width = display_width(&span.text);
+        if let Some(bg) = span.style.bg {
+            out.push_str(&format!(
+                r#"<rect x="{:.1}" y="0" width="{:.1}" height="{}" fill="{}"/>"#,
+                x,
+                span_width as f64 * SVG_CELL_WIDTH,
+                SVG_LINE_HEIGHT,
+                hex(bg)
+            ));
+        }
+        let tspan = format!(
+            r#"<tspan x="{:.1}"{}>{}</tspan>"#,
+            x,
+            span.style.svg_attributes(default_fg),
+            escape(&span.text)
+        );
+        match &span.link {
+            Some(url) => text.push_str(&format!(r#"<a href="{}">{}</a>"#, escape(url), tspan)),
+            None => text.push_str(&tspan),
+        }
+        column += span_width;
+    }
+    out.push_str(&format!(
+        r#"<text y="16" font-family="monospace" font-size="14" xml:space="preserve">{}</text>"#,
+        text
+    ));
+    out.push_str("</svg>\n");
+    out
+}
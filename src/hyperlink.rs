@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+
+/// Whether segments are wrapped in OSC 8 hyperlinks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HyperlinkMode {
+    /// Only in terminals known to support them.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl HyperlinkMode {
+    pub fn enabled(self) -> bool {
+        match self {
+            HyperlinkMode::Always => true,
+            HyperlinkMode::Never => false,
+            HyperlinkMode::Auto => terminal_supports_hyperlinks(),
+        }
+    }
+}
+
+/// Recognizes terminals with OSC 8 support from the variables they set.
+/// tmux and screen are left out: they drop the links unless configured to
+/// pass them through.
+pub fn terminal_supports_hyperlinks() -> bool {
+    let var = |name: &str| std::env::var(name).unwrap_or_default();
+    let program = var("TERM_PROGRAM");
+    if matches!(
+        program.as_str(),
+        "iTerm.app" | "WezTerm" | "vscode" | "ghostty" | "Hyper" | "Tabby" | "rio"
+    ) {
+        return true;
+    }
+    let term = var("TERM");
+    if ["kitty", "wezterm", "alacritty", "foot", "ghostty"]
+        .iter()
+        .any(|name| term.contains(name))
+    {
+        return true;
+    }
+    if var("VTE_VERSION").parse::<u32>().is_ok_and(|v| v >= 5000) {
+        return true;
+    }
+    [
+        "WT_SESSION",
+        "KITTY_WINDOW_ID",
+        "KONSOLE_VERSION",
+        "DOMTERM",
+    ]
+    .iter()
+    .any(|name| std::env::var_os(name).is_some())
+}
+
+/// `text` as a clickable link to `url`.
+pub fn hyperlink(url: &str, text: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+}
@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Glyph families for segment icons. Nerd Fonts 3 moved the Material Design
+/// icons to new codepoints, so fonts patched with 2.x need `nerd-v2`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IconSet {
+    #[default]
+    #[serde(alias = "nerd")]
+    NerdV3,
+    NerdV2,
+    Emoji,
+    Ascii,
+}
+
+/// Names accepted in `[icons.overrides]`.
+pub const ICON_NAMES: &[&str] = &[
+    "branch",
+    "model",
+    "style",
+    "context",
+    "cost",
+    "time",
+    "agent",
+    "plan",
+    "accept_edits",
+    "bypass",
+    "last_tool",
+    "tool_calls",
+    "timer",
+    "kube",
+    "github",
+    "gitlab",
+    "bitbucket",
+    "repo",
+    "commit",
+    "output",
+    "errors",
+    "tasks",
+    "thinking",
+];
+
+impl IconSet {
+    /// The config spelling, `nerd-v3`.
+    pub fn name(self) -> &'static str {
+        match self {
+            IconSet::NerdV3 => "nerd-v3",
+            IconSet::NerdV2 => "nerd-v2",
+            IconSet::Emoji => "emoji",
+            IconSet::Ascii => "ascii",
+        }
+    }
+
+    pub fn icon(self, name: &str) -> &'static str {
+        match (self, name) {
+            (IconSet::NerdV3, "branch") => "\u{f02a2}",
+            (IconSet::NerdV2, "branch") => "\u{f7a1}",
+            (IconSet::NerdV3 | IconSet::NerdV2, name) => match name {
+                "model" => "\u{e26d}",
+                "style" => "\u{f1fc}",
+                "context" => "\u{f49b}",
+                "cost" => "\u{f155}",
+                "time" => "\u{f017}",
+                "last_tool" => "\u{f0ad}",
+                "github" => "\u{f09b}",
+                "gitlab" => "\u{f296}",
+                "bitbucket" => "\u{f171}",
+                "repo" => "\u{f1d3}",
+                "commit" => "\u{f417}",
+                _ => IconSet::Emoji.icon(name),
+            },
+            (IconSet::Emoji, name) => match name {
+                "branch" => "\u{1f33f}",
+                "model" => "\u{1f9e0}",
+                "style" => "\u{1f3a8}",
+                "context" => "\u{1f4ca}",
+                "cost" => "\u{1f4b0}",
+                "time" => "\u{23f1}",
+                "agent" => "\u{1f916}",
+                "plan" => "\u{23f8}",
+                "accept_edits" => "\u{23f5}\u{23f5}",
+                "bypass" => "\u{26e8}",
+                "last_tool" => "\u{1f527}",
+                "tool_calls" => "\u{1f6e0}",
+                "timer" => "\u{1f345}",
+                "kube" => "\u{2638}",
+                "output" => "\u{270d}",
+                "errors" => "\u{2757}",
+                "tasks" => "\u{2611}",
+                "thinking" => "\u{1f9e0}",
+                "github" | "gitlab" | "bitbucket" | "repo" => "\u{1f4e6}",
+                _ => "",
+            },
+            (IconSet::Ascii, name) => match name {
+                "branch" => "git:",
+                "context" => "ctx",
+                "cost" => "$",
+                "agent" => "@",
+                "plan" => "||",
+                "accept_edits" => ">>",
+                "bypass" => "!!",
+                "tool_calls" => "tools",
+                "output" => "out",
+                "errors" => "err",
+                "tasks" => "tasks",
+                "thinking" => "think",
+                "timer" => "pomo",
+                "kube" => "k8s",
+                "github" => "gh:",
+                "gitlab" => "gl:",
+                "bitbucket" => "bb:",
+                _ => "",
+            },
+        }
+    }
+}
+
+/// `[icons]` config section.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IconsConfig {
+    pub set: IconSet,
+    /// Per-segment glyphs (`branch = "⎇"`); an empty string drops the icon.
+    pub overrides: HashMap<String, String>,
+}
+
+impl IconsConfig {
+    pub fn icon(&self, name: &str) -> &str {
+        self.overrides
+            .get(name)
+            .map(String::as_str)
+            .unwrap_or_else(|| self.set.icon(name))
+    }
+
+    /// The icon followed by a space, or nothing for an empty icon, so
+    /// segments don't start with a stray space.
+    pub fn prefix(&self, name: &str) -> String {
+        match self.icon(name) {
+            "" => String::new(),
+            icon => format!("{} ", icon),
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// What the last render of a session showed, recorded so other commands
+/// (`dashboard`, `report`) can list sessions without re-reading stdin.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    pub session_id: String,
+    pub project_dir: Option<String>,
+    pub model: Option<String>,
+    pub context_pct: Option<f64>,
+    pub cost_usd: Option<f64>,
+    pub transcript_path: Option<String>,
+    /// Branch checked out at the last render.
+    #[serde(default)]
+    pub branch: Option<String>,
+    /// Session cost attributed to each branch: every render adds the cost
+    /// accrued since the previous render to the branch checked out now.
+    #[serde(default)]
+    pub branch_costs: BTreeMap<String, f64>,
+    /// Unix seconds of the render that produced this snapshot.
+    pub updated_at: i64,
+}
+
+/// `<cache dir>/sessions`, one `<session_id>.json` per session.
+pub fn sessions_dir() -> PathBuf {
+    crate::cache::cache_dir().join("sessions")
+}
+
+fn session_file(session_id: &str) -> String {
+    format!("sessions/{}.json", crate::cache::cache_key(session_id))
+}
+
+pub fn load_session(session_id: &str) -> Option<SessionSnapshot> {
+    crate::cache::read_cache(&session_file(session_id))
+        .and_then(|data| serde_json::from_str(&data).ok())
+}
+
+/// Stores `snapshot`, carrying over the branch attribution of the previous
+/// snapshot of the same session, and its cost when this render had none.
+pub fn record_session(snapshot: &SessionSnapshot) -> std::io::Result<()> {
+    let mut snapshot = snapshot.clone();
+    let previous = load_session(&snapshot.session_id);
+
+    if let Some(previous) = &previous {
+        for (branch, cost) in &previous.branch_costs {
+            *snapshot.branch_costs.entry(branch.clone()).or_insert(0.0) += cost;
+        }
+    }
+    if let (Some(branch), Some(cost)) = (&snapshot.branch, snapshot.cost_usd) {
+        let before = previous.as_ref().and_then(|p| p.cost_usd).unwrap_or(0.0);
+        let delta = (cost - before).max(0.0);
+        *snapshot.branch_costs.entry(branch.clone()).or_insert(0.0) += delta;
+    }
+    // Otherwise the next render with a cost would count it all again.
+    snapshot.cost_usd = snapshot
+        .cost_usd
+        .or_else(|| previous.as_ref().and_then(|p| p.cost_usd));
+
+    let json = serde_json::to_string(&snapshot)?;
+    crate::cache::write_cache(&session_file(&snapshot.session_id), &json)
+}
+
+/// All recorded sessions, most recently updated first.
+pub fn load_sessions() -> Vec<SessionSnapshot> {
+    let Ok(entries) = fs::read_dir(sessions_dir()) else {
+        return Vec::new();
+    };
+
+    let mut sessions: Vec<SessionSnapshot> = entries
+        .flatten()
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|e| fs::read_to_string(e.path()).ok())
+        .filter_map(|data| serde_json::from_str(&data).ok())
+        .collect();
+
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.updated_at));
+    sessions
+}
+
+/// Compact idle time such as `42s`, `7m`, `3h`, `2d`.
+pub fn format_idle(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    if seconds < 60 {
+        format!("{}s", seconds)
+    } else if seconds < 3600 {
+        format!("{}m", seconds / 60)
+    } else if seconds < 86400 {
+        format!("{}h", seconds / 3600)
+    } else {
+        format!("{}d", seconds / 86400)
+    }
+}
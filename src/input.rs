@@ -0,0 +1,190 @@
+use crate::transcript::{calculate_session_cost, Transcript};
+use crate::StatuslineError;
+use std::io::Read;
+
+/// The JSON document Claude Code pipes to the statusline command.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatuslineInput {
+    value: serde_json::Value,
+}
+
+impl StatuslineInput {
+    pub fn from_value(value: serde_json::Value) -> Self {
+        StatuslineInput { value }
+    }
+
+    /// Parses a payload; blank input is [`StatuslineError::EmptyInput`].
+    pub fn from_json(json: &str) -> Result<Self, StatuslineError> {
+        if json.trim().is_empty() {
+            return Err(StatuslineError::EmptyInput);
+        }
+        Ok(Self::from_value(serde_json::from_str(json)?))
+    }
+
+    pub fn from_reader(mut reader: impl Read) -> Result<Self, StatuslineError> {
+        let mut buffer = String::new();
+        reader.read_to_string(&mut buffer)?;
+        Self::from_json(&buffer)
+    }
+
+    pub fn as_value(&self) -> &serde_json::Value {
+        &self.value
+    }
+
+    pub fn into_value(self) -> serde_json::Value {
+        self.value
+    }
+
+    pub fn session_id(&self) -> Option<&str> {
+        self.value.get("session_id").and_then(|s| s.as_str())
+    }
+}
+
+impl From<serde_json::Value> for StatuslineInput {
+    fn from(value: serde_json::Value) -> Self {
+        Self::from_value(value)
+    }
+}
+
+/// Generations of the payload, each adding fields to the previous one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Schema {
+    /// Before 1.0.80: model, workspace and transcript path only.
+    Legacy,
+    /// 1.0.80 and later: adds `cost` and `output_style`.
+    Cost,
+    /// 2.0 and later: adds `context_window`.
+    ContextWindow,
+}
+
+impl StatuslineInput {
+    /// Which schema the payload follows, from its `version` or, without
+    /// one, from the fields present.
+    pub fn schema(&self) -> Schema {
+        detect_schema(&self.value)
+    }
+}
+
+pub fn detect_schema(input: &serde_json::Value) -> Schema {
+    let features = crate::features::detect(input);
+    if features.context_window {
+        Schema::ContextWindow
+    } else if features.output_style || features.cost_duration {
+        Schema::Cost
+    } else {
+        Schema::Legacy
+    }
+}
+
+/// `totalCostUsd` → `total_cost_usd`.
+fn snake_case(key: &str) -> String {
+    let mut out = String::with_capacity(key.len() + 4);
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            out.push('_');
+            out.push(c.to_ascii_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Adds the snake_case spelling of every camelCase key that has no
+/// snake_case twin, at any depth.
+fn snake_case_keys(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            let renamed: Vec<(String, String)> = map
+                .keys()
+                .filter(|k| k.chars().any(|c| c.is_ascii_uppercase()))
+                .map(|k| (k.clone(), snake_case(k)))
+                .filter(|(_, snake)| !map.contains_key(snake))
+                .collect();
+            for (camel, snake) in renamed {
+                if let Some(v) = map.remove(&camel) {
+                    map.insert(snake, v);
+                }
+            }
+            map.values_mut().for_each(snake_case_keys);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(snake_case_keys),
+        _ => {}
+    }
+}
+
+/// Brings any payload generation into the current shape: camelCase keys are
+/// renamed, a bare `"model": "<id>"` becomes an object, a top-level `cwd`
+/// becomes `workspace.current_dir`, and a missing session cost and duration
+/// are filled in from the transcript, which `transcript` loads from its path
+/// (`None` when it couldn't be parsed in time).
+/// Fields that can't be derived stay absent; the renderer skips their
+/// segments.
+pub fn normalize<'a>(
+    input: &serde_json::Value,
+    config: &crate::Config,
+    transcript: impl FnOnce(&str) -> Option<&'a Transcript>,
+) -> serde_json::Value {
+    let mut input = input.clone();
+    if !input.is_object() {
+        return input;
+    }
+    snake_case_keys(&mut input);
+
+    if let Some(id) = input
+        .get("model")
+        .and_then(|m| m.as_str())
+        .map(str::to_string)
+    {
+        let name = crate::models::family_name(&id, &config.models.aliases);
+        input["model"] = serde_json::json!({"id": id, "display_name": name});
+    }
+    if input.get("workspace").is_none() {
+        if let Some(cwd) = input.get("cwd").cloned() {
+            input["workspace"] = serde_json::json!({"current_dir": cwd});
+        }
+    }
+
+    let transcript_path = input
+        .get("transcript_path")
+        .and_then(|t| t.as_str())
+        .map(str::to_string);
+    if let Some(path) = transcript_path {
+        let cost = input.get("cost");
+        let has_total = cost.and_then(|c| c.get("total_cost_usd")).is_some();
+        let has_duration = cost.and_then(|c| c.get("total_duration_ms")).is_some();
+        let model_id = input
+            .get("model")
+            .and_then(|m| m.get("id"))
+            .and_then(|i| i.as_str())
+            .map(str::to_string);
+
+        let transcript = (!has_total || !has_duration)
+            .then(|| transcript(&path))
+            .flatten();
+        let total = match (model_id, transcript) {
+            (Some(id), Some(transcript)) if !has_total && !transcript.usage.is_empty() => Some(
+                calculate_session_cost(&transcript.usage, &id, &config.models.aliases).total_usd,
+            ),
+            _ => None,
+        };
+        let duration = transcript
+            .filter(|_| !has_duration)
+            .and_then(|t| t.timestamps())
+            .map(|(first, last)| last - first);
+
+        if total.is_some() || duration.is_some() {
+            if !input.get("cost").is_some_and(|c| c.is_object()) {
+                input["cost"] = serde_json::json!({});
+            }
+            if let Some(total) = total {
+                input["cost"]["total_cost_usd"] = serde_json::json!(total);
+            }
+            if let Some(duration) = duration {
+                input["cost"]["total_duration_ms"] = serde_json::json!(duration);
+            }
+        }
+    }
+
+    input
+}
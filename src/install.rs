@@ -0,0 +1,128 @@
+use crate::cli::InstallArgs;
+use crate::config_edit::ConfigDocument;
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where `install` writes: the user's `~/.claude/settings.json`, or the
+/// project's `.claude/settings.json` with `--project`.
+pub fn settings_path(project: bool) -> PathBuf {
+    if project {
+        PathBuf::from(".claude").join("settings.json")
+    } else {
+        crate::expand_home("~/.claude/settings.json")
+    }
+}
+
+/// The command Claude Code should run: this binary, with `$HOME` written as
+/// `~` so the settings stay portable across machines.
+pub fn default_command() -> Option<String> {
+    let exe = std::env::current_exe().ok()?;
+    let exe = exe.canonicalize().unwrap_or(exe);
+    Some(crate::path::tilde_path(&exe.to_string_lossy()))
+}
+
+/// Merges a `statusLine` entry running `command` into `settings`, keeping
+/// every other key (including extra `statusLine` fields such as `padding`).
+pub fn merge_status_line(settings: &mut Value, command: &str) -> Result<(), String> {
+    let root = settings
+        .as_object_mut()
+        .ok_or("settings file is not a JSON object")?;
+    let entry = root
+        .entry("statusLine")
+        .or_insert_with(|| Value::Object(Map::new()));
+    if !entry.is_object() {
+        *entry = Value::Object(Map::new());
+    }
+    let entry = entry.as_object_mut().expect("just made an object");
+    entry.insert("type".to_string(), json!("command"));
+    entry.insert("command".to_string(), json!(command));
+    Ok(())
+}
+
+/// Copies `path` to `<path>.bak-<timestamp>` and returns the backup path.
+fn backup(path: &Path) -> std::io::Result<PathBuf> {
+    let stamp = chrono::Local::now().format("%Y%m%d%H%M%S");
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".bak-{}", stamp));
+    let backup = PathBuf::from(name);
+    fs::copy(path, &backup)?;
+    Ok(backup)
+}
+
+/// Writes the `statusLine` entry into the settings file at `path`, backing up
+/// an existing file first. An existing file that isn't valid JSON is left
+/// untouched.
+pub fn install(path: &Path, command: &str) -> Result<Option<PathBuf>, String> {
+    let existing = match fs::read_to_string(path) {
+        Ok(data) => Some(data),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+
+    let mut settings = match existing.as_deref().map(str::trim) {
+        None | Some("") => json!({}),
+        Some(data) => serde_json::from_str(data)
+            .map_err(|e| format!("{} is not valid JSON ({}); not modified", path.display(), e))?,
+    };
+    merge_status_line(&mut settings, command)?;
+
+    let backup = match existing {
+        Some(_) => Some(backup(path).map_err(|e| format!("backup failed: {}", e))?),
+        None => None,
+    };
+    let json = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
+    crate::cache::write_atomic(path, &(json + "\n")).map_err(|e| e.to_string())?;
+    Ok(backup)
+}
+
+/// `statusline install [--project] [--command <cmd>] [--set key=value]... [-- args...]`.
+///
+/// Arguments after `--` are appended to the command (e.g. `-- --short`);
+/// `--set` adjusts the statusline's own config file.
+pub fn run_cli(args: &InstallArgs) -> i32 {
+    let Some(mut command) = args.command.clone().or_else(default_command) else {
+        eprintln!("install: cannot determine the statusline binary; pass --command");
+        return 1;
+    };
+    for arg in &args.args {
+        command.push(' ');
+        command.push_str(arg);
+    }
+
+    let path = settings_path(args.project);
+    match install(&path, &command) {
+        Ok(backup) => {
+            if let Some(backup) = backup {
+                println!("backed up {} to {}", path.display(), backup.display());
+            }
+            println!("statusLine in {} now runs `{}`", path.display(), command);
+        }
+        Err(e) => {
+            eprintln!("install: {}", e);
+            return 1;
+        }
+    }
+
+    if args.sets.is_empty() {
+        return 0;
+    }
+    let result = ConfigDocument::open_default().and_then(|mut doc| {
+        for (key, value) in &args.sets {
+            doc.set_from_str(key, value)?;
+        }
+        doc.validate()?;
+        doc.save()?;
+        Ok(doc.path().to_path_buf())
+    });
+    match result {
+        Ok(config) => {
+            println!("updated {}", config.display());
+            0
+        }
+        Err(e) => {
+            eprintln!("install: config not updated: {}", e);
+            1
+        }
+    }
+}
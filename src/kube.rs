@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// `[kube]` config section: the Kubernetes context the agent's shell
+/// commands will talk to, read from the kubeconfig without running kubectl.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KubeConfig {
+    pub enabled: bool,
+    /// Append `/<namespace>` to the context name.
+    pub show_namespace: bool,
+    /// Glob patterns (`*prod*`) for contexts shown in red.
+    pub production: Vec<String>,
+}
+
+impl Default for KubeConfig {
+    fn default() -> Self {
+        KubeConfig {
+            enabled: false,
+            show_namespace: true,
+            production: vec!["*prod*".to_string()],
+        }
+    }
+}
+
+/// The current context and its namespace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KubeContext {
+    pub context: String,
+    pub namespace: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct Kubeconfig {
+    current_context: Option<String>,
+    contexts: Vec<NamedContext>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct NamedContext {
+    name: String,
+    context: ContextEntry,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ContextEntry {
+    namespace: Option<String>,
+}
+
+/// `$KUBECONFIG` (a path list), else `~/.kube/config`.
+pub fn kubeconfig_paths() -> Vec<PathBuf> {
+    match std::env::var_os("KUBECONFIG") {
+        Some(paths) if !paths.is_empty() => std::env::split_paths(&paths).collect(),
+        _ => vec![crate::expand_home("~/.kube/config")],
+    }
+}
+
+/// Resolves the current context like kubectl does for merged configs: the
+/// first file that sets `current-context` wins, and the context's entry is
+/// taken from the first file that defines it.
+pub fn read_current(paths: &[PathBuf]) -> Option<KubeContext> {
+    let configs: Vec<Kubeconfig> = paths
+        .iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .filter_map(|data| serde_yaml_ng::from_str(&data).ok())
+        .collect();
+
+    let context = configs
+        .iter()
+        .find_map(|c| c.current_context.clone())
+        .filter(|c| !c.is_empty())?;
+    let namespace = configs
+        .iter()
+        .flat_map(|c| &c.contexts)
+        .find(|c| c.name == context)
+        .and_then(|c| c.context.namespace.clone());
+    Some(KubeContext { context, namespace })
+}
+
+pub fn is_production(config: &KubeConfig, context: &str) -> bool {
+    crate::matches_any(&config.production, context)
+}
+
+/// `☸ staging/payments`, red for production contexts.
+pub fn format_kube(config: &KubeConfig, icon: &str, current: &KubeContext) -> String {
+    let name = match (&current.namespace, config.show_namespace) {
+        (Some(ns), true) => format!("{}/{}", current.context, ns),
+        _ => current.context.clone(),
+    };
+    if is_production(config, &current.context) {
+        format!("\x1b[1;31m{}{}\x1b[0m", icon, name)
+    } else {
+        format!("\x1b[34m{}{}\x1b[0m", icon, name)
+    }
+}
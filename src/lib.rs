@@ -1,229 +1,759 @@
 use std::fs;
-use std::io::{self, Read};
-use std::path::Path;
-use std::process::Command;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub mod aggregate;
+pub mod alerts;
+pub mod anomaly;
+pub mod bar;
+pub mod budget;
+pub mod build_status;
+pub mod build_target;
+pub mod cache;
+pub mod cli;
+pub mod cloud;
+pub mod config;
+pub mod config_edit;
+pub mod context;
+pub mod custom;
+#[cfg(feature = "dashboard")]
+pub mod dashboard;
+pub mod deadline;
+pub mod debug;
+pub mod diagnostics;
+pub mod doctor;
+pub mod error;
+pub mod export;
+pub mod features;
+pub mod format;
+pub mod frame;
+pub mod git;
+pub mod history;
+pub mod hooks;
+pub mod host;
+pub mod html;
+pub mod hyperlink;
+pub mod icons;
+pub mod index;
+pub mod input;
+pub mod install;
+pub mod kube;
+pub mod models;
+#[cfg(feature = "native-git")]
+mod native_git;
+pub mod path;
+pub mod post;
+pub mod preview;
+mod process;
+pub mod project;
+pub mod provider;
+pub mod pull_request;
+pub mod release;
+pub mod remote;
+pub mod remote_workspace;
+pub mod report;
+pub mod runtime;
+pub mod script;
+mod segments;
+pub mod selftest;
+pub mod state;
+pub mod summary;
+pub mod system;
+pub mod template;
+pub mod theme;
+pub mod timer;
+pub mod transcript;
+pub mod vcs;
+pub mod visibility;
+pub mod width;
+
+pub use config::{Config, CostMode, DurationFormat, PathMode, SessionIdMode};
+pub use error::StatuslineError;
+pub use git::{
+    get_git_branch, get_git_dirs, get_git_toplevel, git_branch, is_git_repo, GitContext, GitDirs,
+};
+pub use input::StatuslineInput;
+pub use path::fish_shorten_path;
+pub use provider::{DataProvider, Registry};
+
+/// Everything a render needs besides the input: the user's config and the
+/// providers of an embedding application.
+#[derive(Default)]
+pub struct Options {
+    pub config: Config,
+    pub registry: Registry,
+}
 
-pub fn statusline(_show_pr_status: bool) -> String {
-    let input = read_input().unwrap_or_default();
+impl Options {
+    pub fn new(config: Config) -> Self {
+        Options {
+            config,
+            registry: Registry::default(),
+        }
+    }
 
-    let current_dir = input
-        .get("workspace")
-        .and_then(|w| w.get("current_dir"))
-        .and_then(|d| d.as_str());
-
-    let model = input
-        .get("model")
-        .and_then(|m| m.get("display_name"))
-        .and_then(|d| d.as_str());
-
-    let output_style = input
-        .get("output_style")
-        .and_then(|o| o.get("name"))
-        .and_then(|n| n.as_str());
-
-    let model_display = if let Some(model) = model {
-        let style_suffix = match output_style {
-            Some(style) => format!(" \x1b[90m({})\x1b[0m", style),
-            None => String::new(),
-        };
-        format!(
-            "\x1b[38;5;14m\u{e26d} \x1b[38;5;208m{}{}",
-            model, style_suffix
-        )
+    /// Options with the user's config file.
+    pub fn load() -> Self {
+        Options::new(Config::load())
+    }
+}
+
+/// A rendered line with the metrics it was built from (`context_pct`,
+/// `cost_usd`, `tokens_total`, ...) and the labels exporters attach to them.
+pub struct Rendered {
+    pub line: String,
+    pub metrics: alerts::AlertMetrics,
+    pub attributes: Vec<(&'static str, String)>,
+}
+
+/// Renders the statusline for `input` without touching stdin; the entry
+/// point for embedding applications and tests.
+pub fn statusline_from_value(input: &StatuslineInput, opts: &Options) -> String {
+    render(input.as_value(), &opts.config, &opts.registry)
+}
+
+/// Reads the Claude Code JSON from stdin and renders the statusline. Bad
+/// input still renders what it can, followed by a `⚠ stdin`/`⚠ json` marker.
+pub fn statusline(config: &Config) -> String {
+    statusline_from_path(Path::new("-"), config)
+}
+
+/// [`statusline`] with the JSON read from a file or FIFO instead of stdin
+/// (`--input`); `-` still means stdin. An unreadable file renders what it
+/// can, followed by `⚠ input`.
+pub fn statusline_from_path(path: &Path, config: &Config) -> String {
+    rendered_from_path(path, config).line
+}
+
+/// [`statusline_from_path`] with the render's metrics.
+pub fn rendered_from_path(path: &Path, config: &Config) -> Rendered {
+    let input = debug::timed("read input", || {
+        if path == Path::new("-") {
+            return StatuslineInput::from_reader(io::stdin().lock());
+        }
+        fs::read(path)
+            .map_err(|e| StatuslineError::InputFile(path.to_path_buf(), e))
+            .and_then(|data| StatuslineInput::from_reader(data.as_slice()))
+    });
+    let registry = Registry::default();
+    match input {
+        Ok(input) => {
+            debug::trace(&format!("input schema: {:?}", input.schema()));
+            let rendered = debug::timed("render", || {
+                render_with_metrics(input.as_value(), config, &registry)
+            });
+            debug::flush_profile();
+            rendered
+        }
+        Err(e) => {
+            debug::trace(&e.to_string());
+            let mut rendered = render_with_metrics(&serde_json::Value::Null, config, &registry);
+            rendered.line = format!("{} {}", rendered.line, e.placeholder());
+            rendered
+        }
+    }
+}
+
+/// Renders the statusline for an already parsed input, appending the
+/// segments of every provider in `registry`.
+pub fn render(input: &serde_json::Value, config: &Config, registry: &Registry) -> String {
+    render_with_metrics(input, config, registry).line
+}
+
+/// [`render`], also returning the numbers behind the line for
+/// `--output prometheus`.
+pub fn render_with_metrics(
+    input: &serde_json::Value,
+    config: &Config,
+    registry: &Registry,
+) -> Rendered {
+    let deadline = deadline::Deadline::start(config.layout.budget_ms);
+    let git_context = &git::GitContext::new(&config.git, &config.remote_workspace);
+    // The session's transcript, parsed at most once per render and only
+    // when something reads it.
+    let scan = std::cell::OnceCell::new();
+    let input = &debug::profiled("input", || {
+        input::normalize(input, config, |path| {
+            segments::load_transcript(&scan, path, &deadline)
+        })
+    });
+    let ctx = segments::SegmentContext::new(input, config, deadline, git_context, &scan);
+    let (session_id, model_id, current_dir) = (ctx.session_id, ctx.model_id, ctx.current_dir);
+
+    let project_display = ctx.render("project", false, || segments::project(&ctx));
+    let display_dir = ctx.render("path", false, || segments::path(&ctx));
+    let git_display = ctx.render("git", false, || segments::git(&ctx));
+    let branch = &ctx.workspace().branch;
+
+    let mut components: Vec<(&str, String)> = segments::SEGMENTS
+        .iter()
+        .map(|segment| (segment.name, ctx.run(segment)))
+        .filter(|(_, segment)| !segment.is_empty())
+        .collect();
+    components.extend(deadline.optional("providers", || registry.named_segments(input)));
+    custom::splice(
+        &mut components,
+        &config.segments.custom,
+        current_dir,
+        &deadline,
+    );
+
+    let separator = if config.layout.compact {
+        " "
     } else {
+        " \x1b[90m• \x1b[0m"
+    };
+
+    let components_str = if components.is_empty() {
         String::new()
+    } else {
+        let texts: Vec<&str> = components.iter().map(|(_, s)| s.as_str()).collect();
+        format!("{}{}", separator, texts.join(separator))
     };
 
-    let context_display = if let Some(ctx) = input.get("context_window") {
-        let window_size = ctx
-            .get("context_window_size")
-            .and_then(|v| v.as_u64())
-            .unwrap_or(200000);
-
-        let used = if let Some(current) = ctx.get("current_usage") {
-            let input = current
-                .get("input_tokens")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(0);
-            let cache_creation = current
-                .get("cache_creation_input_tokens")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(0);
-            let cache_read = current
-                .get("cache_read_input_tokens")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(0);
-            input + cache_creation + cache_read
-        } else {
-            0
-        };
-        let pct = if window_size > 0 {
-            ((used as f64 * 100.0) / window_size as f64).min(100.0)
+    let line = if !branch.is_empty() {
+        if display_dir.is_empty() {
+            format!("{}{}", git_display, components_str)
         } else {
-            0.0
-        };
+            format!(
+                "\x1b[36m{}\x1b[0m {}{}",
+                display_dir.trim_end(),
+                git_display,
+                components_str
+            )
+        }
+    } else {
+        format!(
+            "\x1b[36m{}\x1b[0m{}",
+            display_dir.trim_end(),
+            components_str
+        )
+    };
 
-        let pct_color = if pct >= 90.0 {
-            "\x1b[31m"
-        } else if pct >= 70.0 {
-            "\x1b[38;5;208m"
-        } else if pct >= 50.0 {
-            "\x1b[33m"
-        } else {
-            "\x1b[90m"
-        };
+    let line = if project_display.is_empty() {
+        line
+    } else {
+        format!("{} {}", project_display, line)
+    };
 
-        let bar_width: usize = 15;
-        let filled = (pct * bar_width as f64 / 100.0).round() as usize;
-        let empty = bar_width.saturating_sub(filled);
-        let bar: String = "█".repeat(filled) + &"░".repeat(empty);
+    let mut segments = Vec::new();
+    if !project_display.is_empty() {
+        segments.push(("project", project_display));
+    }
+    if !display_dir.is_empty() {
+        segments.push(("path", format!("\x1b[36m{}\x1b[0m", display_dir.trim_end())));
+    }
+    if !git_display.is_empty() {
+        segments.push(("git", git_display));
+    }
+    segments.extend(components);
+
+    let context = ctx.context();
+    let line = match &config.layout.template {
+        Some(source) => match template::parse(source) {
+            Ok(nodes) => {
+                let lookup = |name: &str| match name {
+                    "context_bar" => Some(context.bar.as_str()),
+                    "context_tokens" => Some(context.tokens.as_str()),
+                    name => {
+                        let name = if name == "dir" { "path" } else { name };
+                        segments
+                            .iter()
+                            .find(|(n, _)| *n == name)
+                            .map(|(_, text)| text.as_str())
+                    }
+                };
+                template::render(&nodes, &lookup)
+            }
+            Err(e) => {
+                debug::trace(&e.to_string());
+                format!("{} {}", line, e.placeholder())
+            }
+        },
+        None => line,
+    };
 
-        format!(
-            "\x1b[38;5;13m\u{f49b} \x1b[90m{}\x1b[0m {}{}%\x1b[0m",
-            bar,
-            pct_color,
-            pct.round() as u32
-        )
+    let line = match &config.script.path {
+        Some(path) => debug::profiled("script", || script::apply(path, &segments, input, &line))
+            .unwrap_or_else(|e| {
+                debug::trace(&e.to_string());
+                format!("{} {}", line, e.placeholder())
+            }),
+        None => line,
+    };
+
+    let line = match &config.load_error {
+        Some(e) => format!(
+            "{} {}",
+            line,
+            StatuslineError::Config(e.clone()).placeholder()
+        ),
+        None => line,
+    };
+
+    let context_pct = context.percent();
+    let cost = ctx.cost();
+    let metrics = render_metrics(&ctx);
+    let alert_outcome = debug::profiled("alerts", || {
+        alerts::evaluate(&config.alerts, &metrics, session_id.unwrap_or("default"))
+    });
+
+    let anomaly_badge = match model_id {
+        Some(id) if config.anomaly.enabled => ctx.render("anomaly", false, || {
+            let Some(transcript) = ctx.transcript() else {
+                return String::new();
+            };
+            let turns = transcript.turns(id, &config.models.aliases);
+            let anomalies = anomaly::check_latest(
+                &turns,
+                &anomaly::detectors_for(&config.anomaly, registry),
+                &config.anomaly,
+            );
+            anomaly::log_anomalies(session_id.unwrap_or("default"), &anomalies);
+            anomaly::format_anomaly_badge(&anomalies)
+        }),
+        _ => String::new(),
+    };
+    let line = if anomaly_badge.is_empty() {
+        line
     } else {
-        String::new()
+        format!("{} {}", line, anomaly_badge)
     };
 
-    let current_dir = match current_dir {
-        Some(dir) => dir,
-        None => return format!("\x1b[31m\u{f071} missing workspace.current_dir\x1b[0m"),
+    let hard_warn = cost
+        .over_budget
+        .map(|exceeded| budget::format_hard_warn(&config.budget, exceeded))
+        .unwrap_or_default();
+    let line = if hard_warn.is_empty() {
+        line
+    } else {
+        format!("{} {}", line, hard_warn)
     };
 
-    let branch = if is_git_repo(current_dir) {
-        get_git_branch(current_dir)
+    let line = if alert_outcome.badges.is_empty() {
+        line
     } else {
-        String::new()
+        format!("{} {}", line, alert_outcome.badges.join(" "))
     };
 
-    let display_dir = format!("{} ", fish_shorten_path(current_dir));
+    let line = apply_contrast(&line, config);
+    let line = theme::downsample(&line, config.theme.color_depth);
+    let mut line = apply_padding(&line, config);
+    if alert_outcome.bell {
+        line.push('\x07');
+    }
 
-    let lines_changed = if let Some(cost_obj) = input.get("cost") {
-        let lines_added = cost_obj
-            .get("total_lines_added")
-            .and_then(|l| l.as_u64())
-            .unwrap_or(0);
-        let lines_removed = cost_obj
-            .get("total_lines_removed")
-            .and_then(|l| l.as_u64())
-            .unwrap_or(0);
+    let project_dir = input
+        .get("workspace")
+        .and_then(|w| w.get("project_dir"))
+        .and_then(|d| d.as_str())
+        .or(current_dir);
 
-        if lines_added > 0 || lines_removed > 0 {
-            format!(
-                "(\x1b[32m+{}\x1b[0m \x1b[31m-{}\x1b[0m)",
-                lines_added, lines_removed
-            )
-        } else {
-            String::new()
+    if let (Some(session_id), Some(trends)) = (session_id, &*ctx.trends.borrow()) {
+        let _ = debug::profiled("state", || state::save(session_id, trends));
+    }
+
+    if let Some(session_id) = session_id {
+        let _ = debug::profiled("index", || {
+            index::record_session(&index::SessionSnapshot {
+                session_id: session_id.to_string(),
+                project_dir: project_dir.map(str::to_string),
+                model: input
+                    .get("model")
+                    .and_then(|m| m.get("display_name"))
+                    .and_then(|d| d.as_str())
+                    .map(str::to_string),
+                context_pct,
+                cost_usd: cost.total,
+                transcript_path: ctx.transcript_path.map(str::to_string),
+                branch: (!branch.is_empty()).then(|| branch.clone()),
+                branch_costs: Default::default(),
+                updated_at: chrono::Utc::now().timestamp(),
+            })
+        });
+    }
+
+    if let (Some(session_id), true) = (session_id, config.history.enabled) {
+        let row = history::HistoryRow {
+            session_id: session_id.to_string(),
+            at: chrono::Utc::now().timestamp(),
+            project_dir: project_dir.map(str::to_string),
+            model: model_id.map(str::to_string),
+            branch: (!branch.is_empty()).then(|| branch.clone()),
+            cost_usd: cost.total,
+            context_pct,
+            input_tokens: metrics.get("input_tokens").map(|&t| t as u64),
+            output_tokens: metrics.get("output_tokens").map(|&t| t as u64),
+            duration_ms: metrics.get("duration_ms").map(|&ms| ms as i64),
+            lines_added: ctx.lines_added,
+            lines_removed: ctx.lines_removed,
+        };
+        if let Err(e) = debug::profiled("history", || history::record(&config.history, &row)) {
+            debug::trace(&format!("history: {}", e));
         }
-    } else {
-        String::new()
-    };
+    }
 
-    let cost_display = if let Some(cost_obj) = input.get("cost") {
-        if let Some(total_cost) = cost_obj.get("total_cost_usd").and_then(|c| c.as_f64()) {
-            let formatted_cost = format_cost(total_cost);
-            let cost_color = if total_cost < 5.0 {
-                "\x1b[32m"
-            } else if total_cost < 20.0 {
-                "\x1b[33m"
-            } else {
-                "\x1b[31m"
-            };
-            format!(
-                "\x1b[38;5;3m\u{f155} {}{}\x1b[0m",
-                cost_color, formatted_cost
+    let mut attributes = export::ExportAttributes::new();
+    for (key, value) in [
+        ("session_id", session_id),
+        ("model", model_id),
+        ("project", project_dir),
+    ] {
+        if let Some(value) = value {
+            attributes.push((key, value));
+        }
+    }
+    if !branch.is_empty() {
+        attributes.push(("branch", branch));
+    }
+    if config.export.is_enabled() {
+        debug::profiled("export", || {
+            export::export(
+                &config.export,
+                &metrics,
+                &attributes,
+                session_id.unwrap_or("default"),
             )
-        } else {
-            String::new()
+        });
+    }
+
+    let line = match session_id {
+        Some(session_id) if config.layout.frame_diff => {
+            debug::profiled("frame", || frame::render_frame(session_id, &line))
         }
-    } else {
-        String::new()
+        _ => line,
     };
+    Rendered {
+        line,
+        attributes: attributes
+            .into_iter()
+            .map(|(key, value)| (key, value.to_string()))
+            .collect(),
+        metrics,
+    }
+}
 
-    let mut components = Vec::new();
-    if !model_display.is_empty() {
-        components.push(model_display.clone());
+/// The numbers behind a render (`context_pct`, `cost_usd`, `tokens_total`,
+/// ...), for alert rules and exporters.
+fn render_metrics(ctx: &segments::SegmentContext) -> alerts::AlertMetrics {
+    let cost = ctx.cost();
+    let mut metrics = alerts::AlertMetrics::new();
+    if let Some(pct) = ctx.context().percent() {
+        metrics.insert("context_pct", pct);
+    }
+    if let Some(cost) = cost.total {
+        metrics.insert("cost_usd", cost);
+    }
+    metrics.insert("lines_added", ctx.lines_added as f64);
+    metrics.insert("lines_removed", ctx.lines_removed as f64);
+    let context_window = ctx.input.get("context_window");
+    let mut tokens_total = None;
+    for (metric, key) in [
+        ("input_tokens", "total_input_tokens"),
+        ("output_tokens", "total_output_tokens"),
+    ] {
+        if let Some(tokens) = context_window
+            .and_then(|c| c.get(key))
+            .and_then(as_f64_lenient)
+        {
+            metrics.insert(metric, tokens);
+            *tokens_total.get_or_insert(0.0) += tokens;
+        }
+    }
+    if let Some(tokens) = tokens_total {
+        metrics.insert("tokens_total", tokens);
     }
-    if !context_display.is_empty() {
-        components.push(context_display.clone());
+    // Branch-only status has no counts to report.
+    if let Some(status) = ctx
+        .working_tree()
+        .filter(|s| s.mode != git::StatusMode::BranchOnly)
+    {
+        metrics.insert(
+            "git_dirty_files",
+            (status.changed + status.untracked) as f64,
+        );
     }
-    if !cost_display.is_empty() {
-        components.push(cost_display.clone());
+    if let Some(ms) = ctx
+        .input
+        .get("cost")
+        .and_then(|c| c.get("total_duration_ms"))
+        .and_then(as_f64_lenient)
+    {
+        metrics.insert("duration_ms", ms);
     }
+    if ctx.config.aggregate.enabled {
+        if let Some(cost) = debug::profiled("aggregate", || {
+            aggregate::shared_cost_today(&ctx.config.aggregate, &ctx.config.models.aliases)
+        }) {
+            metrics.insert("shared_cost_today", cost);
+        }
+    }
+    if let Some(cost) = cost.daily {
+        metrics.insert("daily_cost_usd", cost);
+    }
+    metrics.insert("over_budget", cost.over_budget.is_some() as u8 as f64);
+    metrics.insert(
+        "build_failing",
+        (ctx.build() == Some(build_status::BuildStatus::Fail)) as u8 as f64,
+    );
+    metrics
+}
 
-    let components_str = if components.is_empty() {
-        String::new()
+/// The working directory as configured by `[path]`: fish-style or relative
+/// to the repository root.
+pub fn display_path(
+    current_dir: impl AsRef<Path>,
+    in_git_repo: bool,
+    config: &Config,
+    git: &GitContext,
+) -> String {
+    let current_dir = current_dir.as_ref();
+    let wants_root = config.path.mode == PathMode::Repo || config.path.keep_repo_root;
+    let repo_root = if wants_root && in_git_repo {
+        get_git_toplevel(current_dir, git)
     } else {
-        format!(
-            " \x1b[90m• \x1b[0m{}",
-            components.join(" \x1b[90m• \x1b[0m")
+        None
+    };
+
+    let repo_relative = match (&repo_root, config.path.mode) {
+        (Some(root), PathMode::Repo) => {
+            path::repo_relative_path(current_dir, root, config.path.max_length)
+        }
+        _ => None,
+    };
+
+    repo_relative.unwrap_or_else(|| {
+        path::shorten(
+            current_dir,
+            &config.path.shorten_options_for(current_dir),
+            repo_root.as_deref(),
         )
+    })
+}
+
+/// `[N]`, `[I]`, `[V]` for the vim input mode reported by Claude Code.
+pub fn format_vim_mode(mode: &str) -> String {
+    let mode = mode.trim().to_ascii_uppercase();
+    let color = match mode.as_str() {
+        "NORMAL" => "\x1b[34m",
+        "INSERT" => "\x1b[32m",
+        m if m.starts_with("VISUAL") => "\x1b[35m",
+        _ => "\x1b[90m",
     };
+    match mode.chars().next() {
+        Some(initial) => format!("{}[{}]\x1b[0m", color, initial),
+        None => String::new(),
+    }
+}
 
-    if !branch.is_empty() {
-        if display_dir.is_empty() {
+/// A badge for the permission mode: `⏸ plan` in blue, `⏵⏵ accept edits`
+/// in yellow, `⛨ bypass` in bold red, the raw name for modes added later,
+/// nothing for `default`.
+pub fn format_permission_mode(mode: &str, icons: &icons::IconsConfig) -> String {
+    match mode {
+        "default" | "" => String::new(),
+        "plan" => format!("\x1b[34m{}plan\x1b[0m", icons.prefix("plan")),
+        "acceptEdits" => format!(
+            "\x1b[33m{}accept edits\x1b[0m",
+            icons.prefix("accept_edits")
+        ),
+        "bypassPermissions" => format!("\x1b[1;31m{}bypass\x1b[0m", icons.prefix("bypass")),
+        other => format!("\x1b[90m{}\x1b[0m", other),
+    }
+}
+
+/// The session ID as configured: `1a2b3c4d`, the full ID, or
+/// `claude --resume <id>`.
+pub fn format_session_id(id: &str, mode: SessionIdMode) -> String {
+    let text = match mode {
+        SessionIdMode::Off => return String::new(),
+        SessionIdMode::Short => id.chars().take(8).collect(),
+        SessionIdMode::Full => id.to_string(),
+        SessionIdMode::Resume => format!("claude --resume {}", id),
+    };
+    format!("\x1b[90m{}\x1b[0m", text)
+}
+
+/// Runs the contrast post-processor when `theme.min_contrast` is set.
+pub fn apply_contrast(line: &str, config: &Config) -> String {
+    match theme::Rgb::from_hex(&config.theme.background) {
+        Some(background) if config.theme.min_contrast > 1.0 => {
+            theme::enforce_contrast(line, background, config.theme.min_contrast)
+        }
+        _ => line.to_string(),
+    }
+}
+
+/// Adds the configured leading/trailing padding, fitting the line itself into
+/// whatever terminal width remains.
+pub fn apply_padding(line: &str, config: &Config) -> String {
+    let left = config.layout.padding_left;
+    let right = config.layout.padding_right;
+    let line = fit_to_terminal(line, left + right);
+    format!("{}{}{}", " ".repeat(left), line, " ".repeat(right))
+}
+
+/// Truncates the rendered line to `$COLUMNS` cells (less `reserved`) when the
+/// terminal width is known, measuring with double-width and Nerd Font glyphs
+/// in mind.
+pub fn fit_to_terminal(line: &str, reserved: usize) -> String {
+    match std::env::var("COLUMNS")
+        .ok()
+        .and_then(|c| c.trim().parse::<usize>().ok())
+    {
+        Some(columns) if columns > 0 => {
+            width::truncate_to_width(line, columns.saturating_sub(reserved))
+        }
+        _ => line.to_string(),
+    }
+}
+
+/// The cost segment: dollars in `api` mode, or the share of the plan's
+/// monthly price in `max5`/`max20` mode. Colored by `thresholds.cost` (in
+/// dollars, or percent in plan modes; green below $5, yellow below $20, red
+/// above by default), and bold red when `over_budget`. The per-model
+/// `breakdown` and `rolling` window follow the dollar figure and are dropped
+/// in plan modes. With a `format.currency`, the amount is converted and its
+/// symbol replaces the icon; the thresholds stay in dollars.
+pub fn format_cost_segment(
+    total_cost: f64,
+    breakdown: &str,
+    rolling: &str,
+    over_budget: bool,
+    config: &Config,
+    numbers: &format::NumberFormat,
+) -> String {
+    let icon = config.icons.prefix("cost");
+    let mode = config.cost.mode;
+    let band = |value: f64| {
+        if over_budget {
+            "\x1b[1;31m".to_string()
+        } else {
+            theme::band_color(value, &config.thresholds.cost, &config.theme.cost_colors)
+        }
+    };
+
+    match mode.plan_price_usd() {
+        Some(price) => {
+            let pct = total_cost * 100.0 / price;
             format!(
-                "\x1b[38;5;12m\u{f02a2} \x1b[32m{}{}\x1b[0m{}",
-                branch, lines_changed, components_str
+                "\x1b[38;5;3m{}{}{}%\x1b[0m \x1b[90mof {}\x1b[0m",
+                icon,
+                band(pct),
+                numbers.localize(&format!("{:.1}", pct)),
+                mode.plan_name()
             )
-        } else {
+        }
+        None => {
+            let (symbol, suffix) = match &numbers.currency {
+                Some(currency) if currency.after => {
+                    (String::new(), format!(" {}", currency.symbol))
+                }
+                Some(currency) => (currency.symbol.clone(), String::new()),
+                None => (icon, String::new()),
+            };
             format!(
-                "\x1b[36m{}\x1b[0m \x1b[38;5;12m\u{f02a2} \x1b[32m{}{}\x1b[0m{}",
-                display_dir.trim_end(),
-                branch,
-                lines_changed,
-                components_str
+                "\x1b[38;5;3m{}{}{}{}\x1b[0m{}{}",
+                symbol,
+                band(total_cost),
+                numbers.amount(total_cost),
+                suffix,
+                breakdown,
+                rolling
             )
         }
-    } else {
-        format!(
-            "\x1b[36m{}\x1b[0m{}",
-            display_dir.trim_end(),
-            components_str
-        )
     }
 }
 
-pub fn read_input() -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-    let mut buffer = String::new();
-    io::stdin().read_to_string(&mut buffer)?;
-    Ok(serde_json::from_str(&buffer)?)
+/// Prices the session's token totals from `context_window` when the input has
+/// no `cost.total_cost_usd` (e.g. behind some gateways).
+pub fn estimate_cost(input: &serde_json::Value, model_id: &str, config: &Config) -> Option<f64> {
+    let pricing = models::pricing_for(model_id, &config.models.aliases)?;
+    let ctx = input.get("context_window")?;
+    let input_tokens = ctx.get("total_input_tokens").and_then(as_u64_lenient)?;
+    let output_tokens = ctx
+        .get("total_output_tokens")
+        .and_then(as_u64_lenient)
+        .unwrap_or(0);
+    Some(pricing.cost(input_tokens, output_tokens, 0, 0))
+}
+
+pub fn read_input() -> Result<serde_json::Value, StatuslineError> {
+    StatuslineInput::from_reader(io::stdin().lock()).map(StatuslineInput::into_value)
 }
 
+/// The user's home directory (`$HOME` on Unix, the profile directory on
+/// Windows), or `/` when it cannot be determined.
+pub fn home_dir() -> String {
+    dirs::home_dir()
+        .map(|h| h.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "/".to_string())
+}
 
-pub fn get_git_branch(working_dir: &str) -> String {
-    let output = Command::new("git")
-        .args(["rev-parse", "--abbrev-ref", "HEAD"])
-        .current_dir(working_dir)
-        .output();
+/// `$<xdg_var>` when set, on every platform so XDG setups on macOS keep
+/// working; else the platform directory from `dirs` (`~/Library/Caches`,
+/// `%LOCALAPPDATA%`, ...); else `~/<fallback>`.
+pub fn base_dir(xdg_var: &str, platform: Option<PathBuf>, fallback: &str) -> PathBuf {
+    match std::env::var(xdg_var) {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => platform.unwrap_or_else(|| Path::new(&home_dir()).join(fallback)),
+    }
+}
 
-    match output {
-        Ok(output) if output.status.success() => {
-            String::from_utf8_lossy(&output.stdout).trim().to_string()
-        }
-        _ => String::new(),
+/// True when `name` matches one of the glob `patterns` (`*prod*`).
+pub fn matches_any(patterns: &[String], name: &str) -> bool {
+    patterns
+        .iter()
+        .filter_map(|p| glob::Pattern::new(p).ok())
+        .any(|p| p.matches(name))
+}
+
+/// Expands a leading `~` to the home directory.
+pub fn expand_home(path: &str) -> PathBuf {
+    if path == "~" {
+        PathBuf::from(home_dir())
+    } else if let Some(rest) = path.strip_prefix("~/") {
+        Path::new(&home_dir()).join(rest)
+    } else {
+        PathBuf::from(path)
     }
 }
 
-pub fn is_git_repo(dir: &str) -> bool {
-    let output = Command::new("git")
-        .args(["rev-parse", "--is-inside-work-tree"])
-        .current_dir(dir)
-        .output();
+/// True when `dir` is the top level of a repository that sits directly inside
+/// one of `project_roots`, e.g. `~/Projects/{repo}`.
+pub fn is_standard_project_dir(
+    dir: impl AsRef<Path>,
+    project_roots: &[String],
+    git: &GitContext,
+) -> bool {
+    let dir = dir.as_ref();
+    let Some(toplevel) = get_git_toplevel(dir, git) else {
+        return false;
+    };
+    if toplevel != dir {
+        return false;
+    }
+
+    match toplevel.parent() {
+        Some(parent) => project_roots.iter().any(|root| expand_home(root) == parent),
+        None => false,
+    }
+}
 
-    matches!(output, Ok(output) if output.status.success() &&
-             String::from_utf8_lossy(&output.stdout).trim() == "true")
+/// Time between the first and last timestamped transcript entries.
+pub fn get_session_duration(transcript_path: Option<&str>) -> Option<Duration> {
+    let (first, last) = session_timestamps(transcript_path)?;
+    Some(Duration::from_millis((last - first).max(0) as u64))
 }
 
-pub fn home_dir() -> String {
-    std::env::var("HOME").unwrap_or_else(|_| "/".to_string())
+/// Milliseconds between the first and last timestamped transcript entries.
+pub fn get_session_duration_ms(transcript_path: Option<&str>) -> Option<i64> {
+    session_timestamps(transcript_path).map(|(first, last)| last - first)
 }
 
-pub fn get_session_duration(transcript_path: Option<&str>) -> Option<String> {
+/// Unix milliseconds of the first and last timestamped transcript entries.
+pub fn session_timestamps(transcript_path: Option<&str>) -> Option<(i64, i64)> {
     let transcript_path = transcript_path?;
     if !Path::new(transcript_path).exists() {
         return None;
@@ -258,32 +788,100 @@ pub fn get_session_duration(transcript_path: Option<&str>) -> Option<String> {
     }
 
     if let (Some(first), Some(last)) = (first_ts, last_ts) {
-        let duration_ms = last - first;
-        let hours = duration_ms / (1000 * 60 * 60);
-        let minutes = (duration_ms % (1000 * 60 * 60)) / (1000 * 60);
-
-        if hours > 0 {
-            Some(format!("{}h{}m", hours, minutes))
-        } else if minutes > 0 {
-            Some(format!("{}m", minutes))
-        } else {
-            Some("<1m".to_string())
-        }
+        Some((first, last))
     } else {
         None
     }
 }
 
+/// `1h23m`, `47m`, `<1m`; with `seconds`, durations under an hour keep
+/// their seconds (`4m12s`, `45s`).
+pub fn format_duration(duration: Duration, seconds: bool) -> String {
+    let total = duration.as_secs();
+    let (hours, minutes, secs) = (total / 3600, total % 3600 / 60, total % 60);
+
+    if hours > 0 {
+        format!("{}h{}m", hours, minutes)
+    } else if seconds && minutes > 0 {
+        format!("{}m{:02}s", minutes, secs)
+    } else if seconds {
+        format!("{}s", secs)
+    } else if minutes > 0 {
+        format!("{}m", minutes)
+    } else {
+        "<1m".to_string()
+    }
+}
+
+/// `01:23:45`; hours grow past two digits rather than wrapping.
+pub fn format_duration_clock(duration: Duration) -> String {
+    let total = duration.as_secs();
+    format!(
+        "{:02}:{:02}:{:02}",
+        total / 3600,
+        total % 3600 / 60,
+        total % 60
+    )
+}
+
+/// `started 14:02`, with the weekday when the session started on an
+/// earlier day than `now` (`started Mon 14:02`).
+pub fn format_started(started_ms: i64, now: chrono::DateTime<chrono::Local>) -> String {
+    let Some(started) = chrono::DateTime::from_timestamp_millis(started_ms) else {
+        return String::new();
+    };
+    let started = started.with_timezone(&chrono::Local);
+    if started.date_naive() == now.date_naive() {
+        format!("started {}", started.format("%H:%M"))
+    } else {
+        format!("started {}", started.format("%a %H:%M"))
+    }
+}
+
+pub fn format_duration_ms(duration_ms: i64) -> String {
+    format_duration(Duration::from_millis(duration_ms.max(0) as u64), false)
+}
+
 pub fn parse_timestamp(timestamp: &serde_json::Value) -> Option<i64> {
     if let Some(ts_str) = timestamp.as_str() {
         chrono::DateTime::parse_from_rfc3339(ts_str)
             .map(|dt| dt.timestamp_millis())
             .ok()
+            .or_else(|| ts_str.trim().parse::<i64>().ok())
     } else {
         timestamp.as_i64()
     }
 }
 
+/// Reads a non-negative integer from a JSON number or a numeric string.
+/// Some proxies serialize token counts as `"12345"`, which `as_u64` rejects.
+pub fn as_u64_lenient(value: &serde_json::Value) -> Option<u64> {
+    match value {
+        serde_json::Value::Number(n) => n
+            .as_u64()
+            .or_else(|| n.as_f64().filter(|f| *f >= 0.0).map(|f| f as u64)),
+        serde_json::Value::String(s) => {
+            let s = s.trim();
+            s.parse::<u64>().ok().or_else(|| {
+                s.parse::<f64>()
+                    .ok()
+                    .filter(|f| f.is_finite() && *f >= 0.0)
+                    .map(|f| f as u64)
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Reads a float from a JSON number or a numeric string such as `"7.50"`.
+pub fn as_f64_lenient(value: &serde_json::Value) -> Option<f64> {
+    match value {
+        serde_json::Value::Number(n) => n.as_f64(),
+        serde_json::Value::String(s) => s.trim().parse::<f64>().ok().filter(|f| f.is_finite()),
+        _ => None,
+    }
+}
+
 pub fn format_cost(cost: f64) -> String {
     if cost < 0.01 {
         format!("{:.3}", cost)
@@ -292,9 +890,23 @@ pub fn format_cost(cost: f64) -> String {
     }
 }
 
+/// `in 12k · out 4.1k · cr 130k · cw 8.0k` from a `current_usage` object.
+pub fn format_token_breakdown(usage: &serde_json::Value) -> String {
+    context::ContextUsage::from_current_usage(usage, 0, context::ContextSource::Input)
+        .format_breakdown(&format::NumberFormat::default())
+}
+
+/// `8.0k`, `42k`, `412k`, `1M`, `1.2M`.
 pub fn format_tokens(tokens: u64) -> String {
     let k = tokens as f64 / 1000.0;
-    if k >= 100.0 {
+    if tokens >= 1_000_000 {
+        let m = tokens as f64 / 1_000_000.0;
+        if tokens.is_multiple_of(1_000_000) {
+            format!("{}M", m as u64)
+        } else {
+            format!("{:.1}M", m)
+        }
+    } else if k >= 100.0 {
         format!("{}k", k.round() as u64)
     } else if k >= 10.0 {
         format!("{:.0}k", k)
@@ -302,32 +914,3 @@ pub fn format_tokens(tokens: u64) -> String {
         format!("{:.1}k", k)
     }
 }
-
-pub fn fish_shorten_path(path: &str) -> String {
-    let home = home_dir();
-    let path = path.replace(&home, "~");
-
-    let parts: Vec<&str> = path.split('/').collect();
-    if parts.len() <= 1 {
-        return path;
-    }
-
-    let shortened: Vec<String> = parts
-        .iter()
-        .enumerate()
-        .map(|(i, part)| {
-            if i == parts.len() - 1 || part.is_empty() || *part == "~" {
-                part.to_string()
-            } else if part.starts_with('.') && part.len() > 1 {
-                format!(".{}", part.chars().nth(1).unwrap_or_default())
-            } else {
-                part.chars()
-                    .next()
-                    .map(|c| c.to_string())
-                    .unwrap_or_default()
-            }
-        })
-        .collect();
-
-    shortened.join("/")
-}
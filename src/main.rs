@@ -1,9 +1,117 @@
-use cc_statusline_rs::statusline;
-use std::env;
+use cc_statusline_rs::cli::{Cli, Command, Output, Shell, Sink};
+use cc_statusline_rs::hyperlink::HyperlinkMode;
+use cc_statusline_rs::theme::{ColorDepth, Rgb};
+use cc_statusline_rs::{debug, export, html, rendered_from_path, Config};
+use clap::Parser;
+use std::path::Path;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    let show_pr_status = !args.contains(&"--skip-pr-status".to_string());
+    let cli = Cli::parse();
 
-    print!("{}", statusline(show_pr_status));
+    let code = match cli.command {
+        Some(Command::Timer { action }) => cc_statusline_rs::timer::run_cli(&action),
+        Some(Command::Config { action }) => cc_statusline_rs::config_edit::run_cli(&action),
+        Some(Command::Doctor) => cc_statusline_rs::doctor::run_cli(),
+        Some(Command::Hook) => cc_statusline_rs::hooks::run_cli(),
+        Some(Command::Selftest(args)) => cc_statusline_rs::selftest::run_cli(&args),
+        Some(Command::Install(args)) => cc_statusline_rs::install::run_cli(&args),
+        Some(Command::Preview(args)) => cc_statusline_rs::preview::run_cli(&args),
+        Some(Command::Summarize(args)) => cc_statusline_rs::summary::run_cli(&args),
+        Some(Command::Post(args)) => cc_statusline_rs::post::run_cli(&args),
+        Some(Command::AggregateRefresh) => cc_statusline_rs::aggregate::run_cli(),
+        Some(Command::PrRefresh(args)) => cc_statusline_rs::pull_request::run_cli(&args),
+        Some(Command::Report(args)) => cc_statusline_rs::report::run_cli(&args),
+        #[cfg(feature = "dashboard")]
+        Some(Command::Dashboard) => {
+            if let Err(e) = cc_statusline_rs::dashboard::run() {
+                eprintln!("dashboard: {}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        // Generated from the CLI definition by build.rs.
+        Some(Command::Completions { shell }) => {
+            print!(
+                "{}",
+                match shell {
+                    Shell::Bash => include_str!(concat!(env!("OUT_DIR"), "/statusline.bash")),
+                    Shell::Zsh => include_str!(concat!(env!("OUT_DIR"), "/_statusline")),
+                    Shell::Fish => include_str!(concat!(env!("OUT_DIR"), "/statusline.fish")),
+                }
+            );
+            0
+        }
+        Some(Command::Man) => {
+            print!(
+                "{}",
+                include_str!(concat!(env!("OUT_DIR"), "/statusline.1"))
+            );
+            0
+        }
+        None => {
+            render(&cli);
+            return;
+        }
+    };
+    std::process::exit(code);
+}
+
+fn sink(sink: Sink) -> debug::Sink {
+    match sink {
+        Sink::File => debug::Sink::File,
+        Sink::Stderr => debug::Sink::Stderr,
+    }
+}
+
+fn render(cli: &Cli) {
+    if let Some(flag) = cli.debug {
+        debug::enable(sink(flag));
+    }
+    if let Some(flag) = cli.profile {
+        debug::enable_profile(sink(flag));
+    }
+
+    let mut config = Config::load();
+    config.skip_pr_status = cli.skip_pr_status;
+    if cli.compact {
+        config.layout.compact = true;
+    }
+    if cli.verbose_context {
+        config.layout.verbose_context = true;
+    }
+    if cli.short {
+        config.path.short = true;
+    }
+
+    if cli.output != Output::Ansi {
+        // Exports keep every color and link, and need whole lines rather
+        // than a diff against the last frame.
+        config.theme.color_depth = ColorDepth::Truecolor;
+        config.layout.hyperlinks = HyperlinkMode::Always;
+        config.layout.frame_diff = false;
+    }
+    if cli.output == Output::Prometheus {
+        // `git_dirty_files` comes from the working tree status.
+        config.git.status = true;
+    }
+
+    let rendered = rendered_from_path(cli.input.as_deref().unwrap_or(Path::new("-")), &config);
+    let line = &rendered.line;
+    let background = Rgb::from_hex(&config.theme.background).unwrap_or(Rgb(0, 0, 0));
+    match cli.output {
+        Output::Ansi => print!("{}", line),
+        Output::Html => print!("{}", html::to_html(line, background)),
+        Output::Svg => print!("{}", html::to_svg(line, background)),
+        Output::Prometheus => {
+            let attributes: Vec<(&str, &str)> = rendered
+                .attributes
+                .iter()
+                .map(|(key, value)| (*key, value.as_str()))
+                .collect();
+            print!(
+                "{}",
+                export::prometheus_exposition(&config.export, &rendered.metrics, &attributes)
+            )
+        }
+    }
 }
@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+
+/// When the price table below was last checked against Anthropic's published
+/// pricing (`YYYY-MM-DD`); `statusline doctor` warns once it gets old.
+pub const PRICING_UPDATED: &str = "2025-11-24";
+
+/// USD per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPricing {
+    pub input: f64,
+    pub output: f64,
+    pub cache_write: f64,
+    pub cache_read: f64,
+}
+
+impl ModelPricing {
+    pub fn cost(&self, input: u64, output: u64, cache_write: u64, cache_read: u64) -> f64 {
+        (input as f64 * self.input
+            + output as f64 * self.output
+            + cache_write as f64 * self.cache_write
+            + cache_read as f64 * self.cache_read)
+            / 1_000_000.0
+    }
+}
+
+const OPUS_4: ModelPricing = ModelPricing {
+    input: 15.0,
+    output: 75.0,
+    cache_write: 18.75,
+    cache_read: 1.5,
+};
+const OPUS_4_5: ModelPricing = ModelPricing {
+    input: 5.0,
+    output: 25.0,
+    cache_write: 6.25,
+    cache_read: 0.5,
+};
+const SONNET: ModelPricing = ModelPricing {
+    input: 3.0,
+    output: 15.0,
+    cache_write: 3.75,
+    cache_read: 0.3,
+};
+const HAIKU_4_5: ModelPricing = ModelPricing {
+    input: 1.0,
+    output: 5.0,
+    cache_write: 1.25,
+    cache_read: 0.1,
+};
+const HAIKU_3_5: ModelPricing = ModelPricing {
+    input: 0.8,
+    output: 4.0,
+    cache_write: 1.0,
+    cache_read: 0.08,
+};
+const HAIKU_3: ModelPricing = ModelPricing {
+    input: 0.25,
+    output: 1.25,
+    cache_write: 0.3,
+    cache_read: 0.03,
+};
+
+pub const DEFAULT_CONTEXT_WINDOW: u64 = 200_000;
+pub const EXTENDED_CONTEXT_WINDOW: u64 = 1_000_000;
+
+/// Reduces the model IDs reported by gateways and cloud providers to the
+/// canonical Anthropic form, e.g.
+///
+/// - `anthropic/claude-sonnet-4` → `claude-sonnet-4`
+/// - `us.anthropic.claude-opus-4-1-20250805-v1:0` → `claude-opus-4-1`
+/// - `claude-3.5-sonnet@20241022` → `claude-3-5-sonnet`
+///
+/// A trailing `[1m]` marker is kept since it changes the context window.
+pub fn normalize_model_id(id: &str) -> String {
+    let mut id = id.trim().to_ascii_lowercase();
+
+    let extended = id.ends_with("[1m]");
+    if extended {
+        id.truncate(id.len() - "[1m]".len());
+    }
+
+    if let Some(pos) = id.rfind('/') {
+        id = id[pos + 1..].to_string();
+    }
+    if let Some(pos) = id.find("anthropic.") {
+        id = id[pos + "anthropic.".len()..].to_string();
+    }
+    for sep in ['@', ':'] {
+        if let Some(pos) = id.find(sep) {
+            id.truncate(pos);
+        }
+    }
+    if let Some(stripped) = id.strip_suffix("-v1").or_else(|| id.strip_suffix("-v2")) {
+        id = stripped.to_string();
+    }
+    if let Some(pos) = id.rfind('-') {
+        let suffix = &id[pos + 1..];
+        if suffix.len() == 8 && suffix.chars().all(|c| c.is_ascii_digit()) {
+            id.truncate(pos);
+        }
+    }
+    if let Some(stripped) = id.strip_suffix("-latest") {
+        id = stripped.to_string();
+    }
+
+    id = id.replace('.', "-");
+    if extended {
+        id.push_str("[1m]");
+    }
+    id
+}
+
+/// Resolves configured aliases (matched against both the raw and the
+/// normalized ID) before normalizing.
+pub fn resolve_model_id(id: &str, aliases: &HashMap<String, String>) -> String {
+    if let Some(target) = aliases.get(id) {
+        return normalize_model_id(target);
+    }
+    let normalized = normalize_model_id(id);
+    match aliases.get(&normalized) {
+        Some(target) => normalize_model_id(target),
+        None => normalized,
+    }
+}
+
+pub fn pricing_for(model_id: &str, aliases: &HashMap<String, String>) -> Option<ModelPricing> {
+    let id = resolve_model_id(model_id, aliases);
+    let id = id.trim_end_matches("[1m]");
+
+    if id.contains("opus") {
+        if ["opus-4-5", "opus-4-6", "opus-4-7"]
+            .iter()
+            .any(|v| id.contains(v))
+        {
+            Some(OPUS_4_5)
+        } else {
+            Some(OPUS_4)
+        }
+    } else if id.contains("sonnet") {
+        Some(SONNET)
+    } else if id.contains("haiku") {
+        if id.contains("haiku-4") {
+            Some(HAIKU_4_5)
+        } else if id.contains("3-5-haiku") || id.contains("haiku-3-5") {
+            Some(HAIKU_3_5)
+        } else {
+            Some(HAIKU_3)
+        }
+    } else {
+        None
+    }
+}
+
+/// Short label for breakdowns: `Opus`, `Sonnet`, `Haiku`, or the resolved ID
+/// for models without a known family.
+pub fn family_name(model_id: &str, aliases: &HashMap<String, String>) -> String {
+    let id = resolve_model_id(model_id, aliases);
+    for family in ["opus", "sonnet", "haiku"] {
+        if id.contains(family) {
+            return format!("{}{}", family[..1].to_ascii_uppercase(), &family[1..]);
+        }
+    }
+    id
+}
+
+pub fn context_window_for(model_id: &str, aliases: &HashMap<String, String>) -> u64 {
+    let id = resolve_model_id(model_id, aliases);
+    if id.ends_with("[1m]") || id.ends_with("-1m") {
+        EXTENDED_CONTEXT_WINDOW
+    } else {
+        DEFAULT_CONTEXT_WINDOW
+    }
+}
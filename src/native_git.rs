@@ -0,0 +1,56 @@
+//! In-process git queries for the `native-git` feature. Each returns `None`
+//! when gix can't answer, and the caller falls back to the `git` subprocess.
+
+use crate::git::HeadCommit;
+use std::path::{Path, PathBuf};
+
+fn open(dir: &Path) -> Option<gix::Repository> {
+    gix::discover(dir).ok()
+}
+
+/// Whether `dir` is inside a work tree; `Some(false)` for bare repositories
+/// and when there is no repository at or above `dir`.
+pub fn is_git_repo(dir: &Path) -> Option<bool> {
+    match gix::discover(dir) {
+        Ok(repo) => Some(repo.workdir().is_some()),
+        Err(gix::discover::Error::Discover(_)) => Some(false),
+        Err(_) => None,
+    }
+}
+
+/// The short branch name, or `HEAD` when detached.
+pub fn branch(dir: &Path) -> Option<String> {
+    let repo = open(dir)?;
+    Some(match repo.head_name().ok()? {
+        Some(name) => name.shorten().to_string(),
+        None => "HEAD".to_string(),
+    })
+}
+
+pub fn toplevel(dir: &Path) -> Option<PathBuf> {
+    open(dir)?.workdir().map(Path::to_path_buf)
+}
+
+/// Commits reachable from `left` but not `right`, and the reverse.
+pub fn ahead_behind(dir: &Path, left: &str, right: &str) -> Option<(u64, u64)> {
+    let repo = open(dir)?;
+    let left = repo.rev_parse_single(left).ok()?.detach();
+    let right = repo.rev_parse_single(right).ok()?.detach();
+    let count = |tip: gix::ObjectId, hidden: gix::ObjectId| {
+        repo.rev_walk([tip])
+            .with_hidden([hidden])
+            .all()
+            .ok()
+            .map(|walk| walk.filter_map(Result::ok).count() as u64)
+    };
+    Some((count(left, right)?, count(right, left)?))
+}
+
+pub fn head_commit(dir: &Path) -> Option<HeadCommit> {
+    let repo = open(dir)?;
+    let commit = repo.head_commit().ok()?;
+    Some(HeadCommit {
+        sha: commit.id().shorten().ok()?.to_string(),
+        timestamp: commit.time().ok()?.seconds,
+    })
+}
@@ -0,0 +1,174 @@
+use crate::width::{char_width, display_width};
+use std::path::{Component, Path, PathBuf};
+
+/// Knobs for [`shorten_path`]. The defaults reproduce classic fish-style
+/// abbreviation: every component but the last reduced to one character.
+#[derive(Debug, Clone)]
+pub struct ShortenOptions {
+    /// Number of trailing components kept at full length.
+    pub full_components: usize,
+    /// Maximum display width of the result; leading components are dropped
+    /// (and replaced by `…`) beyond it. `0` disables the limit.
+    pub max_length: usize,
+    /// Keep the repository root directory name unabbreviated.
+    pub keep_repo_root: bool,
+    /// Only abbreviate when the unabbreviated path is wider than this many
+    /// cells. `0` always abbreviates.
+    pub shorten_over_width: usize,
+}
+
+impl Default for ShortenOptions {
+    fn default() -> Self {
+        ShortenOptions {
+            full_components: 1,
+            max_length: 0,
+            keep_repo_root: false,
+            shorten_over_width: 0,
+        }
+    }
+}
+
+pub fn fish_shorten_path(path: impl AsRef<Path>) -> String {
+    shorten(path.as_ref(), &ShortenOptions::default(), None)
+}
+
+/// Replaces a leading home directory with `~`. Paths are compared by
+/// component, so `/home/al` does not turn `/home/alice` into `~ice`.
+pub fn tilde(path: &Path) -> PathBuf {
+    let Some(home) = dirs::home_dir().filter(|h| h.parent().is_some()) else {
+        return path.to_path_buf();
+    };
+    match path.strip_prefix(&home) {
+        Ok(rest) => Path::new("~").join(rest),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// String form of [`tilde`].
+pub fn tilde_path(path: &str) -> String {
+    tilde(Path::new(path))
+        .to_string_lossy()
+        .trim_end_matches('/')
+        .to_string()
+}
+
+/// The components of `path` as display strings; the root is an empty
+/// component so that joining with `/` restores the leading slash. Non-UTF-8
+/// names are converted lossily.
+fn display_parts(path: &Path) -> Vec<String> {
+    let mut parts: Vec<String> = path
+        .components()
+        .filter_map(|c| match c {
+            Component::RootDir => Some(String::new()),
+            Component::CurDir => None,
+            other => Some(other.as_os_str().to_string_lossy().into_owned()),
+        })
+        .collect();
+    if parts == [""] {
+        // `/` alone
+        parts.push(String::new());
+    }
+    parts
+}
+
+/// String form of [`shorten`].
+pub fn shorten_path(path: &str, opts: &ShortenOptions, repo_root: Option<&Path>) -> String {
+    shorten(Path::new(path), opts, repo_root)
+}
+
+/// Abbreviates `path` for display as configured by `opts`, keeping
+/// `repo_root`'s own directory name whole when `keep_repo_root` is set.
+pub fn shorten(path: &Path, opts: &ShortenOptions, repo_root: Option<&Path>) -> String {
+    let path = tilde(path);
+    let parts = display_parts(&path);
+
+    if opts.shorten_over_width > 0 && display_width(&parts.join("/")) <= opts.shorten_over_width {
+        return parts.join("/");
+    }
+
+    let parts: Vec<&str> = parts.iter().map(String::as_str).collect();
+    if parts.len() <= 1 {
+        return fit_width(&parts, opts.max_length);
+    }
+
+    let keep_from = parts.len().saturating_sub(opts.full_components.max(1));
+    let repo_root_index = if opts.keep_repo_root {
+        repo_root.and_then(|root| {
+            let root = tilde(root);
+            path.starts_with(&root)
+                .then(|| display_parts(&root).len() - 1)
+        })
+    } else {
+        None
+    };
+
+    let shortened: Vec<String> = parts
+        .iter()
+        .enumerate()
+        .map(|(i, part)| {
+            if i >= keep_from || Some(i) == repo_root_index || part.is_empty() || *part == "~" {
+                part.to_string()
+            } else {
+                abbreviate(part)
+            }
+        })
+        .collect();
+
+    let shortened: Vec<&str> = shortened.iter().map(String::as_str).collect();
+    fit_width(&shortened, opts.max_length)
+}
+
+/// `dir` relative to the repository root, prefixed with the root's name:
+/// `/home/me/work/myrepo/src/api` → `myrepo/src/api`.
+pub fn repo_relative_path(dir: &Path, repo_root: &Path, max_length: usize) -> Option<String> {
+    let name = repo_root.file_name()?.to_string_lossy();
+    let rel = dir.strip_prefix(repo_root).ok()?;
+
+    let mut parts = vec![name.to_string()];
+    parts.extend(
+        rel.components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned()),
+    );
+    let parts: Vec<&str> = parts.iter().map(String::as_str).collect();
+    Some(fit_width(&parts, max_length))
+}
+
+fn abbreviate(part: &str) -> String {
+    let mut chars = part.chars();
+    match chars.next() {
+        Some('.') if part.len() > 1 => format!(".{}", chars.next().unwrap_or_default()),
+        Some(c) => c.to_string(),
+        None => String::new(),
+    }
+}
+
+/// Drops leading components until the joined path fits in `max` cells,
+/// marking the cut with `…`. The last component is cut from the left if it is
+/// too long on its own.
+fn fit_width(parts: &[&str], max: usize) -> String {
+    let joined = parts.join("/");
+    if max == 0 || display_width(&joined) <= max {
+        return joined;
+    }
+
+    for start in 1..parts.len() {
+        let candidate = format!("…/{}", parts[start..].join("/"));
+        if display_width(&candidate) <= max {
+            return candidate;
+        }
+    }
+
+    let last = parts.last().copied().unwrap_or_default();
+    let mut tail: Vec<char> = Vec::new();
+    let mut used = 1;
+    for c in last.chars().rev() {
+        let w = char_width(c);
+        if used + w > max {
+            break;
+        }
+        used += w;
+        tail.push(c);
+    }
+    tail.reverse();
+    format!("…{}", tail.into_iter().collect::<String>())
+}
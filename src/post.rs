@@ -0,0 +1,61 @@
+use crate::cli::PostArgs;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// POSTs `body` as JSON to `url` without waiting for the reply, so a slow
+/// or unreachable endpoint costs the render nothing: from a background
+/// `statusline post` process, or a thread when embedded.
+pub(crate) fn in_background(url: &str, body: &serde_json::Value, timeout: Duration) {
+    match crate::process::statusline_exe() {
+        Some(exe) => {
+            let child = Command::new(exe)
+                .args([
+                    "post",
+                    url,
+                    "--timeout-ms",
+                    &timeout.as_millis().to_string(),
+                ])
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn();
+            // Bodies are a few KB, well within the pipe's buffer, so this
+            // doesn't wait for the worker to read them.
+            if let Some(mut stdin) = child.ok().and_then(|mut child| child.stdin.take()) {
+                let _ = stdin.write_all(body.to_string().as_bytes());
+            }
+        }
+        None => {
+            let (url, body) = (url.to_string(), body.clone());
+            std::thread::spawn(move || send(&url, &body, timeout));
+        }
+    }
+}
+
+fn send(url: &str, body: &serde_json::Value, timeout: Duration) -> bool {
+    reqwest::blocking::Client::builder()
+        .timeout(timeout)
+        .build()
+        .and_then(|client| client.post(url).json(body).send())
+        .is_ok_and(|response| response.status().is_success())
+}
+
+/// `statusline post <url> [--timeout-ms N]`: the background worker started
+/// by [`in_background`]. POSTs the JSON read from stdin.
+pub fn run_cli(args: &PostArgs) -> i32 {
+    let timeout = Duration::from_millis(args.timeout_ms);
+
+    let mut input = String::new();
+    if std::io::stdin().read_to_string(&mut input).is_err() {
+        return 1;
+    }
+    let Ok(body) = serde_json::from_str(&input) else {
+        return 1;
+    };
+    if send(&args.url, &body, timeout) {
+        0
+    } else {
+        1
+    }
+}
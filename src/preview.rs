@@ -0,0 +1,232 @@
+use crate::cli::PreviewArgs;
+use crate::{render, Config, Registry};
+use serde_json::json;
+use std::path::Path;
+
+/// A named synthetic payload. `{repo}` and `{failing_repo}` in string values
+/// are replaced with scratch repositories created for the preview.
+pub struct Fixture {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub input: fn() -> serde_json::Value,
+}
+
+fn base(
+    model_id: &str,
+    model_name: &str,
+    used_tokens: u64,
+    window: u64,
+    cost: f64,
+) -> serde_json::Value {
+    json!({
+        "session_id": "preview",
+        "version": "2.0.14",
+        "model": {"id": model_id, "display_name": model_name},
+        "workspace": {"current_dir": "{repo}", "project_dir": "{repo}"},
+        "output_style": {"name": "default"},
+        "cost": {
+            "total_cost_usd": cost,
+            "total_duration_ms": 2_820_000,
+            "total_lines_added": 0,
+            "total_lines_removed": 0,
+        },
+        "context_window": {
+            "total_input_tokens": used_tokens * 3,
+            "total_output_tokens": used_tokens / 4,
+            "context_window_size": window,
+            "current_usage": {
+                "input_tokens": used_tokens / 10,
+                "output_tokens": used_tokens / 40,
+                "cache_creation_input_tokens": used_tokens / 10,
+                "cache_read_input_tokens": used_tokens - used_tokens / 5,
+            },
+        },
+    })
+}
+
+pub const FIXTURES: &[Fixture] = &[
+    Fixture {
+        name: "opus",
+        description: "Opus, fresh session",
+        input: || base("claude-opus-4-5", "Opus", 18_000, 200_000, 0.42),
+    },
+    Fixture {
+        name: "sonnet-1m",
+        description: "Sonnet with the 1M context window",
+        input: || base("claude-sonnet-4-5[1m]", "Sonnet", 412_000, 1_000_000, 6.10),
+    },
+    Fixture {
+        name: "haiku",
+        description: "Haiku, cheap and quick",
+        input: || base("claude-haiku-4-5", "Haiku", 40_000, 200_000, 0.08),
+    },
+    Fixture {
+        name: "high-context",
+        description: "context almost full",
+        input: || base("claude-opus-4-5", "Opus", 186_000, 200_000, 12.40),
+    },
+    Fixture {
+        name: "expensive",
+        description: "long, expensive session",
+        input: || base("claude-opus-4-1", "Opus", 120_000, 200_000, 48.75),
+    },
+    Fixture {
+        name: "dirty-repo",
+        description: "many uncommitted changes",
+        input: || {
+            let mut input = base("claude-sonnet-4-5", "Sonnet", 90_000, 200_000, 3.20);
+            input["cost"]["total_lines_added"] = json!(842);
+            input["cost"]["total_lines_removed"] = json!(120);
+            input
+        },
+    },
+    Fixture {
+        name: "failing-ci",
+        description: "build failing with diagnostics",
+        input: || {
+            let mut input = base("claude-sonnet-4-5", "Sonnet", 70_000, 200_000, 2.05);
+            input["workspace"] =
+                json!({"current_dir": "{failing_repo}", "project_dir": "{failing_repo}"});
+            input["cost"]["total_lines_added"] = json!(37);
+            input["cost"]["total_lines_removed"] = json!(4);
+            input
+        },
+    },
+    Fixture {
+        name: "plan-mode",
+        description: "plan mode, vim insert, subagent",
+        input: || {
+            let mut input = base("claude-opus-4-5", "Opus", 30_000, 200_000, 1.15);
+            input["permission_mode"] = json!("plan");
+            input["vim"] = json!({"mode": "INSERT"});
+            input["agent"] = json!({"name": "code-reviewer"});
+            input["output_style"] = json!({"name": "Explanatory"});
+            input
+        },
+    },
+    Fixture {
+        name: "no-workspace",
+        description: "no workspace in the payload",
+        input: || {
+            let mut input = base("claude-opus-4-5", "Opus", 10_000, 200_000, 0.05);
+            input.as_object_mut().map(|o| o.remove("workspace"));
+            input
+        },
+    },
+];
+
+fn git(dir: &Path, args: &[&str]) -> bool {
+    crate::git::command()
+        .args([
+            "-c",
+            "user.name=preview",
+            "-c",
+            "user.email=preview@example.com",
+        ])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .is_ok_and(|o| o.status.success())
+}
+
+/// Creates a repository on `branch` with one commit.
+fn make_repo(dir: &Path, branch: &str) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(dir.join("README.md"), "preview\n")?;
+    let ok = git(dir, &["init", "-q", "-b", branch])
+        && git(dir, &["add", "."])
+        && git(dir, &["commit", "-q", "-m", "init"]);
+    if ok {
+        Ok(())
+    } else {
+        Err(std::io::Error::other("git init failed"))
+    }
+}
+
+fn substitute(value: &mut serde_json::Value, repo: &str, failing_repo: &str) {
+    match value {
+        serde_json::Value::String(s) => {
+            *s = s
+                .replace("{failing_repo}", failing_repo)
+                .replace("{repo}", repo);
+        }
+        serde_json::Value::Array(items) => items
+            .iter_mut()
+            .for_each(|v| substitute(v, repo, failing_repo)),
+        serde_json::Value::Object(map) => map
+            .values_mut()
+            .for_each(|v| substitute(v, repo, failing_repo)),
+        _ => {}
+    }
+}
+
+/// `statusline preview [<fixture>...] [--list]`: renders built-in sample
+/// payloads with the current config, so themes and settings can be tried
+/// without a live session.
+pub fn run_cli(args: &PreviewArgs) -> i32 {
+    if args.list {
+        for fixture in FIXTURES {
+            println!("{:<14} {}", fixture.name, fixture.description);
+        }
+        return 0;
+    }
+
+    let selected: Vec<&Fixture> = if args.fixtures.is_empty() {
+        FIXTURES.iter().collect()
+    } else {
+        let mut selected = Vec::new();
+        for name in &args.fixtures {
+            match FIXTURES.iter().find(|f| f.name == name) {
+                Some(fixture) => selected.push(fixture),
+                None => {
+                    eprintln!("preview: unknown fixture '{}' (see --list)", name);
+                    return 2;
+                }
+            }
+        }
+        selected
+    };
+
+    let scratch = match crate::selftest::isolated_scratch_dir("preview") {
+        Ok(dir) => dir,
+        Err(e) => {
+            eprintln!("preview: {}", e);
+            return 1;
+        }
+    };
+    let repo = scratch.join("my-project");
+    let failing_repo = scratch.join("my-service");
+    let repos_ok =
+        make_repo(&repo, "feature/preview").is_ok() && make_repo(&failing_repo, "main").is_ok();
+    if repos_ok {
+        let statusbar = failing_repo.join(".git/statusbar");
+        let _ = std::fs::create_dir_all(statusbar.join("diagnostics"));
+        let _ = std::fs::write(statusbar.join("build.json"), r#"{"status": "fail"}"#);
+        let _ = std::fs::write(
+            statusbar.join("diagnostics/clippy.json"),
+            r#"{"errors": 3, "warnings": 12}"#,
+        );
+    } else {
+        eprintln!("preview: git unavailable, repository segments are not shown");
+    }
+
+    let config = Config::load();
+    let registry = Registry::default();
+    let width = selected.iter().map(|f| f.name.len()).max().unwrap_or(0);
+    for fixture in selected {
+        let mut input = (fixture.input)();
+        substitute(
+            &mut input,
+            &repo.to_string_lossy(),
+            &failing_repo.to_string_lossy(),
+        );
+        println!(
+            "\x1b[90m{:<width$}\x1b[0m  {}",
+            fixture.name,
+            render(&input, &config, &registry)
+        );
+    }
+
+    let _ = std::fs::remove_dir_all(&scratch);
+    0
+}
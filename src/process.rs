@@ -0,0 +1,84 @@
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// The running executable when it is the `statusline` binary. Work that
+/// should outlive the render is handed to it as a hidden subcommand, since
+/// the binary exits right after printing the line and would cut a thread
+/// short. A program embedding the library keeps running, so it uses a
+/// thread instead of starting itself.
+pub(crate) fn statusline_exe() -> Option<PathBuf> {
+    std::env::current_exe()
+        .ok()
+        .filter(|exe| exe.file_stem().is_some_and(|stem| stem == "statusline"))
+}
+
+/// Waits up to `timeout` for `child` to exit, killing it when it doesn't.
+/// `None` when it was killed or its status couldn't be read.
+pub(crate) fn wait_within(child: &mut Child, timeout: Duration) -> Option<ExitStatus> {
+    let started = Instant::now();
+    // Short commands are noticed quickly; long ones aren't polled hard.
+    let mut poll = Duration::from_millis(1);
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Some(status),
+            Ok(None) if started.elapsed() < timeout => {
+                std::thread::sleep(poll);
+                poll = (poll * 2).min(Duration::from_millis(20));
+            }
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    }
+}
+
+/// Stdout of `command` when it runs successfully, or `Ok(None)` when it
+/// can't be started or fails. Killed after `timeout`, which is `Err`.
+///
+/// The whole call, reading included, stays within `timeout`: a background
+/// process the command left behind may keep stdout open long after the
+/// command exits, so whatever arrived by then is its output.
+pub(crate) fn output_within(
+    command: &mut Command,
+    timeout: Duration,
+) -> Result<Option<Vec<u8>>, ()> {
+    let started = Instant::now();
+    let Ok(mut child) = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+    else {
+        return Ok(None);
+    };
+
+    // Read on a thread so a large output can't block on a full pipe while
+    // we wait for it to exit. The thread isn't joined: it ends once the
+    // last writer closes the pipe, however long that takes.
+    let Some(mut stdout) = child.stdout.take() else {
+        let _ = child.kill();
+        let _ = child.wait();
+        return Ok(None);
+    };
+    let (sender, chunks) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut buffer = [0u8; 8192];
+        while let Ok(n @ 1..) = stdout.read(&mut buffer) {
+            if sender.send(buffer[..n].to_vec()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let status = wait_within(&mut child, timeout).ok_or(())?;
+    let mut output = Vec::new();
+    while let Ok(chunk) = chunks.recv_timeout(timeout.saturating_sub(started.elapsed())) {
+        output.extend_from_slice(&chunk);
+    }
+    Ok(status.success().then_some(output))
+}
@@ -0,0 +1,78 @@
+use crate::remote::RemoteRepo;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// One `[[projects]]` entry: a short label shown in front of the path for
+/// every repository matching one of its patterns, so the client or team a
+/// checkout belongs to is visible at a glance.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectLabel {
+    /// Text of the chip, e.g. `ACME`.
+    pub label: String,
+    /// A color name, 256-color index or `#rrggbb` as for custom segments.
+    /// Defaults to magenta.
+    pub color: Option<String>,
+    /// Globs matched against the working directory, the project directory
+    /// and their parents (`~/work/acme-*`).
+    pub paths: Vec<String>,
+    /// Globs matched against the origin remote as `host/owner/repo`
+    /// (`github.com/acme-corp/*`).
+    pub remotes: Vec<String>,
+}
+
+impl ProjectLabel {
+    fn matches_path(&self, dir: &Path) -> bool {
+        // `*` stops at `/`, so a pattern names a directory and the ancestor
+        // walk extends it to everything below.
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+        let patterns: Vec<glob::Pattern> = self
+            .paths
+            .iter()
+            .filter_map(|p| glob::Pattern::new(&crate::expand_home(p).to_string_lossy()).ok())
+            .collect();
+        dir.ancestors()
+            .any(|dir| patterns.iter().any(|p| p.matches_path_with(dir, options)))
+    }
+
+    fn matches_remote(&self, remote: &RemoteRepo) -> bool {
+        crate::matches_any(&self.remotes, &format!("{}/{}", remote.host, remote.path))
+    }
+}
+
+/// The first label, in config order, with a path pattern matching one of
+/// `dirs` or a remote pattern matching the origin. `origin` is only called
+/// once a label with remote patterns is reached.
+pub fn find<'a>(
+    labels: &'a [ProjectLabel],
+    dirs: &[&str],
+    origin: impl FnOnce() -> Option<RemoteRepo>,
+) -> Option<&'a ProjectLabel> {
+    let mut origin = Some(origin);
+    let mut remote = None;
+    labels.iter().find(|label| {
+        if dirs.iter().any(|dir| label.matches_path(Path::new(dir))) {
+            return true;
+        }
+        if label.remotes.is_empty() {
+            return false;
+        }
+        if let Some(lookup) = origin.take() {
+            remote = lookup();
+        }
+        remote.as_ref().is_some_and(|r| label.matches_remote(r))
+    })
+}
+
+/// The label in bold, in its color.
+pub fn format_label(label: &ProjectLabel) -> String {
+    let color = label
+        .color
+        .as_deref()
+        .and_then(crate::theme::color_code)
+        .unwrap_or_else(|| "\x1b[35m".to_string());
+    format!("{}\x1b[1m{}\x1b[0m", color, label.label)
+}
@@ -0,0 +1,94 @@
+use crate::anomaly::AnomalyDetector;
+
+/// A source of extra segments supplied by a host application embedding the
+/// renderer (an IDE's open file, an LSP diagnostics count, ...).
+pub trait DataProvider: Send + Sync {
+    /// Identifies the provider; registering a second provider with the same
+    /// name replaces the first.
+    fn name(&self) -> &str;
+
+    /// The segment to show for this render, or `None` to show nothing. The
+    /// string may contain ANSI colors like the built-in segments.
+    fn segment(&self, input: &serde_json::Value) -> Option<String>;
+}
+
+/// Providers whose segments are appended after the built-in ones, in
+/// registration order, and extra detectors for `[anomaly]`.
+#[derive(Default)]
+pub struct Registry {
+    providers: Vec<Box<dyn DataProvider>>,
+    detectors: Vec<Box<dyn AnomalyDetector>>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Registry::default()
+    }
+
+    pub fn register_provider(&mut self, provider: Box<dyn DataProvider>) {
+        match self
+            .providers
+            .iter_mut()
+            .find(|p| p.name() == provider.name())
+        {
+            Some(existing) => *existing = provider,
+            None => self.providers.push(provider),
+        }
+    }
+
+    /// Removes the provider called `name`; returns whether it was registered.
+    pub fn unregister_provider(&mut self, name: &str) -> bool {
+        let before = self.providers.len();
+        self.providers.retain(|p| p.name() != name);
+        self.providers.len() != before
+    }
+
+    pub fn provider_names(&self) -> Vec<&str> {
+        self.providers.iter().map(|p| p.name()).collect()
+    }
+
+    /// Adds a detector run on each turn while `[anomaly]` is enabled;
+    /// registering a second detector with the same name replaces the first.
+    pub fn register_detector(&mut self, detector: Box<dyn AnomalyDetector>) {
+        match self
+            .detectors
+            .iter_mut()
+            .find(|d| d.name() == detector.name())
+        {
+            Some(existing) => *existing = detector,
+            None => self.detectors.push(detector),
+        }
+    }
+
+    /// Removes the detector called `name`; returns whether it was registered.
+    pub fn unregister_detector(&mut self, name: &str) -> bool {
+        let before = self.detectors.len();
+        self.detectors.retain(|d| d.name() != name);
+        self.detectors.len() != before
+    }
+
+    pub fn detectors(&self) -> Vec<&dyn AnomalyDetector> {
+        self.detectors.iter().map(|d| d.as_ref()).collect()
+    }
+
+    /// Non-empty segments from every provider for this render. A provider
+    /// that panics shows a placeholder instead.
+    pub fn segments(&self, input: &serde_json::Value) -> Vec<String> {
+        self.named_segments(input)
+            .into_iter()
+            .map(|(_, segment)| segment)
+            .collect()
+    }
+
+    /// [`Registry::segments`] paired with the provider names.
+    pub fn named_segments(&self, input: &serde_json::Value) -> Vec<(&str, String)> {
+        self.providers
+            .iter()
+            .filter_map(|p| {
+                let segment = crate::error::guard(p.name(), || p.segment(input))
+                    .unwrap_or_else(|e| Some(e.placeholder()))?;
+                (!segment.is_empty()).then(|| (p.name(), segment))
+            })
+            .collect()
+    }
+}
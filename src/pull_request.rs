@@ -0,0 +1,147 @@
+use crate::cli::PrRefreshArgs;
+use crate::git::GitDirs;
+use crate::remote::RemoteRepo;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// How long `gh` may take to answer.
+const GH_TIMEOUT: Duration = Duration::from_secs(10);
+/// A lookup that hasn't finished after this long is assumed dead, and the
+/// next render may start another one.
+const LOCK_STALE_AFTER: Duration = Duration::from_secs(60);
+
+/// The pull request of a branch, as reported by `gh pr view`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PullRequest {
+    pub number: u64,
+    /// `OPEN`, `CLOSED` or `MERGED`.
+    pub state: String,
+    #[serde(default)]
+    pub is_draft: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedPullRequest {
+    at: i64,
+    pull_request: Option<PullRequest>,
+}
+
+/// Cache entry for `branch`, shared by every worktree of the repository.
+fn cache_name(dirs: &GitDirs, branch: &str) -> String {
+    format!(
+        "pr-{}-{}",
+        crate::cache::cache_key(&dirs.common_dir.to_string_lossy()),
+        crate::cache::cache_key(branch)
+    )
+}
+
+/// The pull request of `branch` as of the last lookup. Once that is
+/// `refresh_secs` old, `gh` is asked again in the background and the old
+/// answer is served until it replies. `None` without a pull request, or
+/// until the first lookup finishes.
+pub fn lookup(
+    working_dir: &Path,
+    dirs: &GitDirs,
+    branch: &str,
+    refresh_secs: i64,
+) -> Option<PullRequest> {
+    let name = cache_name(dirs, branch);
+    let now = chrono::Utc::now().timestamp();
+    let cached = crate::cache::read_cache(&name)
+        .and_then(|data| serde_json::from_str::<CachedPullRequest>(&data).ok());
+    if cached
+        .as_ref()
+        .is_none_or(|cached| now - cached.at >= refresh_secs)
+        && crate::cache::try_lock(&name, LOCK_STALE_AFTER)
+    {
+        refresh_in_background(working_dir, dirs, branch);
+    }
+    cached.and_then(|cached| cached.pull_request)
+}
+
+/// A `statusline pr-refresh` process, or a thread when embedded.
+fn refresh_in_background(working_dir: &Path, dirs: &GitDirs, branch: &str) {
+    match crate::process::statusline_exe() {
+        Some(exe) => {
+            let spawned = Command::new(exe)
+                .arg("pr-refresh")
+                .arg(working_dir)
+                .arg(branch)
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn();
+            if spawned.is_err() {
+                crate::cache::unlock(&cache_name(dirs, branch));
+            }
+        }
+        None => {
+            let (working_dir, dirs, branch) =
+                (working_dir.to_path_buf(), dirs.clone(), branch.to_string());
+            std::thread::spawn(move || {
+                refresh(&working_dir, &dirs, &branch);
+                crate::cache::unlock(&cache_name(&dirs, &branch));
+            });
+        }
+    }
+}
+
+/// Asks `gh` for the pull request of `branch` and caches the answer, also
+/// when there is none. A failed `gh` (not installed, not logged in, offline)
+/// is cached as no pull request too, so it isn't retried on every render.
+pub fn refresh(working_dir: &Path, dirs: &GitDirs, branch: &str) -> Option<PullRequest> {
+    let mut command = Command::new("gh");
+    command
+        .args(["pr", "view", branch, "--json", "number,state,isDraft"])
+        .current_dir(working_dir)
+        .env("GH_PROMPT_DISABLED", "1");
+    let pull_request = crate::process::output_within(&mut command, GH_TIMEOUT)
+        .ok()
+        .flatten()
+        .and_then(|out| serde_json::from_slice::<PullRequest>(&out).ok());
+    let cached = CachedPullRequest {
+        at: chrono::Utc::now().timestamp(),
+        pull_request: pull_request.clone(),
+    };
+    if let Ok(data) = serde_json::to_string(&cached) {
+        let _ = crate::cache::write_cache(&cache_name(dirs, branch), &data);
+    }
+    pull_request
+}
+
+/// `statusline pr-refresh <dir> <branch>`: the background worker started by
+/// [`lookup`].
+pub fn run_cli(args: &PrRefreshArgs) -> i32 {
+    let Some(dirs) = crate::git::get_git_dirs(&args.dir, &Default::default()) else {
+        return 1;
+    };
+    let found = refresh(&args.dir, &dirs, &args.branch).is_some();
+    crate::cache::unlock(&cache_name(&dirs, &args.branch));
+    if found {
+        0
+    } else {
+        1
+    }
+}
+
+/// `#123`: green while open, gray as a draft, magenta once merged and red
+/// when closed. Links to the pull request on `repo` when given.
+pub fn format_pull_request(pull_request: &PullRequest, repo: Option<&RemoteRepo>) -> String {
+    let color = match pull_request.state.as_str() {
+        "MERGED" => "35",
+        "CLOSED" => "31",
+        _ if pull_request.is_draft => "90",
+        _ => "32",
+    };
+    let text = format!("#{}", pull_request.number);
+    let text = match repo {
+        Some(repo) => {
+            crate::hyperlink::hyperlink(&repo.pull_request_url(pull_request.number), &text)
+        }
+        None => text,
+    };
+    format!("\x1b[{}m{}\x1b[0m", color, text)
+}
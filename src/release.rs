@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+
+/// `[release]` config section: the version bump semantic-release would make
+/// from the conventional commits since the last tag.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReleaseConfig {
+    pub enabled: bool,
+}
+
+/// Semver component a release would increment, in increasing order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Bump {
+    pub fn name(self) -> &'static str {
+        match self {
+            Bump::Patch => "patch",
+            Bump::Minor => "minor",
+            Bump::Major => "major",
+        }
+    }
+}
+
+/// The bump a single commit message calls for under the Conventional
+/// Commits rules: `feat` is minor, `fix` and `perf` are patch, and a `!`
+/// after the type or a `BREAKING CHANGE` footer is major. Other types and
+/// non-conventional messages release nothing.
+pub fn commit_bump(message: &str) -> Option<Bump> {
+    let subject = message.lines().next().unwrap_or_default();
+    let (prefix, _) = subject.split_once(':')?;
+    let breaking_mark = prefix.ends_with('!');
+    let kind = prefix.trim_end_matches('!');
+    let kind = kind.split_once('(').map(|(k, _)| k).unwrap_or(kind);
+    if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+
+    let breaking_footer = message
+        .lines()
+        .skip(1)
+        .any(|l| l.starts_with("BREAKING CHANGE:") || l.starts_with("BREAKING-CHANGE:"));
+    if breaking_mark || breaking_footer {
+        return Some(Bump::Major);
+    }
+    match kind.to_ascii_lowercase().as_str() {
+        "feat" => Some(Bump::Minor),
+        "fix" | "perf" => Some(Bump::Patch),
+        _ => None,
+    }
+}
+
+/// The largest bump among `messages`.
+pub fn next_bump<'a>(messages: impl IntoIterator<Item = &'a str>) -> Option<Bump> {
+    messages.into_iter().filter_map(commit_bump).max()
+}
+
+/// `→ minor`: red for major, yellow for minor, green for patch.
+pub fn format_bump(bump: Bump) -> String {
+    let color = match bump {
+        Bump::Major => "\x1b[31m",
+        Bump::Minor => "\x1b[33m",
+        Bump::Patch => "\x1b[32m",
+    };
+    format!("{}\u{2192} {}\x1b[0m", color, bump.name())
+}
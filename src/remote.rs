@@ -0,0 +1,195 @@
+use crate::git::GitContext;
+use serde::{Deserialize, Serialize};
+
+/// `[repo]` config section: the `owner/repo` of the origin remote and, for
+/// forks, how they relate to the upstream repository.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RepoConfig {
+    pub enabled: bool,
+    /// Show the tracked remote and how far the upstream's default branch
+    /// is ahead of the fork's, when an upstream remote exists.
+    pub fork: bool,
+    /// Name of the remote pointing at the original repository.
+    pub upstream_remote: String,
+    /// Show the pull request of the current branch (`#123`), looked up with
+    /// `gh` in the background. GitHub only.
+    pub pr: bool,
+    /// How long a looked-up pull request is reused before asking `gh` again.
+    pub pr_refresh_secs: i64,
+}
+
+impl Default for RepoConfig {
+    fn default() -> Self {
+        RepoConfig {
+            enabled: false,
+            fork: false,
+            upstream_remote: "upstream".to_string(),
+            pr: false,
+            pr_refresh_secs: 300,
+        }
+    }
+}
+
+/// Code hosts whose web URLs we know how to build.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Forge {
+    GitHub,
+    GitLab,
+    Bitbucket,
+    /// Anything else; assumed to use GitHub's URL layout.
+    Other,
+}
+
+impl Forge {
+    /// Name of the host's icon in `[icons]`.
+    pub fn icon_name(self) -> &'static str {
+        match self {
+            Forge::GitHub => "github",
+            Forge::GitLab => "gitlab",
+            Forge::Bitbucket => "bitbucket",
+            Forge::Other => "repo",
+        }
+    }
+}
+
+/// A repository on a code host, parsed from a git remote URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteRepo {
+    pub host: String,
+    /// `owner/repo`, or `group/subgroup/repo` on GitLab.
+    pub path: String,
+    pub forge: Forge,
+}
+
+impl RemoteRepo {
+    /// Parses `git@host:owner/repo.git`, `ssh://git@host[:port]/owner/repo`
+    /// and `https://host/owner/repo.git`.
+    pub fn parse(url: &str) -> Option<RemoteRepo> {
+        let url = url.trim();
+        let (host, path) = if let Some((_, rest)) = url.split_once("://") {
+            let rest = rest.rsplit_once('@').map(|(_, r)| r).unwrap_or(rest);
+            let (host, path) = rest.split_once('/')?;
+            (host.split(':').next()?, path)
+        } else {
+            let rest = url.rsplit_once('@').map(|(_, r)| r).unwrap_or(url);
+            rest.split_once(':')?
+        };
+        let path = path.trim_matches('/').trim_end_matches(".git");
+        if host.is_empty() || !path.contains('/') {
+            return None;
+        }
+        let forge = if host == "github.com" || host.starts_with("github.") {
+            Forge::GitHub
+        } else if host.contains("gitlab") {
+            Forge::GitLab
+        } else if host.contains("bitbucket") {
+            Forge::Bitbucket
+        } else {
+            Forge::Other
+        };
+        Some(RemoteRepo {
+            host: host.to_string(),
+            path: path.to_string(),
+            forge,
+        })
+    }
+
+    pub fn web_url(&self) -> String {
+        format!("https://{}/{}", self.host, self.path)
+    }
+
+    pub fn branch_url(&self, branch: &str) -> String {
+        let branch = branch
+            .replace('%', "%25")
+            .replace('#', "%23")
+            .replace(' ', "%20");
+        match self.forge {
+            Forge::GitLab => format!("{}/-/tree/{}", self.web_url(), branch),
+            Forge::Bitbucket => format!("{}/branch/{}", self.web_url(), branch),
+            Forge::GitHub | Forge::Other => format!("{}/tree/{}", self.web_url(), branch),
+        }
+    }
+
+    pub fn pull_request_url(&self, number: u64) -> String {
+        match self.forge {
+            Forge::GitLab => format!("{}/-/merge_requests/{}", self.web_url(), number),
+            Forge::Bitbucket => format!("{}/pull-requests/{}", self.web_url(), number),
+            Forge::GitHub | Forge::Other => format!("{}/pull/{}", self.web_url(), number),
+        }
+    }
+}
+
+/// `<host icon> owner/repo`, linked to the repository's page with `link`.
+pub fn format_repo(repo: &RemoteRepo, icon: &str, link: bool) -> String {
+    let name = if link {
+        crate::hyperlink::hyperlink(&repo.web_url(), &repo.path)
+    } else {
+        repo.path.clone()
+    };
+    format!("\x1b[37m{}{}\x1b[0m", icon, name)
+}
+
+/// How a fork's checkout relates to the repository it was forked from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForkStatus {
+    /// Remote the current branch tracks, if any.
+    pub tracking_remote: Option<String>,
+    pub upstream_remote: String,
+    /// Commits on the upstream's default branch missing from origin's.
+    pub upstream_ahead: u64,
+}
+
+/// The fork status of the repository at `dir`, or `None` unless both
+/// `origin` and the upstream remote exist and point at different URLs.
+/// Works from the last fetch; nothing is fetched here.
+pub fn fork_status(dir: &str, config: &RepoConfig, git: &GitContext) -> Option<ForkStatus> {
+    let upstream = config.upstream_remote.as_str();
+    let remotes = crate::git::remotes(dir, git);
+    if !remotes.iter().any(|r| r == "origin") || !remotes.iter().any(|r| r == upstream) {
+        return None;
+    }
+    if crate::git::remote_url(dir, "origin", git)? == crate::git::remote_url(dir, upstream, git)? {
+        return None;
+    }
+
+    let tracking_remote = crate::git::tracking_ref(dir, git).and_then(|tracking| {
+        remotes
+            .iter()
+            .find(|r| tracking.starts_with(&format!("{}/", r)))
+            .cloned()
+    });
+    let upstream_ahead = match (
+        crate::git::remote_default_branch(dir, "origin", git),
+        crate::git::remote_default_branch(dir, upstream, git),
+    ) {
+        (Some(fork), Some(original)) => crate::git::ahead_behind(dir, &fork, &original, git)
+            .map(|(_, behind)| behind)
+            .unwrap_or(0),
+        _ => 0,
+    };
+
+    Some(ForkStatus {
+        tracking_remote,
+        upstream_remote: upstream.to_string(),
+        upstream_ahead,
+    })
+}
+
+/// `origin↑ upstream↓12`: the branch pushes to `origin`, and upstream's
+/// default branch has 12 commits the fork's lacks.
+pub fn format_fork(status: &ForkStatus) -> String {
+    let mut parts = Vec::new();
+    if let Some(remote) = &status.tracking_remote {
+        parts.push(format!("\x1b[90m{}\u{2191}\x1b[0m", remote));
+    }
+    if status.upstream_ahead > 0 {
+        parts.push(format!(
+            "\x1b[33m{}\u{2193}{}\x1b[0m",
+            status.upstream_remote, status.upstream_ahead
+        ));
+    } else if status.tracking_remote.as_deref() != Some(status.upstream_remote.as_str()) {
+        parts.push(format!("\x1b[90m{}\x1b[0m", status.upstream_remote));
+    }
+    parts.join(" ")
+}
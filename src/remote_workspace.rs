@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+
+/// `[remote_workspace]` config section: runs git on the machine that holds
+/// the workspace when Claude Code reports a directory of an SSH or
+/// devcontainer workspace, instead of querying a path that doesn't exist
+/// locally.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteWorkspaceConfig {
+    pub enabled: bool,
+    /// Runs a program on the remote machine; the git command line is
+    /// appended. E.g. `["ssh", "-o", "BatchMode=yes", "devbox"]` or
+    /// `["docker", "exec", "my-container"]`.
+    pub command: Vec<String>,
+    /// Globs of workspace directories that are always delegated. When
+    /// empty, directories that don't exist locally are.
+    pub paths: Vec<String>,
+    /// Shell-quote the appended arguments, as `ssh` joins them into one
+    /// remote shell command. Turn off for commands that pass arguments
+    /// through as is, like `docker exec`.
+    pub quote: bool,
+    /// How long one delegated git command may take, including the
+    /// connection.
+    pub timeout_ms: u64,
+}
+
+impl Default for RemoteWorkspaceConfig {
+    fn default() -> Self {
+        RemoteWorkspaceConfig {
+            enabled: false,
+            command: Vec::new(),
+            paths: Vec::new(),
+            quote: true,
+            timeout_ms: 2000,
+        }
+    }
+}
+
+impl RemoteWorkspaceConfig {
+    /// Whether git for `dir` runs through `command`.
+    pub fn applies_to(&self, dir: &Path) -> bool {
+        if !self.enabled || self.command.is_empty() {
+            return false;
+        }
+        if self.paths.is_empty() {
+            !dir.exists()
+        } else {
+            crate::matches_any(&self.paths, &dir.to_string_lossy())
+        }
+    }
+
+    /// `command` followed by `env GIT_OPTIONAL_LOCKS=0 ... git -C <dir>
+    /// <args>`, so the remote git runs as hardened as the local one.
+    pub fn git_command(&self, dir: &Path, args: &[&str]) -> Command {
+        let dir = dir.to_string_lossy();
+        let remote_args = [
+            "env",
+            "GIT_OPTIONAL_LOCKS=0",
+            "GIT_TERMINAL_PROMPT=0",
+            "git",
+            "-C",
+            &dir,
+        ]
+        .into_iter()
+        .chain(args.iter().copied())
+        .map(|arg| {
+            if self.quote {
+                shell_quote(arg)
+            } else {
+                arg.to_string()
+            }
+        });
+
+        let mut command = Command::new(&self.command[0]);
+        command.args(&self.command[1..]).args(remote_args);
+        command
+    }
+}
+
+/// `arg` as one word for a POSIX shell; plain words are left as they are.
+pub fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:@%+,".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
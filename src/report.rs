@@ -0,0 +1,243 @@
+use crate::cli::{ReportArgs, ReportBy};
+use crate::index::{self, SessionSnapshot};
+use crate::transcript;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Transcripts of the current user, read by the transcript-based reports.
+pub const LOCAL_TRANSCRIPTS: &str = "~/.claude/projects/*/*.jsonl";
+
+/// One line of a report table.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReportRow {
+    pub key: String,
+    pub sessions: usize,
+    /// Input, output and cache tokens; `0` for reports without token data.
+    pub tokens: u64,
+    pub cost_usd: f64,
+    /// Summed active span of the sessions (first to last message); `0` for
+    /// reports without timing data.
+    pub duration_ms: i64,
+}
+
+/// How transcript usage is grouped into rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grouping {
+    /// Local date of each message.
+    Day,
+    /// Directory name the session was started in.
+    Project,
+    /// Model family of each message.
+    Model,
+}
+
+pub(crate) fn project_name(dir: Option<&str>) -> String {
+    dir.and_then(|d| Path::new(d).file_name())
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// Cost per `project:branch`, summed over every recorded session.
+pub fn by_branch(sessions: &[SessionSnapshot]) -> Vec<ReportRow> {
+    let mut rows: BTreeMap<String, ReportRow> = BTreeMap::new();
+
+    for session in sessions {
+        let project = project_name(session.project_dir.as_deref());
+
+        for (branch, cost) in &session.branch_costs {
+            let key = format!("{}:{}", project, branch);
+            let row = rows.entry(key.clone()).or_insert_with(|| ReportRow {
+                key,
+                ..Default::default()
+            });
+            row.sessions += 1;
+            row.cost_usd += cost;
+        }
+    }
+
+    let mut rows: Vec<ReportRow> = rows.into_values().collect();
+    rows.sort_by(|a, b| b.cost_usd.total_cmp(&a.cost_usd));
+    rows
+}
+
+/// Aggregates every transcript in `files` (one session each) by `grouping`.
+/// Days are listed newest first, projects and models by cost.
+pub fn from_transcripts(
+    files: &[String],
+    grouping: Grouping,
+    aliases: &HashMap<String, String>,
+) -> Vec<ReportRow> {
+    let mut rows: BTreeMap<String, ReportRow> = BTreeMap::new();
+
+    for file in files {
+        let project = project_name(transcript::read_cwd(file).as_deref());
+        // First and last message time of this session within each row.
+        let mut spans: BTreeMap<String, (i64, i64)> = BTreeMap::new();
+
+        for entry in transcript::read_usage_entries(file) {
+            let key = match grouping {
+                Grouping::Day => match entry.timestamp_ms.and_then(crate::aggregate::local_date) {
+                    Some(day) => day,
+                    None => continue,
+                },
+                Grouping::Project => project.clone(),
+                Grouping::Model => match entry.model.as_deref() {
+                    Some(model) if !model.starts_with('<') => {
+                        crate::models::resolve_model_id(model, aliases)
+                    }
+                    _ => continue,
+                },
+            };
+
+            let row = rows.entry(key.clone()).or_insert_with(|| ReportRow {
+                key: key.clone(),
+                ..Default::default()
+            });
+            row.tokens += entry.input_tokens
+                + entry.output_tokens
+                + entry.cache_creation_tokens
+                + entry.cache_read_tokens;
+            row.cost_usd += transcript::entry_cost(&entry, None, aliases);
+
+            let ts = entry.timestamp_ms.unwrap_or_default();
+            let span = spans.entry(key).or_insert((ts, ts));
+            span.0 = span.0.min(ts);
+            span.1 = span.1.max(ts);
+        }
+
+        for (key, (first, last)) in spans {
+            if let Some(row) = rows.get_mut(&key) {
+                row.sessions += 1;
+                row.duration_ms += last - first;
+            }
+        }
+    }
+
+    let mut rows: Vec<ReportRow> = rows.into_values().collect();
+    match grouping {
+        Grouping::Day => rows.reverse(),
+        Grouping::Project | Grouping::Model => {
+            rows.sort_by(|a, b| b.cost_usd.total_cmp(&a.cost_usd))
+        }
+    }
+    rows
+}
+
+fn or_dash(value: String, present: bool) -> String {
+    if present {
+        value
+    } else {
+        "-".to_string()
+    }
+}
+
+/// Renders rows as an aligned plain-text table with a total line.
+pub fn format_table(heading: &str, rows: &[ReportRow]) -> String {
+    let key_width = rows
+        .iter()
+        .map(|r| crate::width::display_width(&r.key))
+        .chain([heading.len(), "total".len()])
+        .max()
+        .unwrap_or(0);
+
+    let line = |key: &str, sessions: String, tokens: String, cost: f64, duration: String| {
+        format!(
+            "{}{}  {:>8}  {:>8}  {:>10}  {:>8}\n",
+            key,
+            " ".repeat(key_width - crate::width::display_width(key)),
+            sessions,
+            tokens,
+            format!("${}", crate::format_cost(cost)),
+            duration
+        )
+    };
+
+    let mut out = format!(
+        "{:<key_width$}  {:>8}  {:>8}  {:>10}  {:>8}\n",
+        heading, "sessions", "tokens", "cost", "duration"
+    );
+    for row in rows {
+        out.push_str(&line(
+            &row.key,
+            row.sessions.to_string(),
+            or_dash(crate::format_tokens(row.tokens), row.tokens > 0),
+            row.cost_usd,
+            or_dash(
+                crate::format_duration_ms(row.duration_ms),
+                row.duration_ms > 0,
+            ),
+        ));
+    }
+
+    let tokens: u64 = rows.iter().map(|r| r.tokens).sum();
+    let duration: i64 = rows.iter().map(|r| r.duration_ms).sum();
+    out.push_str(&line(
+        "total",
+        String::new(),
+        or_dash(crate::format_tokens(tokens), tokens > 0),
+        rows.iter().map(|r| r.cost_usd).sum(),
+        or_dash(crate::format_duration_ms(duration), duration > 0),
+    ));
+    out
+}
+
+/// `statusline report [--by day|project|model|branch] [--aggregate] [--json]`.
+///
+/// `day`, `project` and `model` read the local transcripts, or every
+/// transcript matched by `[aggregate] transcript_globs` with `--aggregate`;
+/// `branch` uses the cost attribution recorded by renders. With `[history]`
+/// enabled, local reports query the history database instead.
+pub fn run_cli(args: &ReportArgs) -> i32 {
+    let aggregate = args.aggregate;
+    let by = args.by.unwrap_or(if aggregate {
+        ReportBy::Day
+    } else {
+        ReportBy::Branch
+    });
+    let (heading, grouping) = match by {
+        ReportBy::Day => ("day", Some(Grouping::Day)),
+        ReportBy::Project => ("project", Some(Grouping::Project)),
+        ReportBy::Model => ("model", Some(Grouping::Model)),
+        ReportBy::Branch => ("branch", None),
+    };
+
+    let config = crate::Config::load();
+    let rows = match grouping {
+        _ if config.history.enabled && !aggregate => {
+            match crate::history::report(&config.history, grouping, &config.models.aliases) {
+                Ok(rows) => rows,
+                Err(e) => {
+                    eprintln!("report: history: {}", e);
+                    return 1;
+                }
+            }
+        }
+        Some(grouping) => {
+            let globs = if aggregate {
+                config.aggregate.transcript_globs.clone()
+            } else {
+                vec![LOCAL_TRANSCRIPTS.to_string()]
+            };
+            from_transcripts(
+                &crate::aggregate::transcript_files(&globs),
+                grouping,
+                &config.models.aliases,
+            )
+        }
+        None => by_branch(&index::load_sessions()),
+    };
+
+    if args.json {
+        match serde_json::to_string_pretty(&rows) {
+            Ok(out) => println!("{}", out),
+            Err(e) => {
+                eprintln!("report: {}", e);
+                return 1;
+            }
+        }
+    } else {
+        print!("{}", format_table(heading, &rows));
+    }
+    0
+}
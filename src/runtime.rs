@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// `[runtime]` config section: the interpreter or toolchain builds in the
+/// current directory will use. Each language is opt-in.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuntimeConfig {
+    /// Active virtualenv (`$VIRTUAL_ENV`) or conda env.
+    pub python: bool,
+    /// Version pinned by `.nvmrc` or `package.json` `engines.node`.
+    pub node: bool,
+    /// Toolchain override from `rust-toolchain[.toml]` or `$RUSTUP_TOOLCHAIN`.
+    pub rust: bool,
+}
+
+impl RuntimeConfig {
+    pub fn any_enabled(&self) -> bool {
+        self.python || self.node || self.rust
+    }
+}
+
+/// A detected runtime, e.g. `node` / `20.11`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Runtime {
+    pub language: &'static str,
+    pub version: String,
+}
+
+fn env(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.trim().is_empty())
+}
+
+/// The nearest `name` in `dir` or one of its parents.
+fn find_upwards(dir: &Path, name: &str) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|d| d.join(name))
+        .find(|path| path.is_file())
+}
+
+/// The virtualenv's name, using the project directory for generic names
+/// like `.venv`; else `$CONDA_DEFAULT_ENV`.
+pub fn python_env() -> Option<String> {
+    if let Some(venv) = env("VIRTUAL_ENV") {
+        let path = Path::new(&venv);
+        let name = path.file_name()?.to_string_lossy();
+        let generic = matches!(name.as_ref(), ".venv" | "venv" | ".env" | "env");
+        return match path.parent().and_then(|p| p.file_name()) {
+            Some(project) if generic => Some(project.to_string_lossy().into_owned()),
+            _ => Some(name.into_owned()),
+        };
+    }
+    env("CONDA_DEFAULT_ENV")
+}
+
+/// `.nvmrc`, or `engines.node` of the nearest `package.json`.
+pub fn node_version(dir: &Path) -> Option<String> {
+    if let Some(path) = find_upwards(dir, ".nvmrc") {
+        let version = std::fs::read_to_string(path).ok()?;
+        return Some(version.trim().to_string()).filter(|v| !v.is_empty());
+    }
+    let data = std::fs::read_to_string(find_upwards(dir, "package.json")?).ok()?;
+    let package: serde_json::Value = serde_json::from_str(&data).ok()?;
+    package
+        .get("engines")?
+        .get("node")?
+        .as_str()
+        .map(str::to_string)
+}
+
+/// `$RUSTUP_TOOLCHAIN`, else the channel of the nearest `rust-toolchain.toml`
+/// or legacy `rust-toolchain` file. Directories without an override show
+/// nothing.
+pub fn rust_toolchain(dir: &Path) -> Option<String> {
+    if let Some(toolchain) = env("RUSTUP_TOOLCHAIN") {
+        return Some(toolchain);
+    }
+    let path = dir.ancestors().find_map(|d| {
+        ["rust-toolchain.toml", "rust-toolchain"]
+            .iter()
+            .map(|name| d.join(name))
+            .find(|path| path.is_file())
+    })?;
+    let data = std::fs::read_to_string(path).ok()?;
+    match data.parse::<toml::Table>() {
+        Ok(table) => table
+            .get("toolchain")?
+            .get("channel")?
+            .as_str()
+            .map(str::to_string),
+        Err(_) => Some(data.trim().to_string()).filter(|v| !v.is_empty()),
+    }
+}
+
+/// Every enabled runtime detected for `dir`.
+pub fn detect(config: &RuntimeConfig, dir: &Path) -> Vec<Runtime> {
+    let mut runtimes = Vec::new();
+    if config.python {
+        if let Some(version) = python_env() {
+            runtimes.push(Runtime {
+                language: "py",
+                version,
+            });
+        }
+    }
+    if config.node {
+        if let Some(version) = node_version(dir) {
+            runtimes.push(Runtime {
+                language: "node",
+                version,
+            });
+        }
+    }
+    if config.rust {
+        if let Some(version) = rust_toolchain(dir) {
+            runtimes.push(Runtime {
+                language: "rust",
+                version,
+            });
+        }
+    }
+    runtimes
+}
+
+/// `py myproject node 20 rust nightly`.
+pub fn format_runtimes(runtimes: &[Runtime]) -> String {
+    runtimes
+        .iter()
+        .map(|runtime| {
+            let color = match runtime.language {
+                "py" => "\x1b[33m",
+                "node" => "\x1b[32m",
+                _ => "\x1b[38;5;208m",
+            };
+            format!(
+                "\x1b[90m{} {}{}\x1b[0m",
+                runtime.language, color, runtime.version
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
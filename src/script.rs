@@ -0,0 +1,95 @@
+#[cfg(feature = "scripting")]
+use crate::width;
+use crate::StatuslineError;
+use serde::{Deserialize, Serialize};
+
+/// `[script]` config section: a [rhai](https://rhai.rs) script that receives
+/// the rendered segments and returns the final line. Needs the `scripting`
+/// cargo feature.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScriptConfig {
+    /// Path of the script; `~` is expanded. Unset leaves the line alone.
+    pub path: Option<String>,
+}
+
+/// Upper bound on script operations per render, so a runaway loop can't
+/// hang the prompt.
+#[cfg(feature = "scripting")]
+const MAX_OPERATIONS: u64 = 200_000;
+
+/// Runs the script at `path` with these variables in scope and returns the
+/// string it evaluates to:
+///
+/// - `segments`: array of `#{ name, text, plain }` (`path`, `git`, then the
+///   built-in, provider and custom segments in line order; `plain` has the
+///   colors stripped)
+/// - `input`: the Claude Code payload as a map
+/// - `line`: the line the statusline would print without the script
+///
+/// Also available: `strip_ansi(s)`, `width(s)` (terminal cells),
+/// `join(array, separator)` and `color(s, spec)` with the same color names as
+/// custom segments.
+#[cfg(feature = "scripting")]
+pub fn apply(
+    path: &str,
+    segments: &[(&str, String)],
+    input: &serde_json::Value,
+    line: &str,
+) -> Result<String, StatuslineError> {
+    let source = std::fs::read_to_string(crate::expand_home(path))
+        .map_err(|e| StatuslineError::Script(format!("{}: {}", path, e)))?;
+
+    let mut engine = rhai::Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.register_fn("strip_ansi", |s: &str| width::strip_ansi(s));
+    engine.register_fn("width", |s: &str| width::display_width(s) as i64);
+    engine.register_fn("join", |parts: &mut rhai::Array, separator: &str| {
+        parts
+            .iter()
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>()
+            .join(separator)
+    });
+    engine.register_fn(
+        "color",
+        |s: &str, spec: &str| match crate::theme::color_code(spec) {
+            Some(code) => format!("{}{}\x1b[0m", code, s),
+            None => s.to_string(),
+        },
+    );
+
+    let segments: rhai::Array = segments
+        .iter()
+        .map(|(name, text)| {
+            let mut map = rhai::Map::new();
+            map.insert("name".into(), (*name).into());
+            map.insert("text".into(), text.clone().into());
+            map.insert("plain".into(), width::strip_ansi(text).into());
+            map.into()
+        })
+        .collect();
+    let input =
+        rhai::serde::to_dynamic(input).map_err(|e| StatuslineError::Script(e.to_string()))?;
+
+    let mut scope = rhai::Scope::new();
+    scope.push("segments", segments);
+    scope.push("input", input);
+    scope.push("line", line.to_string());
+
+    engine
+        .eval_with_scope::<String>(&mut scope, &source)
+        .map_err(|e| StatuslineError::Script(format!("{}: {}", path, e)))
+}
+
+#[cfg(not(feature = "scripting"))]
+pub fn apply(
+    _path: &str,
+    _segments: &[(&str, String)],
+    _input: &serde_json::Value,
+    _line: &str,
+) -> Result<String, StatuslineError> {
+    Err(StatuslineError::Script(
+        "built without the `scripting` feature".to_string(),
+    ))
+}
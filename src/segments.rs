@@ -0,0 +1,1099 @@
+//! The built-in segments. Each one is a function of the render's
+//! [`SegmentContext`], which holds what several of them share (the parsed
+//! transcript, the checkout, the session's cost and trend state) and runs
+//! every segment under the render budget.
+
+use crate::build_status::BuildStatus;
+use crate::context::ContextUsage;
+use crate::deadline::Deadline;
+use crate::features::FeatureLevel;
+use crate::format::NumberFormat;
+use crate::git::{GitContext, GitDirs, WorkingTreeStatus};
+use crate::remote::RemoteRepo;
+use crate::state::SessionState;
+use crate::transcript::{SessionCost, Transcript};
+use crate::{
+    as_f64_lenient, as_u64_lenient, bar, budget, build_status, build_target, cloud, context, debug,
+    diagnostics, display_path, error, estimate_cost, features, format, format_cost_segment,
+    format_duration, format_duration_clock, format_permission_mode, format_session_id,
+    format_started, format_vim_mode, get_git_branch, get_git_dirs, git, hooks, host, hyperlink,
+    is_git_repo, is_standard_project_dir, kube, project, pull_request, release, remote, runtime,
+    state, summary, system, theme, timer, transcript, vcs, visibility, Config, CostMode,
+    DurationFormat,
+};
+use std::cell::{OnceCell, RefCell};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// A built-in segment: its name in templates, scripts and `position`, and
+/// how to render it.
+pub(crate) struct Segment {
+    pub name: &'static str,
+    /// Skipped once the render budget is spent. Segments that only format
+    /// the input, or that the line can't do without, always run.
+    pub budgeted: bool,
+    pub render: fn(&SegmentContext) -> String,
+}
+
+const fn segment(name: &'static str, render: fn(&SegmentContext) -> String) -> Segment {
+    Segment {
+        name,
+        budgeted: false,
+        render,
+    }
+}
+
+const fn budgeted(name: &'static str, render: fn(&SegmentContext) -> String) -> Segment {
+    Segment {
+        name,
+        budgeted: true,
+        render,
+    }
+}
+
+/// The segments following the path and branch, in display order.
+pub(crate) const SEGMENTS: &[Segment] = &[
+    budgeted("target", target),
+    segment("repo", repo),
+    budgeted("fork", fork),
+    budgeted("pr", pull_request),
+    budgeted("commit", commit),
+    budgeted("release", release),
+    segment("vim", vim),
+    segment("mode", mode),
+    segment("model", model),
+    segment("agent", agent),
+    segment("style", style),
+    budgeted("summary", summary),
+    segment("session", session),
+    segment("context", context),
+    budgeted("compactions", compactions),
+    segment("cost", cost),
+    budgeted("thinking", thinking),
+    budgeted("tasks", tasks),
+    budgeted("tool_calls", tool_calls),
+    budgeted("output", output),
+    budgeted("errors", errors),
+    budgeted("last_tool", last_tool),
+    budgeted("kube", kube),
+    budgeted("cloud", cloud),
+    budgeted("runtime", runtime),
+    budgeted("host", host),
+    segment("time", time),
+    budgeted("idle", idle),
+    segment("clock", clock),
+    budgeted("battery", battery),
+];
+
+/// The transcript at `path`, parsed into `scan` on first use. `None` until
+/// a render gets through the whole file within its budget.
+pub(crate) fn load_transcript<'s>(
+    scan: &'s OnceCell<Transcript>,
+    path: &str,
+    deadline: &Deadline,
+) -> Option<&'s Transcript> {
+    let transcript = scan.get_or_init(|| {
+        debug::profiled("transcript", || {
+            let transcript = Transcript::load_within(path, deadline);
+            if !transcript.is_complete() {
+                debug::trace("transcript: render budget spent, the rest is parsed next time");
+            }
+            transcript
+        })
+    });
+    transcript.is_complete().then_some(transcript)
+}
+
+/// Where the session runs: the workspace directory and its checkout.
+pub(crate) struct Workspace<'a> {
+    /// The workspace directory was deleted or can't be read, and isn't
+    /// queried.
+    pub dir_missing: bool,
+    /// Directory to run git in; `None` without a workspace or outside a
+    /// repo.
+    pub git_cwd: Option<&'a str>,
+    /// Whether segments link to the code host.
+    pub links: bool,
+    /// The origin remote's code host, for the repo segment and links.
+    pub origin: Option<RemoteRepo>,
+    /// Mercurial and Subversion checkouts get the branch and nothing else.
+    pub checkout: Option<vcs::Checkout>,
+    pub branch: String,
+    pub dirs: Option<GitDirs>,
+}
+
+/// What the session has cost so far.
+pub(crate) struct Cost {
+    /// Priced per message from the transcript, when that was needed.
+    pub session: Option<SessionCost>,
+    pub total: Option<f64>,
+    pub daily: Option<f64>,
+    pub over_budget: Option<budget::BudgetExceeded>,
+}
+
+/// Both forms of the context segment; templates can pick either one.
+pub(crate) struct ContextForms {
+    pub usage: Option<ContextUsage>,
+    pub bar: String,
+    pub tokens: String,
+}
+
+impl ContextForms {
+    pub fn percent(&self) -> Option<f64> {
+        self.usage.as_ref().map(|usage| usage.percent())
+    }
+}
+
+/// Everything the segments of one render share. Costly parts are worked
+/// out on first use, once.
+pub(crate) struct SegmentContext<'a> {
+    pub input: &'a serde_json::Value,
+    pub config: &'a Config,
+    pub deadline: Deadline,
+    pub git: &'a GitContext,
+    pub numbers: NumberFormat,
+    pub features: FeatureLevel,
+    pub current_dir: Option<&'a str>,
+    pub session_id: Option<&'a str>,
+    pub model_id: Option<&'a str>,
+    pub transcript_path: Option<&'a str>,
+    pub lines_added: u64,
+    pub lines_removed: u64,
+    /// Values from earlier renders of this session, for trend arrows.
+    pub trends: RefCell<Option<SessionState>>,
+    scan: &'a OnceCell<Transcript>,
+    workspace: OnceCell<Workspace<'a>>,
+    working_tree: OnceCell<Option<WorkingTreeStatus>>,
+    build: OnceCell<Option<BuildStatus>>,
+    cost: OnceCell<Cost>,
+    context: OnceCell<ContextForms>,
+}
+
+impl<'a> SegmentContext<'a> {
+    /// The context for rendering the normalized `input`. `scan` holds the
+    /// transcript once something has read it.
+    pub fn new(
+        input: &'a serde_json::Value,
+        config: &'a Config,
+        deadline: Deadline,
+        git: &'a GitContext,
+        scan: &'a OnceCell<Transcript>,
+    ) -> Self {
+        let str_at = |path: &[&str]| {
+            path.iter()
+                .try_fold(input, |value, key| value.get(key))
+                .and_then(|v| v.as_str())
+        };
+        let lines = |key: &str| {
+            input
+                .get("cost")
+                .and_then(|c| c.get(key))
+                .and_then(as_u64_lenient)
+                .unwrap_or(0)
+        };
+        let session_id = str_at(&["session_id"]);
+        let trends = match session_id {
+            Some(id)
+                if config.trends.enabled
+                    || config.trends.sparkline != state::SparklineMetric::Off =>
+            {
+                Some(debug::profiled("state", || state::load(id)))
+            }
+            _ => None,
+        };
+
+        SegmentContext {
+            input,
+            config,
+            deadline,
+            git,
+            numbers: format::NumberFormat::new(
+                &config.format,
+                deadline.optional("format", || format::exchange_rate(&config.format)),
+            ),
+            features: features::detect(input),
+            current_dir: str_at(&["workspace", "current_dir"]).or_else(|| str_at(&["cwd"])),
+            session_id,
+            model_id: str_at(&["model", "id"]),
+            transcript_path: str_at(&["transcript_path"]),
+            lines_added: lines("total_lines_added"),
+            lines_removed: lines("total_lines_removed"),
+            trends: RefCell::new(trends),
+            scan,
+            workspace: OnceCell::new(),
+            working_tree: OnceCell::new(),
+            build: OnceCell::new(),
+            cost: OnceCell::new(),
+            context: OnceCell::new(),
+        }
+    }
+
+    /// Renders `segment`, or nothing when it is budgeted and the budget is
+    /// spent. A segment that panics shows a placeholder.
+    pub fn run(&self, segment: &Segment) -> String {
+        self.render(segment.name, segment.budgeted, || (segment.render)(self))
+    }
+
+    /// [`SegmentContext::run`] for a segment outside [`SEGMENTS`].
+    pub fn render(
+        &self,
+        name: &'static str,
+        budgeted: bool,
+        render: impl FnOnce() -> String,
+    ) -> String {
+        let guarded = || error::guarded(name, render);
+        if budgeted {
+            self.deadline.optional(name, guarded)
+        } else {
+            debug::profiled(name, guarded)
+        }
+    }
+
+    /// The session's transcript, parsed on first use; see
+    /// [`load_transcript`].
+    pub fn transcript(&self) -> Option<&'a Transcript> {
+        load_transcript(self.scan, self.transcript_path?, &self.deadline)
+    }
+
+    pub fn workspace(&self) -> &Workspace<'a> {
+        self.workspace.get_or_init(|| self.find_workspace())
+    }
+
+    fn find_workspace(&self) -> Workspace<'a> {
+        let config = self.config;
+        let dir_missing = self.current_dir.is_some_and(|dir| {
+            !config.remote_workspace.applies_to(Path::new(dir)) && fs::read_dir(dir).is_err()
+        });
+        let git_cwd = debug::profiled("git", || {
+            self.current_dir
+                .filter(|dir| !dir_missing && is_git_repo(dir, self.git))
+        });
+        let links = !config.layout.frame_diff && config.layout.hyperlinks.enabled();
+        let origin = match git_cwd {
+            Some(dir) if config.repo.enabled || config.repo.pr || links => {
+                debug::profiled("git", || git::remote_url(dir, "origin", self.git))
+                    .and_then(|url| remote::RemoteRepo::parse(&url))
+            }
+            _ => None,
+        };
+        let checkout = match (git_cwd, self.current_dir) {
+            (None, Some(dir)) if !dir_missing => self
+                .deadline
+                .optional("vcs", || vcs::checkout(Path::new(dir), &config.vcs)),
+            _ => None,
+        };
+        let branch = match &checkout {
+            Some(checkout) => checkout.branch.clone(),
+            None => debug::profiled("git", || {
+                git_cwd
+                    .map(|dir| get_git_branch(dir, self.git))
+                    .unwrap_or_default()
+            }),
+        };
+        let dirs = debug::profiled("git", || {
+            git_cwd.and_then(|dir| get_git_dirs(dir, self.git))
+        });
+        Workspace {
+            dir_missing,
+            git_cwd,
+            links,
+            origin,
+            checkout,
+            branch,
+            dirs,
+        }
+    }
+
+    /// Staged, changed and untracked files, with `[git] status`.
+    pub fn working_tree(&self) -> Option<&WorkingTreeStatus> {
+        self.working_tree
+            .get_or_init(|| {
+                let workspace = self.workspace();
+                match (workspace.git_cwd, &workspace.dirs) {
+                    (Some(dir), Some(dirs)) if self.config.git.status => {
+                        self.deadline.optional("status", || {
+                            git::working_tree_status(
+                                Path::new(dir),
+                                dirs,
+                                &self.config.git,
+                                self.git,
+                            )
+                        })
+                    }
+                    _ => None,
+                }
+            })
+            .as_ref()
+    }
+
+    /// The last build result recorded for the checkout.
+    pub fn build(&self) -> Option<BuildStatus> {
+        *self.build.get_or_init(|| {
+            self.deadline.optional("build", || {
+                self.workspace().dirs.as_ref().and_then(|dirs| {
+                    build_status::read_build_status(&dirs.statusbar_dir().join("build.json"))
+                })
+            })
+        })
+    }
+
+    pub fn cost(&self) -> &Cost {
+        self.cost.get_or_init(|| {
+            let config = self.config;
+            let reported = self
+                .input
+                .get("cost")
+                .and_then(|c| c.get("total_cost_usd"))
+                .and_then(as_f64_lenient);
+            // Only priced per message when something needs it.
+            let session = self
+                .model_id
+                .filter(|_| reported.is_none() || config.cost.breakdown)
+                .and_then(|id| Some((id, self.transcript()?)))
+                .filter(|(_, transcript)| !transcript.usage.is_empty())
+                .map(|(id, transcript)| {
+                    transcript::calculate_session_cost(
+                        &transcript.usage,
+                        id,
+                        &config.models.aliases,
+                    )
+                });
+            let total = reported
+                .or_else(|| session.as_ref().map(|c| c.total_usd))
+                .or_else(|| estimate_cost(self.input, self.model_id?, config));
+            let daily = debug::profiled("budget", || {
+                config
+                    .budget
+                    .daily
+                    .map(|_| budget::daily_cost(self.session_id, total.unwrap_or(0.0)))
+            });
+            Cost {
+                session,
+                total,
+                daily,
+                over_budget: budget::check(&config.budget, total.unwrap_or(0.0), daily),
+            }
+        })
+    }
+
+    pub fn context(&self) -> &ContextForms {
+        self.context.get_or_init(|| {
+            let usage = context::usage(self.input, self.config, || self.transcript());
+            let (bar, tokens) = match &usage {
+                Some(usage) => context_forms(self, usage),
+                None => (String::new(), String::new()),
+            };
+            ContextForms { usage, bar, tokens }
+        })
+    }
+}
+
+fn context_forms(ctx: &SegmentContext, usage: &ContextUsage) -> (String, String) {
+    let config = ctx.config;
+    let pct = usage.percent();
+    if !visibility::visible(&config.segments.show_when, "context", Some(pct)) {
+        return (String::new(), String::new());
+    }
+    let pct_color = theme::band_color(
+        pct,
+        &config.thresholds.context,
+        &config.theme.context_colors,
+    );
+    let bar = format!(
+        "\x1b[38;5;13m{}{} {}{}%\x1b[0m{}",
+        config.icons.prefix("context"),
+        bar::render_bar(&config.bar, pct),
+        pct_color,
+        pct.round() as u32,
+        if usage.is_extended() {
+            format!(" \x1b[90m{}\x1b[0m", usage.format_fraction(&ctx.numbers))
+        } else {
+            String::new()
+        }
+    );
+    let tokens = format!(
+        "\x1b[38;5;13m{}{}{}%\x1b[0m \x1b[90m{}\x1b[0m",
+        config.icons.prefix("context"),
+        pct_color,
+        pct.round() as u32,
+        usage.format_breakdown(&ctx.numbers)
+    );
+
+    let mut suffix = String::new();
+    if let Some(trends) = ctx.trends.borrow_mut().as_mut() {
+        if config.trends.sparkline == state::SparklineMetric::Context {
+            let history = trends.record("context_pct", pct, config.trends.sparkline_length);
+            suffix.push_str(&state::format_sparkline(history, 100.0));
+        }
+        if config.trends.enabled {
+            suffix.push_str(state::format_trend(trends.observe(
+                "context_pct",
+                pct,
+                config.trends.context,
+            )));
+        }
+    }
+    (
+        format!("{}{}", bar, suffix),
+        format!("{}{}", tokens, suffix),
+    )
+}
+
+/// The project label from `[[projects]]`, placed before the path.
+pub(crate) fn project(ctx: &SegmentContext) -> String {
+    if ctx.config.projects.is_empty() {
+        return String::new();
+    }
+    let workspace = ctx.workspace();
+    let project_dir = ctx
+        .input
+        .get("workspace")
+        .and_then(|w| w.get("project_dir"))
+        .and_then(|d| d.as_str());
+    let dirs: Vec<&str> = ctx.current_dir.into_iter().chain(project_dir).collect();
+    project::find(&ctx.config.projects, &dirs, || match &workspace.origin {
+        Some(origin) => Some(origin.clone()),
+        None => workspace
+            .git_cwd
+            .and_then(|dir| git::remote_url(dir, "origin", ctx.git))
+            .and_then(|url| remote::RemoteRepo::parse(&url)),
+    })
+    .map(project::format_label)
+    .unwrap_or_default()
+}
+
+/// The workspace directory followed by a space; nothing for a standard
+/// project directory in `path.short` mode, where the branch says enough.
+pub(crate) fn path(ctx: &SegmentContext) -> String {
+    let config = ctx.config;
+    let workspace = ctx.workspace();
+    let Some(current_dir) = ctx.current_dir else {
+        // No workspace block (some managed/remote setups): a dim marker in
+        // place of the path, keeping the other segments visible.
+        return "\x1b[90m\u{2205} ".to_string();
+    };
+    if workspace.dir_missing {
+        format!(
+            "\x1b[90m{} \u{2716} ",
+            display_path(current_dir, false, config, ctx.git)
+        )
+    } else if config.path.short
+        && !workspace.branch.is_empty()
+        && is_standard_project_dir(current_dir, &config.path.project_roots, ctx.git)
+    {
+        String::new()
+    } else {
+        format!(
+            "{} ",
+            display_path(current_dir, workspace.git_cwd.is_some(), config, ctx.git)
+        )
+    }
+}
+
+/// The branch with the worktree, lines changed, divergence from the default
+/// branch, working tree status, conflicts, build and diagnostics.
+pub(crate) fn git(ctx: &SegmentContext) -> String {
+    let config = ctx.config;
+    let workspace = ctx.workspace();
+    let branch = &workspace.branch;
+    if branch.is_empty() {
+        return String::new();
+    }
+
+    let worktree = match workspace.dirs.as_ref().and_then(|d| d.worktree_name()) {
+        Some(name) if name != *branch => format!("\x1b[35m↟ {}\x1b[0m ", name),
+        Some(_) => "\x1b[35m↟\x1b[0m ".to_string(),
+        None => match &workspace.checkout {
+            Some(checkout) => format!("\x1b[90m{}\x1b[0m ", checkout.vcs.label()),
+            None => String::new(),
+        },
+    };
+
+    // Links to the branch on the origin's code host, where the terminal
+    // supports them.
+    let branch_text = match &workspace.origin {
+        Some(repo) if workspace.links && branch != "HEAD" => {
+            hyperlink::hyperlink(&repo.branch_url(branch), branch)
+        }
+        _ => branch.clone(),
+    };
+
+    let lines_changed = if ctx.lines_added > 0 || ctx.lines_removed > 0 {
+        format!(
+            "(\x1b[32m+{}\x1b[0m \x1b[31m-{}\x1b[0m)",
+            ctx.lines_added, ctx.lines_removed
+        )
+    } else {
+        String::new()
+    };
+
+    let divergence = match workspace.git_cwd {
+        Some(dir) if config.git.divergence && branch != "HEAD" => {
+            ctx.deadline.optional("divergence", || divergence(ctx, dir))
+        }
+        _ => String::new(),
+    };
+
+    let status = match &workspace.checkout {
+        Some(checkout) if checkout.dirty => " \x1b[33m*\x1b[0m".to_string(),
+        _ => ctx
+            .working_tree()
+            .map(git::format_status)
+            .filter(|s| !s.is_empty())
+            .map(|s| format!(" {}", s))
+            .unwrap_or_default(),
+    };
+
+    let conflicts = debug::profiled("git", || match (workspace.git_cwd, &workspace.dirs) {
+        (Some(dir), Some(dirs)) if git::operation_in_progress(&dirs.git_dir) => {
+            match git::count_unmerged_paths(dir, ctx.git) {
+                0 => String::new(),
+                n => format!(" \x1b[31m\u{2718}{}\x1b[0m", n),
+            }
+        }
+        _ => String::new(),
+    });
+
+    let build = ctx
+        .build()
+        .map(|status| format!(" {}", build_status::format_build_status(status)))
+        .unwrap_or_default();
+
+    let diagnostics = ctx.deadline.optional("diagnostics", || {
+        error::guarded("diagnostics", || {
+            workspace
+                .dirs
+                .as_ref()
+                .and_then(|dirs| diagnostics::read_diagnostics(&dirs.statusbar_dir()))
+                .map(diagnostics::format_diagnostics)
+                .filter(|d| !d.is_empty())
+                .map(|d| format!(" {}", d))
+                .unwrap_or_default()
+        })
+    });
+
+    format!(
+        "\x1b[38;5;12m{}{}\x1b[32m{}{}\x1b[0m{}{}{}{}{}",
+        config.icons.prefix("branch"),
+        worktree,
+        branch_text,
+        lines_changed,
+        divergence,
+        status,
+        conflicts,
+        build,
+        diagnostics
+    )
+}
+
+/// Commits ahead of and behind the default branch, with a leading space.
+fn divergence(ctx: &SegmentContext, dir: &str) -> String {
+    let config = ctx.config;
+    let workspace = ctx.workspace();
+    config
+        .git
+        .default_branch
+        .clone()
+        .or_else(|| {
+            workspace.dirs.as_ref().and_then(|dirs| {
+                git::default_branch(
+                    Path::new(dir),
+                    dirs,
+                    config.git.default_branch_refresh_secs,
+                    ctx.git,
+                )
+            })
+        })
+        .and_then(|default| git::divergence(dir, &workspace.branch, &default, ctx.git))
+        .map(|divergence| {
+            let changed = config.trends.enabled
+                && ctx.trends.borrow_mut().as_mut().is_some_and(|trends| {
+                    let ahead = trends.observe("ahead", divergence.ahead as f64, 1.0);
+                    let behind = trends.observe("behind", divergence.behind as f64, 1.0);
+                    ahead.is_some() || behind.is_some()
+                });
+            git::format_divergence(&divergence, changed)
+        })
+        .filter(|d| !d.is_empty())
+        .map(|d| format!(" {}", d))
+        .unwrap_or_default()
+}
+
+fn target(ctx: &SegmentContext) -> String {
+    match ctx.current_dir {
+        Some(dir) if ctx.config.build_target.enabled && !ctx.workspace().dir_missing => {
+            build_target::package(Path::new(dir))
+                .map(|package| build_target::format_package(&ctx.config.build_target, &package))
+                .unwrap_or_default()
+        }
+        _ => String::new(),
+    }
+}
+
+fn repo(ctx: &SegmentContext) -> String {
+    let workspace = ctx.workspace();
+    match &workspace.origin {
+        Some(repo) if ctx.config.repo.enabled => remote::format_repo(
+            repo,
+            &ctx.config.icons.prefix(repo.forge.icon_name()),
+            workspace.links,
+        ),
+        _ => String::new(),
+    }
+}
+
+fn fork(ctx: &SegmentContext) -> String {
+    match ctx.workspace().git_cwd {
+        Some(dir) if ctx.config.repo.fork => remote::fork_status(dir, &ctx.config.repo, ctx.git)
+            .map(|status| remote::format_fork(&status))
+            .unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+/// Only GitHub's `gh` is asked; remote workspaces have no local checkout to
+/// run it in.
+fn pull_request(ctx: &SegmentContext) -> String {
+    let config = ctx.config;
+    let workspace = ctx.workspace();
+    match (workspace.git_cwd, &workspace.dirs, &workspace.origin) {
+        (Some(dir), Some(dirs), Some(repo))
+            if config.repo.pr
+                && !config.skip_pr_status
+                && repo.forge == remote::Forge::GitHub
+                && workspace.branch != "HEAD"
+                && !config.remote_workspace.applies_to(Path::new(dir)) =>
+        {
+            pull_request::lookup(
+                Path::new(dir),
+                dirs,
+                &workspace.branch,
+                config.repo.pr_refresh_secs,
+            )
+            .map(|pr| pull_request::format_pull_request(&pr, workspace.links.then_some(repo)))
+            .unwrap_or_default()
+        }
+        _ => String::new(),
+    }
+}
+
+fn commit(ctx: &SegmentContext) -> String {
+    match ctx.workspace().git_cwd {
+        Some(dir) if ctx.config.git.commit => git::head_commit(dir, ctx.git)
+            .map(|commit| {
+                git::format_commit(
+                    &ctx.config.icons.prefix("commit"),
+                    &commit,
+                    chrono::Utc::now().timestamp(),
+                )
+            })
+            .unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+fn release(ctx: &SegmentContext) -> String {
+    match ctx.workspace().git_cwd {
+        Some(dir) if ctx.config.release.enabled => release::next_bump(
+            git::messages_since_last_tag(dir, ctx.git)
+                .iter()
+                .map(String::as_str),
+        )
+        .map(release::format_bump)
+        .unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+fn vim(ctx: &SegmentContext) -> String {
+    let mode = ctx
+        .input
+        .get("vim")
+        .and_then(|v| v.get("mode"))
+        .or_else(|| ctx.input.get("vim_mode"))
+        .or_else(|| ctx.input.get("editor_mode"))
+        .and_then(|m| m.as_str());
+    match mode {
+        Some(mode) if ctx.config.segments.vim_mode => format_vim_mode(mode),
+        _ => String::new(),
+    }
+}
+
+fn mode(ctx: &SegmentContext) -> String {
+    let mode = ctx
+        .input
+        .get("permission_mode")
+        .or_else(|| ctx.input.get("permissionMode"))
+        .and_then(|p| p.as_str());
+    match mode {
+        Some(mode) if ctx.config.segments.permission_mode => {
+            format_permission_mode(mode, &ctx.config.icons)
+        }
+        _ => String::new(),
+    }
+}
+
+fn model(ctx: &SegmentContext) -> String {
+    let name = ctx
+        .input
+        .get("model")
+        .and_then(|m| m.get("display_name"))
+        .and_then(|d| d.as_str());
+    match name {
+        Some(name) => format!(
+            "\x1b[38;5;14m{}\x1b[38;5;208m{}\x1b[0m",
+            ctx.config.icons.prefix("model"),
+            name
+        ),
+        None => String::new(),
+    }
+}
+
+fn agent(ctx: &SegmentContext) -> String {
+    let name = ctx
+        .input
+        .get("agent")
+        .or_else(|| ctx.input.get("subagent"))
+        .and_then(|a| a.get("name").or_else(|| a.get("type")))
+        .or_else(|| ctx.input.get("agent_name"))
+        .and_then(|n| n.as_str())
+        .filter(|n| !n.is_empty());
+    match name {
+        Some(name) if ctx.config.segments.agent => format!(
+            "\x1b[38;5;141m{}{}\x1b[0m",
+            ctx.config.icons.prefix("agent"),
+            name
+        ),
+        _ => String::new(),
+    }
+}
+
+fn style(ctx: &SegmentContext) -> String {
+    let style = ctx
+        .input
+        .get("output_style")
+        .filter(|_| ctx.features.output_style)
+        .and_then(|o| o.get("name"))
+        .and_then(|n| n.as_str());
+    match style {
+        Some(style) if ctx.config.segments.output_style => format!(
+            "\x1b[38;5;5m{}\x1b[90m{}\x1b[0m",
+            ctx.config.icons.prefix("style"),
+            style
+        ),
+        _ => String::new(),
+    }
+}
+
+fn summary(ctx: &SegmentContext) -> String {
+    let config = &ctx.config.summary;
+    match (ctx.session_id, ctx.transcript_path) {
+        (Some(id), Some(path)) if config.summarizer != summary::SummarizerKind::Off => ctx
+            .transcript()
+            .and_then(|t| t.first_prompt.as_deref())
+            .map(|prompt| summary::format_summary(config, id, path, prompt))
+            .unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+fn session(ctx: &SegmentContext) -> String {
+    ctx.session_id
+        .map(|id| format_session_id(id, ctx.config.segments.session_id))
+        .unwrap_or_default()
+}
+
+fn context(ctx: &SegmentContext) -> String {
+    let forms = ctx.context();
+    if ctx.config.layout.verbose_context {
+        forms.tokens.clone()
+    } else {
+        forms.bar.clone()
+    }
+}
+
+fn cost(ctx: &SegmentContext) -> String {
+    let config = ctx.config;
+    let cost = ctx.cost();
+    let Some(total) = cost.total.filter(|&total| {
+        config.cost.mode != CostMode::Hidden
+            && visibility::visible(&config.segments.show_when, "cost", Some(total))
+    }) else {
+        return String::new();
+    };
+
+    let breakdown = match &cost.session {
+        Some(session) if config.cost.breakdown && session.by_model.len() > 1 => format!(
+            " \x1b[90m({})\x1b[0m",
+            transcript::format_cost_breakdown(session, &config.models.aliases, &ctx.numbers)
+        ),
+        _ => String::new(),
+    };
+    // Checked before the transcript is loaded, which most renders don't need.
+    let recent = ctx
+        .model_id
+        .filter(|_| config.cost.rolling_window_minutes > 0)
+        .and_then(|id| Some((id, ctx.transcript()?)));
+    let rolling = match recent {
+        Some((id, transcript)) if !transcript.recent_usage.is_empty() => {
+            let recent = transcript::rolling_cost(
+                &transcript.recent_usage,
+                id,
+                &config.models.aliases,
+                chrono::Utc::now().timestamp_millis(),
+                config.cost.rolling_window_minutes,
+            );
+            format!(
+                " \x1b[90m({}m \x1b[33m{}\x1b[90m)\x1b[0m",
+                config.cost.rolling_window_minutes,
+                ctx.numbers.amount(recent)
+            )
+        }
+        _ => String::new(),
+    };
+
+    let mut segment = format_cost_segment(
+        total,
+        &breakdown,
+        &rolling,
+        cost.over_budget.is_some(),
+        config,
+        &ctx.numbers,
+    );
+    if let Some(trends) = ctx.trends.borrow_mut().as_mut() {
+        if config.trends.sparkline == state::SparklineMetric::Cost {
+            let history = trends.record("cost_usd", total, config.trends.sparkline_length);
+            let max = history.iter().copied().fold(0.0, f64::max);
+            segment.push_str(&state::format_sparkline(history, max));
+        }
+        if config.trends.enabled {
+            segment.push_str(state::format_trend(trends.observe(
+                "cost_usd",
+                total,
+                config.trends.cost,
+            )));
+        }
+    }
+    if cost.over_budget.is_some() && config.budget.warn_icon {
+        format!("\x1b[1;31m\u{26a0}\x1b[0m {}", segment)
+    } else {
+        segment
+    }
+}
+
+fn compactions(ctx: &SegmentContext) -> String {
+    match ctx.transcript() {
+        Some(t) if ctx.config.segments.compactions => {
+            transcript::format_compactions(t.compactions())
+        }
+        _ => String::new(),
+    }
+}
+
+fn thinking(ctx: &SegmentContext) -> String {
+    match ctx.transcript() {
+        Some(t) if ctx.config.segments.thinking => transcript::format_thinking(
+            &ctx.config.icons.prefix("thinking"),
+            &t.thinking,
+            &ctx.numbers,
+        ),
+        _ => String::new(),
+    }
+}
+
+fn tasks(ctx: &SegmentContext) -> String {
+    if !ctx.config.segments.tasks {
+        return String::new();
+    }
+    ctx.transcript()
+        .and_then(|t| t.todos.as_ref())
+        .map(|progress| {
+            transcript::format_todos(
+                &ctx.config.icons.prefix("tasks"),
+                progress,
+                ctx.config.segments.tasks_active,
+            )
+        })
+        .unwrap_or_default()
+}
+
+fn tool_calls(ctx: &SegmentContext) -> String {
+    match ctx.transcript() {
+        Some(t) if ctx.config.segments.tool_calls => {
+            transcript::format_tool_usage(&ctx.config.icons.prefix("tool_calls"), &t.tools)
+        }
+        _ => String::new(),
+    }
+}
+
+fn output(ctx: &SegmentContext) -> String {
+    if !ctx.config.segments.output {
+        return String::new();
+    }
+    let total = ctx
+        .input
+        .get("context_window")
+        .and_then(|c| c.get("total_output_tokens"))
+        .and_then(as_u64_lenient)
+        .or_else(|| {
+            ctx.transcript()
+                .map(|t| t.usage.iter().map(|e| e.output_tokens).sum())
+        });
+    let last = ctx.transcript().and_then(|t| t.last_response);
+    match total {
+        Some(total) => transcript::format_output(
+            &ctx.config.icons.prefix("output"),
+            total,
+            last.as_ref(),
+            &ctx.numbers,
+        ),
+        None => String::new(),
+    }
+}
+
+fn errors(ctx: &SegmentContext) -> String {
+    match ctx.transcript() {
+        Some(t) if ctx.config.segments.errors => transcript::format_errors(
+            &ctx.config.icons.prefix("errors"),
+            &t.errors,
+            chrono::Utc::now().timestamp_millis(),
+            ctx.config.segments.errors_recent_secs,
+        ),
+        _ => String::new(),
+    }
+}
+
+fn last_tool(ctx: &SegmentContext) -> String {
+    match ctx.session_id {
+        Some(id) if ctx.config.segments.last_tool => hooks::format_last_tool(
+            &ctx.config.icons.prefix("last_tool"),
+            &hooks::read_events(id),
+            chrono::Utc::now().timestamp_millis(),
+        ),
+        _ => String::new(),
+    }
+}
+
+fn kube(ctx: &SegmentContext) -> String {
+    if !ctx.config.kube.enabled {
+        return String::new();
+    }
+    kube::read_current(&kube::kubeconfig_paths())
+        .map(|current| {
+            kube::format_kube(&ctx.config.kube, &ctx.config.icons.prefix("kube"), &current)
+        })
+        .unwrap_or_default()
+}
+
+fn cloud(ctx: &SegmentContext) -> String {
+    if !ctx.config.cloud.enabled {
+        return String::new();
+    }
+    cloud::format_cloud(
+        &ctx.config.cloud,
+        &cloud::active_profiles(&ctx.config.cloud),
+    )
+}
+
+fn runtime(ctx: &SegmentContext) -> String {
+    match ctx.current_dir {
+        Some(dir) if ctx.config.runtime.any_enabled() => {
+            runtime::format_runtimes(&runtime::detect(&ctx.config.runtime, Path::new(dir)))
+        }
+        _ => String::new(),
+    }
+}
+
+fn host(ctx: &SegmentContext) -> String {
+    if !ctx.config.host.enabled {
+        return String::new();
+    }
+    host::format_host(&ctx.config.host, &host::detect())
+}
+
+/// The session duration, then the countdown of `statusline timer`.
+fn time(ctx: &SegmentContext) -> String {
+    let config = ctx.config;
+    let now = chrono::Local::now();
+    // Claude Code's duration runs up to now; the transcript's from the first
+    // to the last entry.
+    let (duration_ms, started_ms) = if ctx.features.cost_duration {
+        let ms = ctx
+            .input
+            .get("cost")
+            .and_then(|c| c.get("total_duration_ms"))
+            .and_then(as_f64_lenient)
+            .map(|ms| ms as i64);
+        (ms, ms.map(|ms| now.timestamp_millis() - ms))
+    } else {
+        match ctx.transcript().and_then(|t| t.timestamps()) {
+            Some((first, last)) => (Some(last - first), Some(first)),
+            None => (None, None),
+        }
+    };
+    let duration = duration_ms
+        .filter(|&ms| {
+            visibility::visible(
+                &config.segments.show_when,
+                "duration",
+                Some(ms as f64 / 1000.0),
+            )
+        })
+        .map(|ms| {
+            let duration = Duration::from_millis(ms.max(0) as u64);
+            match (config.segments.duration_format, started_ms) {
+                (DurationFormat::Clock, _) => format_duration_clock(duration),
+                (DurationFormat::Started, Some(started)) => format_started(started, now),
+                _ => format_duration(duration, config.segments.duration_seconds),
+            }
+        });
+
+    let mut parts = Vec::new();
+    if let Some(duration) = duration {
+        parts.push(format!(
+            "\x1b[38;5;6m{}\x1b[90m{}\x1b[0m",
+            config.icons.prefix("time"),
+            duration
+        ));
+    }
+    if let Some(state) = timer::load() {
+        parts.push(timer::format_timer(
+            &config.icons.prefix("timer"),
+            &state,
+            chrono::Utc::now().timestamp(),
+        ));
+    }
+    parts.join(" ")
+}
+
+fn idle(ctx: &SegmentContext) -> String {
+    if !ctx.config.segments.idle {
+        return String::new();
+    }
+    ctx.transcript()
+        .and_then(|t| t.last_activity_ms)
+        .map(|last| {
+            transcript::format_idle_badge(
+                chrono::Utc::now().timestamp_millis() - last,
+                ctx.config.segments.idle_after_secs,
+            )
+        })
+        .unwrap_or_default()
+}
+
+fn clock(ctx: &SegmentContext) -> String {
+    if !ctx.config.clock.enabled {
+        return String::new();
+    }
+    system::format_clock(&ctx.config.clock, chrono::Local::now())
+}
+
+fn battery(ctx: &SegmentContext) -> String {
+    if !ctx.config.battery.enabled {
+        return String::new();
+    }
+    system::battery(&ctx.config.battery)
+        .map(|battery| system::format_battery(&ctx.config.battery, &battery))
+        .unwrap_or_default()
+}
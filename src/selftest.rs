@@ -0,0 +1,352 @@
+use crate::cli::SelftestArgs;
+use crate::{render, Config, Registry};
+use serde_json::json;
+use std::fs;
+use std::io::Write;
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Small xorshift generator; the self-test only needs reproducible variety.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n.max(1)
+    }
+
+    fn chance(&mut self, percent: u64) -> bool {
+        self.below(100) < percent
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len() as u64) as usize]
+    }
+}
+
+/// Writes a transcript of `messages` assistant turns (with tool calls and
+/// duplicated streaming entries) spread over the last two hours.
+fn write_transcript(path: &Path, messages: usize, rng: &mut Rng) -> std::io::Result<()> {
+    let mut file = std::io::BufWriter::new(fs::File::create(path)?);
+    let now = chrono::Utc::now();
+    let models = [
+        "claude-opus-4-1-20250805",
+        "claude-sonnet-4-5-20250929",
+        "<synthetic>",
+    ];
+
+    for i in 0..messages {
+        let at = now - chrono::Duration::seconds(((messages - i) * 7200 / messages.max(1)) as i64);
+        let timestamp = at.to_rfc3339();
+        if i % 5 == 0 {
+            let prompt = json!({"type": "user", "uuid": format!("u{}", i), "timestamp": timestamp,
+                "message": {"role": "user", "content": "continue"}});
+            writeln!(file, "{}", prompt)?;
+        }
+        let assistant = json!({"type": "assistant", "timestamp": timestamp, "message": {
+        "id": format!("msg_{}", i),
+        "model": rng.pick(&models),
+        "content": [{"type": "tool_use", "id": format!("tool_{}", i),
+            "name": rng.pick(&["Bash", "Edit", "Read", "Grep"]), "input": {}}],
+        "usage": {
+            "input_tokens": rng.below(20_000),
+            "output_tokens": rng.below(4_000),
+            "cache_creation_input_tokens": rng.below(10_000),
+            "cache_read_input_tokens": rng.below(150_000),
+        }}});
+        writeln!(file, "{}", assistant)?;
+        if rng.chance(30) {
+            writeln!(file, "{}", assistant)?;
+        }
+        if rng.chance(1) {
+            writeln!(file, "{{not json")?;
+        }
+    }
+    Ok(())
+}
+
+fn synthetic_input(rng: &mut Rng, dirs: &[String], transcripts: &[PathBuf]) -> serde_json::Value {
+    let mut input = json!({
+        "hook_event_name": "Status",
+        "session_id": format!("selftest-{}", rng.below(8)),
+    });
+    let obj = input.as_object_mut().expect("object literal");
+
+    if rng.chance(90) {
+        let dir = rng.pick(dirs).clone();
+        obj.insert(
+            "workspace".into(),
+            json!({"current_dir": dir, "project_dir": dir}),
+        );
+    }
+    if rng.chance(90) {
+        let (id, name) = rng.pick(&[
+            ("claude-opus-4-1", "Opus"),
+            ("claude-sonnet-4-5[1m]", "Sonnet"),
+            ("claude-haiku-4-5", "Haiku"),
+            ("some-gateway-model", "Custom"),
+        ]);
+        obj.insert("model".into(), json!({"id": id, "display_name": name}));
+    }
+    if rng.chance(80) {
+        obj.insert(
+            "version".into(),
+            json!(rng.pick(&["1.0.70", "1.0.85", "2.0.14", "garbage"])),
+        );
+    }
+    if rng.chance(70) {
+        obj.insert("transcript_path".into(), json!(rng.pick(transcripts)));
+    }
+    if rng.chance(60) {
+        obj.insert(
+            "output_style".into(),
+            json!({"name": rng.pick(&["default", "Explanatory", ""])}),
+        );
+    }
+    if rng.chance(40) {
+        obj.insert(
+            "permission_mode".into(),
+            json!(rng.pick(&["default", "plan", "acceptEdits", "bypassPermissions"])),
+        );
+    }
+    if rng.chance(20) {
+        obj.insert(
+            "vim".into(),
+            json!({"mode": rng.pick(&["NORMAL", "INSERT"])}),
+        );
+    }
+    if rng.chance(20) {
+        obj.insert("agent".into(), json!({"name": "code-reviewer"}));
+    }
+    if rng.chance(85) {
+        obj.insert(
+            "cost".into(),
+            json!({
+                "total_cost_usd": rng.below(5_000) as f64 / 100.0,
+                "total_duration_ms": rng.below(20_000_000),
+                "total_lines_added": rng.below(2_000),
+                "total_lines_removed": rng.below(2_000),
+            }),
+        );
+    }
+    if rng.chance(85) {
+        obj.insert(
+            "context_window".into(),
+            json!({
+                "total_input_tokens": rng.below(500_000),
+                "total_output_tokens": rng.below(100_000),
+                "context_window_size": rng.pick(&[200_000, 1_000_000, 0]),
+                "current_usage": {
+                    "input_tokens": rng.below(150_000),
+                    "output_tokens": rng.below(10_000),
+                    "cache_creation_input_tokens": rng.below(30_000),
+                    "cache_read_input_tokens": rng.below(100_000),
+                }
+            }),
+        );
+    }
+    input
+}
+
+/// Checks that every escape sequence is a complete CSI or OSC sequence and
+/// that no SGR style is left open at the end of the line.
+pub fn check_ansi(line: &str) -> Result<(), String> {
+    let mut chars = line.chars().peekable();
+    let mut styled = false;
+
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            continue;
+        }
+        match chars.next() {
+            Some('[') => {
+                let mut params = String::new();
+                loop {
+                    match chars.next() {
+                        Some(c) if c.is_ascii_digit() || c == ';' || c == ':' => params.push(c),
+                        Some('m') => {
+                            styled = !(params.is_empty() || params == "0");
+                            break;
+                        }
+                        Some('G' | 'K') => break,
+                        other => {
+                            return Err(format!("malformed CSI sequence ending in {:?}", other))
+                        }
+                    }
+                }
+            }
+            Some(']') => loop {
+                match chars.next() {
+                    Some('\x07') => break,
+                    Some('\x1b') if chars.peek() == Some(&'\\') => {
+                        chars.next();
+                        break;
+                    }
+                    Some(_) => {}
+                    None => return Err("unterminated OSC sequence".to_string()),
+                }
+            },
+            other => return Err(format!("unknown escape {:?}", other)),
+        }
+    }
+
+    if styled {
+        Err("style not reset at end of line".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+/// Creates `<tmp>/cc-statusline-<name>-<pid>` and points the cache and state
+/// directories into it, so synthetic renders leave the real ones alone. The
+/// caller removes it when done.
+pub fn isolated_scratch_dir(name: &str) -> std::io::Result<PathBuf> {
+    let scratch =
+        std::env::temp_dir().join(format!("cc-statusline-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&scratch)?;
+    std::env::set_var("XDG_CACHE_HOME", scratch.join("cache"));
+    std::env::set_var("XDG_STATE_HOME", scratch.join("state"));
+    Ok(scratch)
+}
+
+struct Summary {
+    failures: Vec<String>,
+    latencies: Vec<Duration>,
+}
+
+/// The user's config without the sections that act outside the line:
+/// alert state lives in the scratch cache, so every webhook, command and
+/// notification would fire as new, and exports and history rows would be
+/// sent for the synthetic sessions.
+fn without_side_effects(config: Config) -> Config {
+    Config {
+        alerts: Default::default(),
+        export: Default::default(),
+        history: Default::default(),
+        ..config
+    }
+}
+
+fn run(iterations: usize, seed: u64, max_ms: u64, scratch: &Path) -> std::io::Result<Summary> {
+    let mut rng = Rng(seed.max(1));
+
+    let transcripts: Vec<PathBuf> = [0, 50, 2_000, 20_000]
+        .iter()
+        .map(|&size| {
+            let path = scratch.join(format!("transcript-{}.jsonl", size));
+            write_transcript(&path, size, &mut rng).map(|_| path)
+        })
+        .collect::<std::io::Result<_>>()?;
+    let mut dirs = vec![
+        scratch.display().to_string(),
+        scratch.join("deleted").display().to_string(),
+    ];
+    if let Ok(cwd) = std::env::current_dir() {
+        dirs.push(cwd.display().to_string());
+    }
+
+    let config = without_side_effects(Config::load());
+    let registry = Registry::default();
+    let mut summary = Summary {
+        failures: Vec::new(),
+        latencies: Vec::with_capacity(iterations),
+    };
+
+    for i in 0..iterations {
+        let input = synthetic_input(&mut rng, &dirs, &transcripts);
+        let started = Instant::now();
+        let result =
+            std::panic::catch_unwind(AssertUnwindSafe(|| render(&input, &config, &registry)));
+        let elapsed = started.elapsed();
+        summary.latencies.push(elapsed);
+
+        let line = match result {
+            Ok(line) => line,
+            Err(_) => {
+                summary
+                    .failures
+                    .push(format!("#{}: panic on input {}", i, input));
+                continue;
+            }
+        };
+        if let Err(e) = check_ansi(line.trim_end_matches('\x07')) {
+            summary
+                .failures
+                .push(format!("#{}: {} in {:?}", i, e, line));
+        }
+        if elapsed > Duration::from_millis(max_ms) {
+            summary.failures.push(format!(
+                "#{}: render took {}ms (limit {}ms)",
+                i,
+                elapsed.as_millis(),
+                max_ms
+            ));
+        }
+    }
+
+    Ok(summary)
+}
+
+/// `statusline selftest [--iterations N] [--seed N] [--max-ms N]`: renders
+/// synthetic inputs with the user's config and verifies that nothing panics,
+/// every render stays under `--max-ms`, and the ANSI output is well formed.
+/// Caches and logs go to a scratch directory, not the real ones.
+pub fn run_cli(args: &SelftestArgs) -> i32 {
+    let (iterations, max_ms) = (args.iterations, args.max_ms);
+    let seed = args
+        .seed
+        .unwrap_or_else(|| chrono::Utc::now().timestamp_millis() as u64);
+
+    let scratch = match isolated_scratch_dir("selftest") {
+        Ok(dir) => dir,
+        Err(e) => {
+            eprintln!("selftest: {}", e);
+            return 1;
+        }
+    };
+
+    let result = run(iterations, seed, max_ms, &scratch);
+    let _ = fs::remove_dir_all(&scratch);
+    let mut summary = match result {
+        Ok(summary) => summary,
+        Err(e) => {
+            eprintln!("selftest: {}", e);
+            return 1;
+        }
+    };
+
+    summary.latencies.sort();
+    let percentile = |p: usize| {
+        summary
+            .latencies
+            .get((summary.latencies.len() * p / 100).min(summary.latencies.len().saturating_sub(1)))
+            .map(|d| d.as_secs_f64() * 1000.0)
+            .unwrap_or(0.0)
+    };
+    println!(
+        "{} renders (seed {}): p50 {:.1}ms, p95 {:.1}ms, max {:.1}ms",
+        iterations,
+        seed,
+        percentile(50),
+        percentile(95),
+        percentile(100)
+    );
+
+    for failure in summary.failures.iter().take(20) {
+        println!("FAIL {}", failure);
+    }
+    if summary.failures.is_empty() {
+        println!("ok");
+        0
+    } else {
+        println!("{} failures", summary.failures.len());
+        1
+    }
+}
@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// `[trends]` config section: arrows next to values that moved noticeably
+/// since an earlier render of the same session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrendsConfig {
+    pub enabled: bool,
+    /// Context percentage points a value must move to get an arrow.
+    pub context: f64,
+    /// Dollars the session cost must move to get an arrow.
+    pub cost: f64,
+    /// Draw a sparkline of the last `sparkline_length` values of this
+    /// metric next to its segment.
+    pub sparkline: SparklineMetric,
+    pub sparkline_length: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SparklineMetric {
+    #[default]
+    Off,
+    Context,
+    Cost,
+}
+
+impl Default for TrendsConfig {
+    fn default() -> Self {
+        TrendsConfig {
+            enabled: false,
+            context: 10.0,
+            cost: 1.0,
+            sparkline: SparklineMetric::Off,
+            sparkline_length: 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Trend {
+    Up,
+    Down,
+}
+
+/// The last value seen for a metric and the direction of its last change.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Metric {
+    pub value: f64,
+    pub trend: Option<Trend>,
+}
+
+/// Values carried from one render of a session to the next.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionState {
+    #[serde(default)]
+    pub metrics: BTreeMap<String, Metric>,
+    /// Recent distinct values per metric, oldest first.
+    #[serde(default)]
+    pub history: BTreeMap<String, Vec<f64>>,
+    pub updated_at: i64,
+}
+
+impl SessionState {
+    /// Records `value` for `name` and returns its trend. A change of at
+    /// least `min_change` sets the trend, a smaller one clears it, and an
+    /// unchanged value keeps it, so the arrow survives the redraws that
+    /// follow the turn that moved it.
+    pub fn observe(&mut self, name: &str, value: f64, min_change: f64) -> Option<Trend> {
+        let trend = match self.metrics.get(name) {
+            None => None,
+            Some(previous) if previous.value == value => previous.trend,
+            Some(previous) if (value - previous.value).abs() >= min_change => {
+                Some(if value > previous.value {
+                    Trend::Up
+                } else {
+                    Trend::Down
+                })
+            }
+            Some(_) => None,
+        };
+        self.metrics
+            .insert(name.to_string(), Metric { value, trend });
+        trend
+    }
+
+    /// Appends `value` to the history of `name` unless it repeats the last
+    /// entry, keeping at most `limit` values, and returns the history.
+    pub fn record(&mut self, name: &str, value: f64, limit: usize) -> &[f64] {
+        let history = self.history.entry(name.to_string()).or_default();
+        if history.last() != Some(&value) {
+            history.push(value);
+        }
+        let excess = history.len().saturating_sub(limit);
+        history.drain(..excess);
+        history
+    }
+}
+
+/// `▁▂▄▆█`: one block per value, scaled so `max` is a full block.
+pub fn sparkline(values: &[f64], max: f64) -> String {
+    const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    if max <= 0.0 {
+        return BLOCKS[0].to_string().repeat(values.len());
+    }
+    values
+        .iter()
+        .map(|v| {
+            let level = (v / max * (BLOCKS.len() - 1) as f64).round();
+            BLOCKS[(level.max(0.0) as usize).min(BLOCKS.len() - 1)]
+        })
+        .collect()
+}
+
+/// The sparkline in gray with a leading space, or nothing for fewer than
+/// two values.
+pub fn format_sparkline(values: &[f64], max: f64) -> String {
+    if values.len() < 2 {
+        return String::new();
+    }
+    format!(" \x1b[90m{}\x1b[0m", sparkline(values, max))
+}
+
+/// `<state dir>/sessions/<session_id>.json`.
+pub fn state_path(session_id: &str) -> PathBuf {
+    crate::debug::state_dir()
+        .join("sessions")
+        .join(format!("{}.json", crate::cache::cache_key(session_id)))
+}
+
+pub fn load(session_id: &str) -> SessionState {
+    std::fs::read_to_string(state_path(session_id))
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+/// Writes the state atomically, so concurrent renders of the same session
+/// never read a torn file.
+pub fn save(session_id: &str, state: &SessionState) -> std::io::Result<()> {
+    let mut state = state.clone();
+    state.updated_at = chrono::Utc::now().timestamp();
+    crate::cache::write_atomic(&state_path(session_id), &serde_json::to_string(&state)?)
+}
+
+/// `▲` in yellow or `▼` in green; nothing without a trend.
+pub fn format_trend(trend: Option<Trend>) -> &'static str {
+    match trend {
+        Some(Trend::Up) => "\x1b[33m\u{25b2}\x1b[0m",
+        Some(Trend::Down) => "\x1b[32m\u{25bc}\x1b[0m",
+        None => "",
+    }
+}
@@ -0,0 +1,251 @@
+use crate::cache;
+use crate::cli::SummarizeArgs;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// `[summary]` config section: a few words describing what the session is
+/// about, from its first prompt.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SummaryConfig {
+    pub summarizer: SummarizerKind,
+    /// Words kept by the heuristic summarizer.
+    pub words: usize,
+    /// Model passed to `claude -p --model`.
+    pub model: String,
+    /// Maximum width of the segment in terminal cells.
+    pub max_width: usize,
+}
+
+impl Default for SummaryConfig {
+    fn default() -> Self {
+        SummaryConfig {
+            summarizer: SummarizerKind::Off,
+            words: 6,
+            model: "haiku".to_string(),
+            max_width: 40,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SummarizerKind {
+    /// The first `words` words of the first prompt.
+    Heuristic,
+    /// A short title written by `claude -p` in the background; the heuristic
+    /// is shown until it is ready.
+    Claude,
+    /// No summary; the default, as it shows the user's own words on screen.
+    #[default]
+    Off,
+}
+
+/// Turns a session's first prompt into a short summary.
+pub trait Summarizer {
+    /// The summary, or `None` while it isn't available (yet).
+    fn summarize(&self, session_id: &str, transcript_path: &str, prompt: &str) -> Option<String>;
+}
+
+/// First few words of the prompt, on one line.
+pub struct Heuristic {
+    pub words: usize,
+}
+
+impl Summarizer for Heuristic {
+    fn summarize(&self, _session_id: &str, _transcript_path: &str, prompt: &str) -> Option<String> {
+        let words: Vec<&str> = prompt.split_whitespace().take(self.words).collect();
+        (!words.is_empty()).then(|| words.join(" "))
+    }
+}
+
+/// Asks `claude -p` for a title once per session. The call runs in a
+/// detached `statusline summarize` worker (a thread when embedded) so
+/// renders never wait for it; its answer is cached as `summary-<session>`.
+pub struct ClaudeSummarizer {
+    pub model: String,
+}
+
+/// A worker that hasn't finished after this long is assumed dead, and the
+/// next render may start another one.
+const LOCK_STALE_AFTER: Duration = Duration::from_secs(120);
+
+fn cache_name(session_id: &str) -> String {
+    format!("summary-{}", cache::cache_key(session_id))
+}
+
+impl Summarizer for ClaudeSummarizer {
+    fn summarize(&self, session_id: &str, transcript_path: &str, _prompt: &str) -> Option<String> {
+        if let Some(summary) = cache::read_cache(&cache_name(session_id)) {
+            return Some(sanitize(&summary)).filter(|s| !s.is_empty());
+        }
+        if cache::try_lock(&cache_name(session_id), LOCK_STALE_AFTER) {
+            summarize_in_background(session_id, transcript_path, &self.model);
+        }
+        None
+    }
+}
+
+/// A `statusline summarize` process, or a thread when embedded.
+fn summarize_in_background(session_id: &str, transcript_path: &str, model: &str) {
+    match crate::process::statusline_exe() {
+        Some(exe) => {
+            let spawned = Command::new(exe)
+                .args(["summarize", session_id, transcript_path, "--model", model])
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn();
+            if spawned.is_err() {
+                cache::unlock(&cache_name(session_id));
+            }
+        }
+        None => {
+            let (session_id, transcript_path, model) = (
+                session_id.to_string(),
+                transcript_path.to_string(),
+                model.to_string(),
+            );
+            std::thread::spawn(move || summarize(&session_id, &transcript_path, &model));
+        }
+    }
+}
+
+/// Longest part of the first prompt sent to the summarizer.
+const MAX_PROMPT_CHARS: usize = 4000;
+
+/// How long the worker waits for `claude -p` before killing it.
+const CLAUDE_TIMEOUT: Duration = Duration::from_secs(60);
+
+fn prompt_for(message: &str) -> String {
+    let message: String = message.chars().take(MAX_PROMPT_CHARS).collect();
+    format!(
+        "Summarize this request as a title of at most six words. \
+         Reply with the title only.\n\n{}",
+        message
+    )
+}
+
+/// First line of the model's answer with escape sequences and other control
+/// characters removed, so a reply can't restyle or move the cursor in the
+/// user's terminal.
+pub fn sanitize(reply: &str) -> String {
+    let line = crate::width::strip_ansi(reply)
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .unwrap_or_default()
+        .to_string();
+    line.chars()
+        .filter(|c| !c.is_control())
+        .collect::<String>()
+        .trim_matches(|c: char| c == '"' || c == '\'' || c.is_whitespace())
+        .to_string()
+}
+
+/// Runs `claude -p` without a shell: the prompt goes in on stdin (never on
+/// the command line, where it would need quoting and show up in `ps`) and
+/// the reply is written to `tmp`. Killed after [`CLAUDE_TIMEOUT`].
+fn run_claude(model: &str, prompt: &str, tmp: &Path) -> std::io::Result<bool> {
+    let mut child = Command::new("claude")
+        .args(["-p", "--model", model])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::from(fs::File::create(tmp)?))
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // Written from a thread, so a `claude` that stops reading can't keep
+        // us past the timeout. Dropping stdin closes it, which ends the
+        // prompt.
+        let prompt = prompt.to_string();
+        std::thread::spawn(move || {
+            let _ = stdin.write_all(prompt.as_bytes());
+        });
+    }
+
+    let status = crate::process::wait_within(&mut child, CLAUDE_TIMEOUT);
+    Ok(status.is_some_and(|status| status.success()))
+}
+
+/// Caches `claude -p`'s title for the first prompt of the transcript, then
+/// releases the lock, so a failed attempt is retried by the next render.
+fn summarize(session_id: &str, transcript_path: &str, model: &str) -> bool {
+    let written = crate::transcript::read_first_prompt(transcript_path)
+        .is_some_and(|prompt| write_summary(session_id, model, &prompt));
+    cache::unlock(&cache_name(session_id));
+    written
+}
+
+fn write_summary(session_id: &str, model: &str, prompt: &str) -> bool {
+    let path = cache::cache_dir().join(cache_name(session_id));
+    let tmp = path.with_extension(format!("tmp.{}", std::process::id()));
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+
+    let ok = matches!(run_claude(model, &prompt_for(prompt), &tmp), Ok(true));
+    // The raw reply is replaced by its sanitized form in place, then renamed
+    // over the cache entry so readers only ever see a finished summary.
+    let written = ok
+        && fs::read(&tmp)
+            .map(|raw| sanitize(&String::from_utf8_lossy(&raw)))
+            .ok()
+            .filter(|summary| !summary.is_empty())
+            .is_some_and(|summary| {
+                fs::write(&tmp, summary).is_ok() && fs::rename(&tmp, &path).is_ok()
+            });
+    if !written {
+        let _ = fs::remove_file(&tmp);
+    }
+    written
+}
+
+/// `statusline summarize <session_id> <transcript> [--model M]`: the
+/// background worker started by [`ClaudeSummarizer`].
+pub fn run_cli(args: &SummarizeArgs) -> i32 {
+    if summarize(
+        &args.session_id,
+        &args.transcript.to_string_lossy(),
+        &args.model,
+    ) {
+        0
+    } else {
+        1
+    }
+}
+
+/// The summary segment for a session whose first prompt is `prompt`, or an
+/// empty string.
+pub fn format_summary(
+    config: &SummaryConfig,
+    session_id: &str,
+    transcript_path: &str,
+    prompt: &str,
+) -> String {
+    if config.summarizer == SummarizerKind::Off {
+        return String::new();
+    }
+
+    let heuristic = Heuristic {
+        words: config.words,
+    };
+    let summary = match config.summarizer {
+        SummarizerKind::Claude => ClaudeSummarizer {
+            model: config.model.clone(),
+        }
+        .summarize(session_id, transcript_path, prompt)
+        .or_else(|| heuristic.summarize(session_id, transcript_path, prompt)),
+        _ => heuristic.summarize(session_id, transcript_path, prompt),
+    };
+    match summary {
+        Some(summary) => format!(
+            "\x1b[38;5;250m{}\x1b[0m",
+            crate::width::truncate_to_width(&sanitize(&summary), config.max_width)
+        ),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+
+const BATTERY_CACHE: &str = "battery.json";
+
+/// `[clock]` config section, for fullscreen terminals without a system clock.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClockConfig {
+    pub enabled: bool,
+    /// chrono `strftime` format in local time.
+    pub format: String,
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        ClockConfig {
+            enabled: false,
+            format: "%H:%M".to_string(),
+        }
+    }
+}
+
+/// `[battery]` config section.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BatteryConfig {
+    pub enabled: bool,
+    /// Percentage at or below which a discharging battery turns red.
+    pub low: u8,
+    /// How long a reading is reused; `pmset` is too slow to run every render.
+    pub refresh_secs: i64,
+}
+
+impl Default for BatteryConfig {
+    fn default() -> Self {
+        BatteryConfig {
+            enabled: false,
+            low: 20,
+            refresh_secs: 60,
+        }
+    }
+}
+
+pub fn format_clock(config: &ClockConfig, now: chrono::DateTime<chrono::Local>) -> String {
+    use std::fmt::Write;
+    let mut time = String::new();
+    // An invalid format string makes chrono's Display fail; show nothing
+    // instead of panicking.
+    if write!(time, "{}", now.format(&config.format)).is_err() {
+        return String::new();
+    }
+    format!("\x1b[90m{}\x1b[0m", time)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Battery {
+    pub percent: u8,
+    pub charging: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedBattery {
+    at: i64,
+    battery: Option<Battery>,
+}
+
+/// The first `BAT*` under `/sys/class/power_supply`.
+#[cfg(target_os = "linux")]
+fn read_battery() -> Option<Battery> {
+    let supply = std::fs::read_dir("/sys/class/power_supply")
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.file_name()
+                .is_some_and(|n| n.to_string_lossy().starts_with("BAT"))
+        })?;
+    let percent = std::fs::read_to_string(supply.join("capacity"))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    let status = std::fs::read_to_string(supply.join("status")).unwrap_or_default();
+    Some(Battery {
+        percent,
+        charging: matches!(status.trim(), "Charging" | "Full" | "Not charging"),
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn read_battery() -> Option<Battery> {
+    let output = std::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()
+        .ok()?;
+    parse_pmset(&String::from_utf8_lossy(&output.stdout))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn read_battery() -> Option<Battery> {
+    None
+}
+
+/// Parses the `-InternalBattery-0 (id=…)  85%; discharging; 4:12 remaining` line.
+pub fn parse_pmset(output: &str) -> Option<Battery> {
+    let line = output.lines().find(|l| l.contains("InternalBattery"))?;
+    let mut fields = line.split('\t').nth(1)?.split(';').map(str::trim);
+    let percent = fields.next()?.strip_suffix('%')?.parse().ok()?;
+    let state = fields.next().unwrap_or_default();
+    Some(Battery {
+        percent,
+        charging: state != "discharging",
+    })
+}
+
+/// The battery state, cached for `refresh_secs`. `None` on desktops.
+pub fn battery(config: &BatteryConfig) -> Option<Battery> {
+    let now = chrono::Utc::now().timestamp();
+    if let Some(cached) = crate::cache::read_cache(BATTERY_CACHE)
+        .and_then(|data| serde_json::from_str::<CachedBattery>(&data).ok())
+    {
+        if now - cached.at < config.refresh_secs {
+            return cached.battery;
+        }
+    }
+    let battery = read_battery();
+    if let Ok(data) = serde_json::to_string(&CachedBattery { at: now, battery }) {
+        let _ = crate::cache::write_cache(BATTERY_CACHE, &data);
+    }
+    battery
+}
+
+/// `⚡85%` while charging, `85%` on battery, red at or below `low`.
+pub fn format_battery(config: &BatteryConfig, battery: &Battery) -> String {
+    let color = if battery.charging {
+        "\x1b[32m"
+    } else if battery.percent <= config.low {
+        "\x1b[1;31m"
+    } else {
+        "\x1b[90m"
+    };
+    let icon = if battery.charging { "\u{26a1}" } else { "" };
+    format!("{}{}{}%\x1b[0m", color, icon, battery.percent)
+}
@@ -0,0 +1,175 @@
+use crate::error::StatuslineError;
+use crate::width;
+
+/// One piece of a parsed layout template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    Text(String),
+    /// `{name:variant:color=red:max=20:plain}`.
+    Segment {
+        name: String,
+        /// Alternative rendering, looked up as `<name>_<variant>`
+        /// (`{context:bar}` is the `context_bar` segment).
+        variant: Option<String>,
+        color: Option<String>,
+        max_width: Option<usize>,
+        plain: bool,
+    },
+    /// `[ • {cost}]`: shown only when a segment inside it is non-empty.
+    Group(Vec<Node>),
+}
+
+/// Parses a layout template such as `{dir} {git}[ • {cost:color=yellow}]`.
+/// `{{`, `}}`, `[[` and `]]` are literal braces and brackets.
+pub fn parse(template: &str) -> Result<Vec<Node>, StatuslineError> {
+    let mut chars = template.chars().peekable();
+    let nodes = parse_nodes(&mut chars, false)?;
+    Ok(nodes)
+}
+
+fn parse_nodes(
+    chars: &mut std::iter::Peekable<std::str::Chars>,
+    in_group: bool,
+) -> Result<Vec<Node>, StatuslineError> {
+    let mut nodes = Vec::new();
+    let mut text = String::new();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' | '[' | ']' if chars.peek() == Some(&c) => {
+                chars.next();
+                text.push(c);
+            }
+            '{' => {
+                let mut spec = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => spec.push(c),
+                        None => return Err(invalid("unclosed `{`")),
+                    }
+                }
+                flush(&mut text, &mut nodes);
+                nodes.push(parse_segment(&spec)?);
+            }
+            '[' => {
+                flush(&mut text, &mut nodes);
+                nodes.push(Node::Group(parse_nodes(chars, true)?));
+            }
+            ']' if in_group => {
+                flush(&mut text, &mut nodes);
+                return Ok(nodes);
+            }
+            '}' | ']' => return Err(invalid(&format!("unmatched `{}`", c))),
+            c => text.push(c),
+        }
+    }
+
+    if in_group {
+        return Err(invalid("unclosed `[`"));
+    }
+    flush(&mut text, &mut nodes);
+    Ok(nodes)
+}
+
+fn flush(text: &mut String, nodes: &mut Vec<Node>) {
+    if !text.is_empty() {
+        nodes.push(Node::Text(std::mem::take(text)));
+    }
+}
+
+fn invalid(message: &str) -> StatuslineError {
+    StatuslineError::Template(message.to_string())
+}
+
+fn parse_segment(spec: &str) -> Result<Node, StatuslineError> {
+    let mut parts = spec.split(':').map(str::trim);
+    let name = parts.next().unwrap_or_default();
+    if name.is_empty() {
+        return Err(invalid("empty `{}`"));
+    }
+    let (mut variant, mut color, mut max_width, mut plain) = (None, None, None, false);
+    for modifier in parts {
+        match modifier.split_once('=') {
+            Some(("color", spec)) => color = Some(spec.to_string()),
+            Some(("max", n)) => {
+                max_width = Some(
+                    n.parse()
+                        .map_err(|_| invalid(&format!("`max={}` in {{{}}}", n, spec)))?,
+                )
+            }
+            Some(_) => return Err(invalid(&format!("unknown modifier `{}`", modifier))),
+            None if modifier == "plain" => plain = true,
+            None => variant = Some(modifier.to_string()),
+        }
+    }
+    Ok(Node::Segment {
+        name: name.to_string(),
+        variant,
+        color,
+        max_width,
+        plain,
+    })
+}
+
+/// Renders `nodes` with segment text from `lookup` (`None` for unknown or
+/// hidden segments).
+pub fn render<'a>(nodes: &[Node], lookup: &impl Fn(&str) -> Option<&'a str>) -> String {
+    let mut out = String::new();
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Segment {
+                name,
+                variant,
+                color,
+                max_width,
+                plain,
+            } => {
+                let text = match variant {
+                    Some(variant) => lookup(&format!("{}_{}", name, variant)),
+                    None => lookup(name),
+                }
+                .unwrap_or_default();
+                if text.is_empty() {
+                    continue;
+                }
+                let mut text = match color.as_deref().and_then(crate::theme::color_code) {
+                    Some(code) => format!("{}{}\x1b[0m", code, width::strip_ansi(text)),
+                    None if *plain => width::strip_ansi(text),
+                    None => text.to_string(),
+                };
+                if let Some(max) = max_width {
+                    text = width::truncate_to_width(&text, *max);
+                }
+                out.push_str(&text);
+            }
+            Node::Group(children) => {
+                if !has_segments(children) || any_visible(children, lookup) {
+                    out.push_str(&render(children, lookup));
+                }
+            }
+        }
+    }
+    out
+}
+
+fn has_segments(nodes: &[Node]) -> bool {
+    nodes.iter().any(|node| match node {
+        Node::Text(_) => false,
+        Node::Segment { .. } => true,
+        Node::Group(children) => has_segments(children),
+    })
+}
+
+fn any_visible<'a>(nodes: &[Node], lookup: &impl Fn(&str) -> Option<&'a str>) -> bool {
+    nodes.iter().any(|node| match node {
+        Node::Text(_) => false,
+        Node::Segment { name, variant, .. } => match variant {
+            Some(variant) => lookup(&format!("{}_{}", name, variant)),
+            None => lookup(name),
+        }
+        .is_some_and(|text| !text.is_empty()),
+        Node::Group(children) => any_visible(children, lookup),
+    })
+}
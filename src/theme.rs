@@ -0,0 +1,394 @@
+use serde::{Deserialize, Serialize};
+
+/// How many colors the terminal can show. Output is written with 256-color
+/// and 24-bit codes and reduced to this depth just before printing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorDepth {
+    /// Detect from `$NO_COLOR`, `$COLORTERM` and `$TERM`.
+    #[default]
+    Auto,
+    #[serde(alias = "24bit")]
+    Truecolor,
+    #[serde(rename = "256")]
+    Ansi256,
+    #[serde(rename = "16")]
+    Ansi16,
+    /// No colors or text attributes at all.
+    None,
+}
+
+impl ColorDepth {
+    /// Resolves `Auto` from the environment. Without `$TERM` (e.g. when
+    /// embedded) the output is left as is.
+    pub fn resolve(self) -> ColorDepth {
+        if self != ColorDepth::Auto {
+            return self;
+        }
+        if std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+            return ColorDepth::None;
+        }
+        let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            return ColorDepth::Truecolor;
+        }
+        ColorDepth::for_term(std::env::var("TERM").ok().as_deref())
+    }
+
+    /// What a terminal supports going by its `$TERM` alone: `*-direct`
+    /// terminfo entries (`xterm-direct`, `kitty-direct`) are 24-bit.
+    pub fn for_term(term: Option<&str>) -> ColorDepth {
+        match term {
+            None => ColorDepth::Truecolor,
+            Some("dumb") => ColorDepth::None,
+            Some(term) if term.contains("direct") => ColorDepth::Truecolor,
+            Some(term) if term.contains("256color") => ColorDepth::Ansi256,
+            Some(_) => ColorDepth::Ansi16,
+        }
+    }
+}
+
+/// SGR sequence for a color setting: a name (`red`, `bold red`), a
+/// 256-color index (`208`) or `#rrggbb`.
+pub fn color_code(color: &str) -> Option<String> {
+    let color = color.trim().to_ascii_lowercase();
+    let (bold, color) = match color.strip_prefix("bold ") {
+        Some(rest) => ("1;", rest.trim()),
+        None => ("", color.as_str()),
+    };
+    let code = match color {
+        "black" => "30".to_string(),
+        "red" => "31".to_string(),
+        "green" => "32".to_string(),
+        "yellow" => "33".to_string(),
+        "blue" => "34".to_string(),
+        "magenta" => "35".to_string(),
+        "cyan" => "36".to_string(),
+        "white" => "37".to_string(),
+        "gray" | "grey" => "90".to_string(),
+        "orange" => "38;5;208".to_string(),
+        hex if hex.starts_with('#') => {
+            let rgb = Rgb::from_hex(hex)?;
+            format!("38;2;{};{};{}", rgb.0, rgb.1, rgb.2)
+        }
+        index => format!("38;5;{}", index.parse::<u8>().ok()?),
+    };
+    Some(format!("\x1b[{}{}m", bold, code))
+}
+
+/// Which band `value` falls in: the number of `thresholds` it has reached.
+pub fn band(value: f64, thresholds: &[f64]) -> usize {
+    thresholds.iter().filter(|&&t| value >= t).count()
+}
+
+/// The color of `value`'s band, falling back to the last color when there
+/// are more bands than colors.
+pub fn band_color(value: f64, thresholds: &[f64], colors: &[String]) -> String {
+    colors
+        .get(band(value, thresholds))
+        .or(colors.last())
+        .and_then(|spec| color_code(spec))
+        .unwrap_or_default()
+}
+
+/// An sRGB color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    /// Parses `#rrggbb` or `rrggbb`.
+    pub fn from_hex(hex: &str) -> Option<Rgb> {
+        let hex = hex.trim().trim_start_matches('#');
+        // Byte slicing below needs single-byte characters.
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        Some(Rgb(channel(0)?, channel(2)?, channel(4)?))
+    }
+
+    /// WCAG relative luminance.
+    pub fn luminance(&self) -> f64 {
+        let linear = |c: u8| {
+            let c = c as f64 / 255.0;
+            if c <= 0.03928 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * linear(self.0) + 0.7152 * linear(self.1) + 0.0722 * linear(self.2)
+    }
+
+    fn blend(&self, other: Rgb, t: f64) -> Rgb {
+        let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        Rgb(
+            mix(self.0, other.0),
+            mix(self.1, other.1),
+            mix(self.2, other.2),
+        )
+    }
+}
+
+/// WCAG contrast ratio between two colors, from 1.0 to 21.0.
+pub fn contrast_ratio(a: Rgb, b: Rgb) -> f64 {
+    let (la, lb) = (a.luminance(), b.luminance());
+    let (hi, lo) = if la > lb { (la, lb) } else { (lb, la) };
+    (hi + 0.05) / (lo + 0.05)
+}
+
+const BASIC_16: [Rgb; 16] = [
+    Rgb(0, 0, 0),
+    Rgb(205, 0, 0),
+    Rgb(0, 205, 0),
+    Rgb(205, 205, 0),
+    Rgb(0, 0, 238),
+    Rgb(205, 0, 205),
+    Rgb(0, 205, 205),
+    Rgb(229, 229, 229),
+    Rgb(127, 127, 127),
+    Rgb(255, 0, 0),
+    Rgb(0, 255, 0),
+    Rgb(255, 255, 0),
+    Rgb(92, 92, 255),
+    Rgb(255, 0, 255),
+    Rgb(0, 255, 255),
+    Rgb(255, 255, 255),
+];
+
+/// Approximate RGB of an xterm 256-color palette index.
+pub fn ansi256_to_rgb(index: u8) -> Rgb {
+    match index {
+        0..=15 => BASIC_16[index as usize],
+        16..=231 => {
+            let i = index - 16;
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            Rgb(level(i / 36), level((i / 6) % 6), level(i % 6))
+        }
+        _ => {
+            let v = 8 + (index - 232) * 10;
+            Rgb(v, v, v)
+        }
+    }
+}
+
+/// Nearest xterm 256-color index (cube or grayscale ramp) for `rgb`.
+pub fn rgb_to_ansi256(rgb: Rgb) -> u8 {
+    let cube = |c: u8| -> u8 {
+        if c < 48 {
+            0
+        } else if c < 115 {
+            1
+        } else {
+            (c - 35) / 40
+        }
+    };
+    let cube_index = 16 + 36 * cube(rgb.0) + 6 * cube(rgb.1) + cube(rgb.2);
+
+    let avg = (rgb.0 as u16 + rgb.1 as u16 + rgb.2 as u16) / 3;
+    let gray_index = if avg < 8 {
+        16
+    } else if avg > 238 {
+        231
+    } else {
+        232 + ((avg - 8) / 10) as u8
+    };
+
+    let distance = |index: u8| {
+        let c = ansi256_to_rgb(index);
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        d(c.0, rgb.0) + d(c.1, rgb.1) + d(c.2, rgb.2)
+    };
+    if distance(gray_index) < distance(cube_index) {
+        gray_index
+    } else {
+        cube_index
+    }
+}
+
+/// Nearest of the 16 basic colors, as an index into the xterm palette.
+pub fn rgb_to_ansi16(rgb: Rgb) -> u8 {
+    let distance = |c: &Rgb| {
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        d(c.0, rgb.0) + d(c.1, rgb.1) + d(c.2, rgb.2)
+    };
+    (0..16u8)
+        .min_by_key(|&i| distance(&BASIC_16[i as usize]))
+        .unwrap_or(7)
+}
+
+/// Calls `f` with the parameters of every SGR sequence (`1;31` of
+/// `\x1b[1;31m`) and substitutes its result, dropping the sequence for
+/// `None`. Other escapes pass through.
+fn map_sgr(line: &str, f: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+
+    while let Some(start) = rest.find("\x1b[") {
+        out.push_str(&rest[..start]);
+        let seq = &rest[start + 2..];
+        let Some(end) = seq.find(|c: char| ('\x40'..='\x7e').contains(&c)) else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+        if seq.as_bytes()[end] == b'm' {
+            if let Some(params) = f(&seq[..end]) {
+                out.push_str("\x1b[");
+                out.push_str(&params);
+                out.push('m');
+            }
+        } else {
+            out.push_str(&rest[start..start + 2 + end + 1]);
+        }
+        rest = &seq[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Rewrites foreground colors in `line` whose contrast against `background`
+/// is below `min_ratio`, moving them toward white (dark backgrounds) or black
+/// (light backgrounds) until they pass. Everything else is left untouched.
+pub fn enforce_contrast(line: &str, background: Rgb, min_ratio: f64) -> String {
+    if min_ratio <= 1.0 {
+        return line.to_string();
+    }
+    map_sgr(line, |params| {
+        Some(adjust_sgr(params, background, min_ratio))
+    })
+}
+
+/// Reduces every color in `line` to `depth`: 24-bit codes become the nearest
+/// 256-color index, and with 16 colors, both become the nearest basic color.
+/// `None` drops all styling.
+pub fn downsample(line: &str, depth: ColorDepth) -> String {
+    match depth.resolve() {
+        ColorDepth::Auto | ColorDepth::Truecolor => line.to_string(),
+        ColorDepth::None => map_sgr(line, |_| None),
+        depth => map_sgr(line, |params| Some(downsample_sgr(params, depth))),
+    }
+}
+
+fn downsample_sgr(params: &str, depth: ColorDepth) -> String {
+    let parts: Vec<&str> = params.split(';').collect();
+    let mut out: Vec<String> = Vec::with_capacity(parts.len());
+    let mut i = 0;
+
+    while i < parts.len() {
+        let layer = match parts[i] {
+            "38" => Some(30),
+            "48" => Some(40),
+            _ => None,
+        };
+        let number = |k: usize| parts.get(i + k).and_then(|n| n.parse::<u8>().ok());
+        let color = match (layer, parts.get(i + 1)) {
+            (Some(_), Some(&"5")) => number(2).map(|n| (ansi256_to_rgb(n), Some(n), 3)),
+            (Some(_), Some(&"2")) => match (number(2), number(3), number(4)) {
+                (Some(r), Some(g), Some(b)) => Some((Rgb(r, g, b), None, 5)),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        match (layer, color) {
+            (Some(base), Some((rgb, index, consumed))) => {
+                if depth == ColorDepth::Ansi16 {
+                    let basic = match index {
+                        Some(n) if n < 16 => n,
+                        _ => rgb_to_ansi16(rgb),
+                    };
+                    let code = if basic < 8 {
+                        base + basic as u16
+                    } else {
+                        base + 60 + (basic - 8) as u16
+                    };
+                    out.push(code.to_string());
+                } else {
+                    let index = index.unwrap_or_else(|| rgb_to_ansi256(rgb));
+                    out.push(format!("{};5;{}", parts[i], index));
+                }
+                i += consumed;
+            }
+            _ => {
+                out.push(parts[i].to_string());
+                i += 1;
+            }
+        }
+    }
+
+    out.join(";")
+}
+
+fn adjust_sgr(params: &str, background: Rgb, min_ratio: f64) -> String {
+    let parts: Vec<&str> = params.split(';').collect();
+    let mut out: Vec<String> = Vec::with_capacity(parts.len());
+    let mut i = 0;
+
+    while i < parts.len() {
+        // Background and underline colors carry their own sub-parameters,
+        // which must not be mistaken for foreground codes.
+        let skip = match (parts[i], parts.get(i + 1)) {
+            ("48" | "58", Some(&"5")) => 3,
+            ("48" | "58", Some(&"2")) => 5,
+            _ => 0,
+        };
+        if skip > 0 {
+            let end = (i + skip).min(parts.len());
+            out.extend(parts[i..end].iter().map(|p| p.to_string()));
+            i = end;
+            continue;
+        }
+
+        let fg = match parts[i].parse::<u16>() {
+            Ok(n @ 30..=37) => Some((ansi256_to_rgb((n - 30) as u8), 1)),
+            Ok(n @ 90..=97) => Some((ansi256_to_rgb((n - 90 + 8) as u8), 1)),
+            Ok(38) if parts.get(i + 1) == Some(&"5") => parts
+                .get(i + 2)
+                .and_then(|n| n.parse::<u8>().ok())
+                .map(|n| (ansi256_to_rgb(n), 3)),
+            Ok(38) if parts.get(i + 1) == Some(&"2") => {
+                let channel = |k: usize| parts.get(i + k).and_then(|n| n.parse::<u8>().ok());
+                match (channel(2), channel(3), channel(4)) {
+                    (Some(r), Some(g), Some(b)) => Some((Rgb(r, g, b), 5)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+
+        match fg {
+            Some((rgb, consumed)) if contrast_ratio(rgb, background) < min_ratio => {
+                let fixed = raise_contrast(rgb, background, min_ratio);
+                out.push(format!("38;5;{}", rgb_to_ansi256(fixed)));
+                i += consumed;
+            }
+            Some((_, consumed)) => {
+                out.extend(parts[i..i + consumed].iter().map(|p| p.to_string()));
+                i += consumed;
+            }
+            None => {
+                out.push(parts[i].to_string());
+                i += 1;
+            }
+        }
+    }
+
+    out.join(";")
+}
+
+fn raise_contrast(rgb: Rgb, background: Rgb, min_ratio: f64) -> Rgb {
+    let target = if background.luminance() < 0.5 {
+        Rgb(255, 255, 255)
+    } else {
+        Rgb(0, 0, 0)
+    };
+
+    for step in 1..=20 {
+        let candidate = rgb.blend(target, step as f64 / 20.0);
+        let quantized = ansi256_to_rgb(rgb_to_ansi256(candidate));
+        if contrast_ratio(quantized, background) >= min_ratio {
+            return candidate;
+        }
+    }
+    target
+}
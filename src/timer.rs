@@ -0,0 +1,130 @@
+use crate::cli::TimerAction;
+use serde::{Deserialize, Serialize};
+
+const STATE_FILE: &str = "timer.json";
+
+/// A running focus timer, stored in the cache dir by `statusline timer start`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TimerState {
+    /// Unix seconds when the timer was started.
+    pub started_at: i64,
+    pub duration_secs: i64,
+}
+
+impl TimerState {
+    pub fn remaining_secs(&self, now: i64) -> i64 {
+        self.started_at.saturating_add(self.duration_secs) - now
+    }
+}
+
+/// Parses `25m`, `1h`, `90s`, `1h30m`, or a bare number of minutes.
+pub fn parse_duration(spec: &str) -> Option<i64> {
+    let spec = spec.trim();
+    if let Ok(minutes) = spec.parse::<i64>() {
+        return minutes.checked_mul(60).filter(|&secs| secs > 0);
+    }
+
+    let mut total: i64 = 0;
+    let mut digits = String::new();
+    for c in spec.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let n: i64 = digits.parse().ok()?;
+        digits.clear();
+        let unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return None,
+        };
+        total = total.checked_add(n.checked_mul(unit)?)?;
+    }
+
+    (digits.is_empty() && total > 0).then_some(total)
+}
+
+pub fn load() -> Option<TimerState> {
+    crate::cache::read_cache(STATE_FILE).and_then(|data| serde_json::from_str(&data).ok())
+}
+
+pub fn start(duration_secs: i64) -> std::io::Result<TimerState> {
+    let state = TimerState {
+        started_at: chrono::Utc::now().timestamp(),
+        duration_secs,
+    };
+    crate::cache::write_cache(STATE_FILE, &serde_json::to_string(&state)?)?;
+    Ok(state)
+}
+
+pub fn stop() -> std::io::Result<()> {
+    match std::fs::remove_file(crate::cache::cache_dir().join(STATE_FILE)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// `🍅 12:34` while running, then `🍅 +3m` in red once time is up.
+pub fn format_timer(icon: &str, state: &TimerState, now: i64) -> String {
+    let remaining = state.remaining_secs(now);
+    if remaining > 0 {
+        let color = if remaining <= 60 {
+            "\x1b[33m"
+        } else {
+            "\x1b[90m"
+        };
+        format!(
+            "{}{}{:02}:{:02}\x1b[0m",
+            color,
+            icon,
+            remaining / 60,
+            remaining % 60
+        )
+    } else {
+        format!("\x1b[31m{}+{}m\x1b[0m", icon, -remaining / 60)
+    }
+}
+
+/// `statusline timer start <duration> | stop | status`.
+pub fn run_cli(action: &TimerAction) -> i32 {
+    match action {
+        TimerAction::Start { duration } => {
+            let Some(duration) = parse_duration(duration) else {
+                eprintln!("timer: invalid duration '{}' (e.g. 25m, 1h30m)", duration);
+                return 2;
+            };
+            match start(duration) {
+                Ok(_) => {
+                    println!("timer started: {}m", duration / 60);
+                    0
+                }
+                Err(e) => {
+                    eprintln!("timer: {}", e);
+                    1
+                }
+            }
+        }
+        TimerAction::Stop => match stop() {
+            Ok(()) => 0,
+            Err(e) => {
+                eprintln!("timer: {}", e);
+                1
+            }
+        },
+        TimerAction::Status => {
+            match load() {
+                Some(state) => println!(
+                    "{}",
+                    crate::width::strip_ansi(&format_timer(
+                        &crate::Config::load().icons.prefix("timer"),
+                        &state,
+                        chrono::Utc::now().timestamp()
+                    ))
+                ),
+                None => println!("no timer running"),
+            }
+            0
+        }
+    }
+}
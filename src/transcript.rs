@@ -0,0 +1,832 @@
+use crate::deadline::Deadline;
+use crate::format::NumberFormat;
+use crate::models;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+
+/// Token usage of one assistant message in a transcript.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageEntry {
+    pub timestamp_ms: Option<i64>,
+    pub model: Option<String>,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
+}
+
+fn usage_entry(
+    json: &serde_json::Value,
+    message: &serde_json::Value,
+    usage: &serde_json::Value,
+) -> UsageEntry {
+    let tokens = |key: &str| usage.get(key).and_then(crate::as_u64_lenient).unwrap_or(0);
+    UsageEntry {
+        timestamp_ms: json.get("timestamp").and_then(crate::parse_timestamp),
+        model: message
+            .get("model")
+            .and_then(|m| m.as_str())
+            .map(str::to_string),
+        input_tokens: tokens("input_tokens"),
+        output_tokens: tokens("output_tokens"),
+        cache_creation_tokens: tokens("cache_creation_input_tokens"),
+        cache_read_tokens: tokens("cache_read_input_tokens"),
+    }
+}
+
+/// Reads the usage of every assistant message in the JSONL transcript at
+/// `path`. Streaming writes the same message several times; entries are
+/// deduplicated by message ID.
+pub fn read_usage_entries(path: &str) -> Vec<UsageEntry> {
+    let Ok(data) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+
+    let mut seen = HashSet::new();
+    let mut entries = Vec::new();
+
+    for line in data.lines().filter(|l| !l.trim().is_empty()) {
+        let Ok(json) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        let Some(message) = json.get("message") else {
+            continue;
+        };
+        let Some(usage) = message.get("usage") else {
+            continue;
+        };
+
+        if let Some(id) = message.get("id").and_then(|i| i.as_str()) {
+            if !seen.insert(id.to_string()) {
+                continue;
+            }
+        }
+
+        entries.push(usage_entry(&json, message, usage));
+    }
+
+    entries
+}
+
+/// Session cost with each message priced at the model that produced it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionCost {
+    pub total_usd: f64,
+    /// Cost per resolved model ID.
+    pub by_model: BTreeMap<String, f64>,
+}
+
+/// Cost of one message at its own `model`, or at `fallback_model` for entries
+/// that don't name one. Unknown models cost nothing.
+pub fn entry_cost(
+    entry: &UsageEntry,
+    fallback_model: Option<&str>,
+    aliases: &HashMap<String, String>,
+) -> f64 {
+    entry
+        .model
+        .as_deref()
+        .or(fallback_model)
+        .and_then(|m| models::pricing_for(m, aliases))
+        .map(|p| {
+            p.cost(
+                entry.input_tokens,
+                entry.output_tokens,
+                entry.cache_creation_tokens,
+                entry.cache_read_tokens,
+            )
+        })
+        .unwrap_or(0.0)
+}
+
+/// Prices every entry at its own model, so sessions that switch between
+/// Opus and Sonnet are totalled correctly. `model_id`, the session's current
+/// model, is used for entries without a `model` field.
+pub fn calculate_session_cost(
+    entries: &[UsageEntry],
+    model_id: &str,
+    aliases: &HashMap<String, String>,
+) -> SessionCost {
+    let mut cost = SessionCost::default();
+    for entry in entries {
+        let model = entry.model.as_deref().unwrap_or(model_id);
+        // Synthetic placeholder messages carry a `<synthetic>` model and no
+        // real usage; they don't belong in the breakdown.
+        if model.starts_with('<') {
+            continue;
+        }
+        let usd = entry_cost(entry, Some(model_id), aliases);
+        cost.total_usd += usd;
+        *cost
+            .by_model
+            .entry(models::resolve_model_id(model, aliases))
+            .or_default() += usd;
+    }
+    cost
+}
+
+/// `Opus $6.10 · Sonnet $1.40`, most expensive first.
+pub fn format_cost_breakdown(
+    cost: &SessionCost,
+    aliases: &HashMap<String, String>,
+    numbers: &NumberFormat,
+) -> String {
+    let mut models: Vec<(&String, &f64)> = cost.by_model.iter().collect();
+    models.sort_by(|a, b| b.1.total_cmp(a.1));
+    models
+        .iter()
+        .map(|(id, usd)| {
+            format!(
+                "{} {}",
+                models::family_name(id, aliases),
+                numbers.money(**usd)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" · ")
+}
+
+/// Cost of the messages sent in the `window_minutes` before `now_ms`.
+pub fn rolling_cost(
+    entries: &[UsageEntry],
+    model_id: &str,
+    aliases: &HashMap<String, String>,
+    now_ms: i64,
+    window_minutes: u64,
+) -> f64 {
+    let since = now_ms - window_minutes as i64 * 60 * 1000;
+    let recent: Vec<UsageEntry> = entries
+        .iter()
+        .filter(|e| e.timestamp_ms.is_some_and(|ts| ts >= since))
+        .cloned()
+        .collect();
+    calculate_session_cost(&recent, model_id, aliases).total_usd
+}
+
+/// Tool calls made in a session, from the `tool_use` blocks of its transcript.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolUsage {
+    pub last_tool: Option<String>,
+    pub calls: usize,
+    /// How many of the most recent calls in a row went to `last_tool`.
+    pub streak: usize,
+}
+
+/// Consecutive calls to the same tool at which the segment turns yellow.
+pub const TOOL_STREAK_WARN: usize = 10;
+
+/// `🛠 Edit ×47`, yellow once the last tool has been called
+/// [`TOOL_STREAK_WARN`] times in a row.
+pub fn format_tool_usage(icon: &str, usage: &ToolUsage) -> String {
+    let Some(tool) = &usage.last_tool else {
+        return String::new();
+    };
+    let color = if usage.streak >= TOOL_STREAK_WARN {
+        "\x1b[33m"
+    } else {
+        "\x1b[90m"
+    };
+    format!("{}{}{} \u{d7}{}\x1b[0m", color, icon, tool, usage.calls)
+}
+
+/// Size and timing of the most recent assistant message.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResponseStats {
+    pub output_tokens: u64,
+    /// From the entry before the message to the message's last streamed
+    /// write, when both carry timestamps.
+    pub duration_ms: Option<i64>,
+}
+
+impl ResponseStats {
+    pub fn tokens_per_sec(&self) -> Option<f64> {
+        self.duration_ms
+            .filter(|&ms| ms > 0)
+            .map(|ms| self.output_tokens as f64 * 1000.0 / ms as f64)
+    }
+}
+
+/// Output tokens of a response at which the segment turns yellow.
+pub const LARGE_RESPONSE_TOKENS: u64 = 8_000;
+
+/// `✍ 48k · last 3.2k 62 t/s`: the session's output tokens, then the last
+/// response's, yellow from [`LARGE_RESPONSE_TOKENS`].
+pub fn format_output(
+    icon: &str,
+    total_tokens: u64,
+    last: Option<&ResponseStats>,
+    numbers: &NumberFormat,
+) -> String {
+    let mut text = format!("\x1b[90m{}{}", icon, numbers.tokens(total_tokens));
+    if let Some(last) = last {
+        let color = if last.output_tokens >= LARGE_RESPONSE_TOKENS {
+            "\x1b[33m"
+        } else {
+            "\x1b[90m"
+        };
+        text.push_str(&format!(
+            " · {}last {}",
+            color,
+            numbers.tokens(last.output_tokens)
+        ));
+        if let Some(speed) = last.tokens_per_sec() {
+            text.push_str(&format!(" \x1b[90m{:.0} t/s", speed));
+        }
+    }
+    text.push_str("\x1b[0m");
+    text
+}
+
+/// `⟳2`, or nothing before the first compaction.
+pub fn format_compactions(count: usize) -> String {
+    match count {
+        0 => String::new(),
+        n => format!("\x1b[90m\u{27f3}{}\x1b[0m", n),
+    }
+}
+
+/// Extended thinking in the session's most recent turn.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThinkingUsage {
+    pub blocks: usize,
+    /// Estimated at four characters per token: usage reports thinking as
+    /// part of `output_tokens`. Redacted blocks add nothing.
+    pub estimated_tokens: u64,
+}
+
+/// `🧠 1.2k` after a turn that used extended thinking, nothing otherwise.
+pub fn format_thinking(icon: &str, usage: &ThinkingUsage, numbers: &NumberFormat) -> String {
+    if usage.blocks == 0 {
+        return String::new();
+    }
+    let tokens = if usage.estimated_tokens < 1000 {
+        usage.estimated_tokens.to_string()
+    } else {
+        numbers.tokens(usage.estimated_tokens)
+    };
+    format!("\x1b[35m{}{}\x1b[0m", icon, tokens)
+}
+
+/// Progress through the checklist of the session's latest `TodoWrite` call.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TodoProgress {
+    pub completed: usize,
+    pub total: usize,
+    /// `activeForm` of the task in progress, e.g. `Running the tests`.
+    pub active: Option<String>,
+}
+
+/// `☑ 3/7`, green once every task is done. With `show_active`, the task in
+/// progress follows in gray.
+pub fn format_todos(icon: &str, progress: &TodoProgress, show_active: bool) -> String {
+    if progress.total == 0 {
+        return String::new();
+    }
+    let color = if progress.completed == progress.total {
+        "\x1b[32m"
+    } else {
+        "\x1b[36m"
+    };
+    let active = match &progress.active {
+        Some(task) if show_active => format!(" \x1b[90m{}", task),
+        _ => String::new(),
+    };
+    format!(
+        "{}{}{}/{}{}\x1b[0m",
+        color, icon, progress.completed, progress.total, active
+    )
+}
+
+/// API errors and failed tool calls recorded in a session's transcript.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorCounts {
+    /// Failed API requests, including the retries Claude Code reports.
+    pub api: usize,
+    /// Tool results flagged `is_error` (failed commands, MCP errors, ...).
+    pub tool: usize,
+    pub last_ms: Option<i64>,
+}
+
+impl ErrorCounts {
+    pub fn total(&self) -> usize {
+        self.api + self.tool
+    }
+}
+
+fn is_api_error(json: &serde_json::Value) -> bool {
+    json.get("isApiErrorMessage").and_then(|e| e.as_bool()) == Some(true)
+        || (json.get("type").and_then(|t| t.as_str()) == Some("system")
+            && (json.get("subtype").and_then(|s| s.as_str()) == Some("api_error")
+                || json.get("level").and_then(|l| l.as_str()) == Some("error")))
+}
+
+/// `❗ 5 (2 api)`, red when the last error is under `recent_secs` old.
+/// Nothing for a clean session.
+pub fn format_errors(icon: &str, counts: &ErrorCounts, now_ms: i64, recent_secs: i64) -> String {
+    if counts.total() == 0 {
+        return String::new();
+    }
+    let recent = counts
+        .last_ms
+        .is_some_and(|last| now_ms - last < recent_secs * 1000);
+    let color = if recent { "\x1b[31m" } else { "\x1b[90m" };
+    let api = if counts.api > 0 && counts.tool > 0 {
+        format!(" ({} api)", counts.api)
+    } else if counts.api > 0 {
+        " api".to_string()
+    } else {
+        String::new()
+    };
+    format!("{}{}{}{}\x1b[0m", color, icon, counts.total(), api)
+}
+
+/// `idle 12m` once `idle_ms` reaches `after_secs`, else nothing.
+pub fn format_idle_badge(idle_ms: i64, after_secs: i64) -> String {
+    if idle_ms < after_secs * 1000 {
+        return String::new();
+    }
+    format!(
+        "\x1b[33midle {}\x1b[0m",
+        crate::index::format_idle(idle_ms / 1000)
+    )
+}
+
+/// One user prompt and everything Claude did in response to it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Turn {
+    /// `uuid` of the prompt's transcript entry.
+    pub id: String,
+    pub started_ms: Option<i64>,
+    pub ended_ms: Option<i64>,
+    pub cost_usd: f64,
+}
+
+impl Turn {
+    pub fn duration_ms(&self) -> Option<i64> {
+        Some(self.ended_ms? - self.started_ms?)
+    }
+}
+
+fn is_prompt(json: &serde_json::Value) -> bool {
+    if json.get("type").and_then(|t| t.as_str()) != Some("user") {
+        return false;
+    }
+    match json.get("message").and_then(|m| m.get("content")) {
+        Some(serde_json::Value::String(_)) => true,
+        Some(serde_json::Value::Array(blocks)) => !blocks
+            .iter()
+            .any(|b| b.get("type").and_then(|t| t.as_str()) == Some("tool_result")),
+        _ => false,
+    }
+}
+
+/// The working directory recorded in the transcript's first entry that has
+/// one (`cwd`), i.e. where the session was started.
+pub fn read_cwd(path: &str) -> Option<String> {
+    use std::io::BufRead;
+    let file = fs::File::open(path).ok()?;
+    std::io::BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .take(50)
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(&line).ok())
+        .find_map(|json| json.get("cwd").and_then(|c| c.as_str()).map(str::to_string))
+}
+
+fn prompt_text(json: &serde_json::Value) -> Option<String> {
+    match json.get("message")?.get("content")? {
+        serde_json::Value::String(text) => Some(text.clone()),
+        serde_json::Value::Array(blocks) => {
+            let text: Vec<&str> = blocks
+                .iter()
+                .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("text"))
+                .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+                .collect();
+            Some(text.join("\n"))
+        }
+        _ => None,
+    }
+}
+
+/// The first prompt the user typed themselves, skipping slash commands and
+/// the `<command-…>`/caveat wrappers Claude Code records around them.
+pub fn read_first_prompt(path: &str) -> Option<String> {
+    let data = fs::read_to_string(path).ok()?;
+    data.lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .find_map(|json| typed_prompt(&json))
+}
+
+/// The text of a prompt entry the user typed themselves; see
+/// [`read_first_prompt`].
+fn typed_prompt(json: &serde_json::Value) -> Option<String> {
+    if !is_prompt(json) {
+        return None;
+    }
+    let text = prompt_text(json)?.trim().to_string();
+    let typed = !text.is_empty()
+        && !text.starts_with('<')
+        && !text.starts_with('/')
+        && !text.starts_with("Caveat:");
+    typed.then_some(text)
+}
+
+/// How much of the parsed text a [`Transcript`] keeps to recognize its file.
+const TAIL_BYTES: usize = 64;
+/// Message, tool use and thinking block keys a [`Transcript`] remembers to
+/// drop streamed duplicates, which are written right after the original.
+const RECENT_KEYS: usize = 256;
+/// How far back [`Transcript::recent_usage`] goes.
+pub const RECENT_USAGE_MINUTES: i64 = 24 * 60;
+/// Turns a [`Transcript`] keeps for [`Transcript::turns`].
+pub const MAX_TURNS: usize = 200;
+
+/// Where a turn starts in a [`Transcript`], and what it has used so far.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct TurnStart {
+    id: String,
+    started_ms: Option<i64>,
+    ended_ms: Option<i64>,
+    /// Usage of the turn's assistant messages, summed per model.
+    usage: Vec<UsageEntry>,
+}
+
+/// The last [`RECENT_KEYS`] keys seen, hashed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct RecentKeys(VecDeque<u64>);
+
+impl RecentKeys {
+    /// Remembers `key`; `false` when it was seen recently.
+    fn insert(&mut self, key: &str) -> bool {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        key.hash(&mut hasher);
+        let hash = hasher.finish();
+        if self.0.contains(&hash) {
+            return false;
+        }
+        if self.0.len() == RECENT_KEYS {
+            self.0.pop_front();
+        }
+        self.0.push_back(hash);
+        true
+    }
+
+    fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+/// Adds `entry` to the total of its model in `totals`. Pricing is linear, so
+/// the totals cost what their entries would.
+fn add_usage(totals: &mut Vec<UsageEntry>, entry: &UsageEntry) {
+    match totals.iter_mut().find(|total| total.model == entry.model) {
+        Some(total) => add_tokens(total, entry),
+        None => totals.push(entry.clone()),
+    }
+}
+
+fn add_tokens(total: &mut UsageEntry, entry: &UsageEntry) {
+    total.timestamp_ms = entry.timestamp_ms.or(total.timestamp_ms);
+    total.input_tokens += entry.input_tokens;
+    total.output_tokens += entry.output_tokens;
+    total.cache_creation_tokens += entry.cache_creation_tokens;
+    total.cache_read_tokens += entry.cache_read_tokens;
+}
+
+/// Everything the segments read from a session's transcript, gathered in a
+/// single pass over its entries. [`Transcript::load`] caches it with the
+/// offset it got to, so each render only parses what was appended since.
+/// It keeps totals rather than every message, so the cache stays small
+/// however long the session runs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Transcript {
+    /// Bytes parsed so far: the file up to the end of its last complete
+    /// line.
+    offset: u64,
+    /// The last bytes before `offset`. A file that no longer has them there
+    /// was replaced, and is parsed again from the start.
+    tail: Vec<u8>,
+    /// The render budget ran out before the end of the file.
+    #[serde(skip)]
+    partial: bool,
+    entries: usize,
+    /// Assistant messages' usage summed per model, deduplicated by message
+    /// ID like [`read_usage_entries`]. `timestamp_ms` is the latest
+    /// message's.
+    pub usage: Vec<UsageEntry>,
+    /// The last assistant message with input tokens: what the context
+    /// window held.
+    pub last_usage: Option<UsageEntry>,
+    /// Usage summed per model and minute over the last
+    /// [`RECENT_USAGE_MINUTES`], oldest first, for the rolling cost.
+    pub recent_usage: Vec<UsageEntry>,
+    seen_messages: RecentKeys,
+    /// `tool_use` blocks, deduplicated by tool use ID.
+    pub tools: ToolUsage,
+    seen_tools: RecentKeys,
+    /// The last assistant message with usage. Streaming writes a message
+    /// several times with growing usage; the last write counts.
+    pub last_response: Option<ResponseStats>,
+    response_id: Option<String>,
+    response_started_ms: Option<i64>,
+    /// `compact_boundary` entries, and the summaries following them.
+    boundaries: usize,
+    summaries: usize,
+    /// `thinking` and `redacted_thinking` blocks written since the last
+    /// prompt. Streamed duplicates of a block are counted once.
+    pub thinking: ThinkingUsage,
+    seen_thinking: RecentKeys,
+    /// The checklist of the last `TodoWrite` call; `None` when the session
+    /// never wrote one.
+    pub todos: Option<TodoProgress>,
+    pub errors: ErrorCounts,
+    pub first_activity_ms: Option<i64>,
+    pub last_activity_ms: Option<i64>,
+    /// See [`read_first_prompt`].
+    pub first_prompt: Option<String>,
+    turns: VecDeque<TurnStart>,
+}
+
+impl Transcript {
+    /// The transcript at `path`, continuing from the cached parse when the
+    /// file has only grown since. Empty when it can't be read.
+    pub fn load(path: &str) -> Transcript {
+        Transcript::load_within(path, &Deadline::start(None))
+    }
+
+    /// [`Transcript::load`], parsing only until `deadline` expires. What was
+    /// parsed by then is cached, and the next render carries on from there;
+    /// until one gets to the end, [`Transcript::is_complete`] is `false`.
+    pub fn load_within(path: &str, deadline: &Deadline) -> Transcript {
+        let name = format!("transcript-{}.json", crate::cache::cache_key(path));
+        let mut transcript = crate::cache::read_cache(&name)
+            .and_then(|data| serde_json::from_str::<Transcript>(&data).ok())
+            .unwrap_or_default();
+        let offset = transcript.offset;
+        if transcript.read_from(path, deadline).is_err() {
+            return Transcript::default();
+        }
+        if transcript.offset != offset {
+            if let Ok(data) = serde_json::to_string(&transcript) {
+                let _ = crate::cache::write_cache(&name, &data);
+            }
+        }
+        transcript
+    }
+
+    /// Whether the whole file was parsed. A partial parse undercounts
+    /// everything, so it isn't shown.
+    pub fn is_complete(&self) -> bool {
+        !self.partial
+    }
+
+    /// Parses the lines appended to `path` since `offset`, starting over
+    /// when the file was truncated or replaced.
+    fn read_from(&mut self, path: &str, deadline: &Deadline) -> std::io::Result<()> {
+        let mut file = fs::File::open(path)?;
+        if file.metadata()?.len() < self.offset {
+            *self = Transcript::default();
+        }
+        file.seek(SeekFrom::Start(self.offset - self.tail.len() as u64))?;
+        let mut tail = vec![0; self.tail.len()];
+        file.read_exact(&mut tail)?;
+        if tail != self.tail {
+            *self = Transcript::default();
+            file.seek(SeekFrom::Start(0))?;
+        }
+
+        let mut reader = BufReader::new(file);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            reader.read_until(b'\n', &mut line)?;
+            // A line still being written is left for the next render.
+            if line.last() != Some(&b'\n') {
+                return Ok(());
+            }
+            if deadline.expired() {
+                self.partial = true;
+                return Ok(());
+            }
+            if let Ok(json) = serde_json::from_slice::<serde_json::Value>(&line) {
+                self.add_entry(&json);
+            }
+            self.offset += line.len() as u64;
+            self.tail.extend_from_slice(&line);
+            self.tail
+                .drain(..self.tail.len().saturating_sub(TAIL_BYTES));
+        }
+    }
+
+    fn add_entry(&mut self, json: &serde_json::Value) {
+        self.entries += 1;
+        let timestamp = json.get("timestamp").and_then(crate::parse_timestamp);
+        let message = json.get("message");
+
+        if is_prompt(json) {
+            self.thinking = ThinkingUsage::default();
+            self.seen_thinking.clear();
+            if self.turns.len() == MAX_TURNS {
+                self.turns.pop_front();
+            }
+            self.turns.push_back(TurnStart {
+                id: json
+                    .get("uuid")
+                    .and_then(|u| u.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                started_ms: timestamp,
+                ended_ms: timestamp,
+                usage: Vec::new(),
+            });
+            if self.first_prompt.is_none() {
+                self.first_prompt = typed_prompt(json);
+            }
+        } else if let Some(turn) = self.turns.back_mut().filter(|_| timestamp.is_some()) {
+            turn.ended_ms = timestamp;
+        }
+
+        if let Some((message, usage)) = message.and_then(|m| Some((m, m.get("usage")?))) {
+            let entry = usage_entry(json, message, usage);
+            let id = message.get("id").and_then(|i| i.as_str());
+            if id.is_none() || id != self.response_id.as_deref() {
+                self.response_started_ms = self.last_activity_ms;
+                self.response_id = id.map(str::to_string);
+            }
+            self.last_response = Some(ResponseStats {
+                output_tokens: entry.output_tokens,
+                duration_ms: self
+                    .response_started_ms
+                    .zip(timestamp)
+                    .map(|(start, end)| end - start),
+            });
+            if id.is_none_or(|id| self.seen_messages.insert(id)) {
+                self.add_usage(entry);
+            }
+        }
+
+        let mut tool_errors = 0;
+        let blocks = message
+            .and_then(|m| m.get("content"))
+            .and_then(|c| c.as_array());
+        for block in blocks.into_iter().flatten() {
+            match block.get("type").and_then(|t| t.as_str()) {
+                Some("tool_use") => self.add_tool_use(block),
+                Some("thinking") | Some("redacted_thinking") => self.add_thinking(block),
+                Some("tool_result")
+                    if block.get("is_error").and_then(|e| e.as_bool()) == Some(true) =>
+                {
+                    tool_errors += 1
+                }
+                _ => {}
+            }
+        }
+
+        let api_error = is_api_error(json);
+        if api_error || tool_errors > 0 {
+            self.errors.api += usize::from(api_error);
+            self.errors.tool += tool_errors;
+            self.errors.last_ms = timestamp.or(self.errors.last_ms);
+        }
+        if json.get("subtype").and_then(|s| s.as_str()) == Some("compact_boundary") {
+            self.boundaries += 1;
+        }
+        if json.get("isCompactSummary").and_then(|s| s.as_bool()) == Some(true) {
+            self.summaries += 1;
+        }
+        if timestamp.is_some() {
+            self.first_activity_ms = self.first_activity_ms.or(timestamp);
+            self.last_activity_ms = timestamp;
+        }
+    }
+
+    fn add_usage(&mut self, entry: UsageEntry) {
+        add_usage(&mut self.usage, &entry);
+        if let Some(turn) = self.turns.back_mut() {
+            add_usage(&mut turn.usage, &entry);
+        }
+        if let Some(ts) = entry.timestamp_ms {
+            let minute = |ts: i64| ts.div_euclid(60_000);
+            let bucket = self
+                .recent_usage
+                .iter_mut()
+                .rev()
+                .take_while(|e| e.timestamp_ms.map(minute) == Some(minute(ts)))
+                .find(|e| e.model == entry.model);
+            match bucket {
+                Some(bucket) => add_tokens(bucket, &entry),
+                None => self.recent_usage.push(entry.clone()),
+            }
+            let since = ts - RECENT_USAGE_MINUTES * 60_000;
+            let stale = self
+                .recent_usage
+                .iter()
+                .take_while(|e| e.timestamp_ms.is_some_and(|t| t < since))
+                .count();
+            self.recent_usage.drain(..stale);
+        }
+        if entry.input_tokens + entry.cache_creation_tokens + entry.cache_read_tokens > 0 {
+            self.last_usage = Some(entry);
+        }
+    }
+
+    fn add_tool_use(&mut self, block: &serde_json::Value) {
+        let name = block.get("name").and_then(|n| n.as_str()).unwrap_or("?");
+        if name == "TodoWrite" {
+            if let Some(todos) = block
+                .get("input")
+                .and_then(|i| i.get("todos"))
+                .and_then(|t| t.as_array())
+            {
+                self.todos = Some(todo_progress(todos));
+            }
+        }
+        if let Some(id) = block.get("id").and_then(|i| i.as_str()) {
+            if !self.seen_tools.insert(id) {
+                return;
+            }
+        }
+
+        let usage = &mut self.tools;
+        usage.calls += 1;
+        if usage.last_tool.as_deref() == Some(name) {
+            usage.streak += 1;
+        } else {
+            usage.streak = 1;
+            usage.last_tool = Some(name.to_string());
+        }
+    }
+
+    fn add_thinking(&mut self, block: &serde_json::Value) {
+        let text = block.get("thinking").and_then(|t| t.as_str());
+        let key = block
+            .get("signature")
+            .or_else(|| block.get("data"))
+            .and_then(|s| s.as_str())
+            .or(text)
+            .unwrap_or_default();
+        if !self.seen_thinking.insert(key) {
+            return;
+        }
+        self.thinking.blocks += 1;
+        self.thinking.estimated_tokens += text.map_or(0, |t| t.chars().count() as u64 / 4);
+    }
+
+    /// How many times the session's context has been compacted. Each
+    /// compaction writes a `compact_boundary` system entry followed by a
+    /// user entry flagged `isCompactSummary`; older versions write only the
+    /// latter.
+    pub fn compactions(&self) -> usize {
+        self.boundaries.max(self.summaries)
+    }
+
+    /// Unix milliseconds of the first and last timestamped entries, once
+    /// there are two entries.
+    pub fn timestamps(&self) -> Option<(i64, i64)> {
+        self.first_activity_ms
+            .zip(self.last_activity_ms)
+            .filter(|_| self.entries >= 2)
+    }
+
+    /// The session's last [`MAX_TURNS`] turns, with assistant messages
+    /// priced like [`calculate_session_cost`]. Tool results are part of the
+    /// turn that requested them.
+    pub fn turns(&self, model_id: &str, aliases: &HashMap<String, String>) -> Vec<Turn> {
+        self.turns
+            .iter()
+            .map(|turn| Turn {
+                id: turn.id.clone(),
+                started_ms: turn.started_ms,
+                ended_ms: turn.ended_ms,
+                cost_usd: turn
+                    .usage
+                    .iter()
+                    .map(|entry| entry_cost(entry, Some(model_id), aliases))
+                    .sum(),
+            })
+            .collect()
+    }
+}
+
+fn todo_progress(todos: &[serde_json::Value]) -> TodoProgress {
+    let status = |todo: &serde_json::Value| {
+        todo.get("status")
+            .and_then(|s| s.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+    TodoProgress {
+        completed: todos.iter().filter(|t| status(t) == "completed").count(),
+        total: todos.len(),
+        active: todos
+            .iter()
+            .find(|t| status(t) == "in_progress")
+            .and_then(|t| t.get("activeForm").or_else(|| t.get("content")))
+            .and_then(|a| a.as_str())
+            .map(str::to_string),
+    }
+}
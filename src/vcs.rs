@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+/// `[vcs]` config section: the minimal branch segment for Mercurial and
+/// Subversion checkouts. Git repositories are handled by [`crate::git`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VcsConfig {
+    /// Look for Mercurial and Subversion checkouts outside git repositories.
+    pub enabled: bool,
+    /// Mark Mercurial checkouts with uncommitted changes (`hg status`).
+    pub hg_dirty: bool,
+    /// How long `hg` or `svn` may take before its part is left out.
+    pub timeout_ms: u64,
+}
+
+impl Default for VcsConfig {
+    fn default() -> Self {
+        VcsConfig {
+            enabled: true,
+            hg_dirty: true,
+            timeout_ms: 300,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Vcs {
+    Git,
+    Mercurial,
+    Subversion,
+}
+
+impl Vcs {
+    /// Short name shown before the branch of non-git checkouts.
+    pub fn label(self) -> &'static str {
+        match self {
+            Vcs::Git => "git",
+            Vcs::Mercurial => "hg",
+            Vcs::Subversion => "svn",
+        }
+    }
+}
+
+/// The version control system of the nearest checkout containing `dir`, and
+/// its root. Only looks for the `.git`, `.hg` and `.svn` markers.
+pub fn detect(dir: &Path) -> Option<(Vcs, PathBuf)> {
+    dir.ancestors().find_map(|root| {
+        if root.join(".git").exists() {
+            Some((Vcs::Git, root.to_path_buf()))
+        } else if root.join(".hg").is_dir() {
+            Some((Vcs::Mercurial, root.to_path_buf()))
+        } else if root.join(".svn").is_dir() {
+            Some((Vcs::Subversion, root.to_path_buf()))
+        } else {
+            None
+        }
+    })
+}
+
+/// Branch and dirty flag of a Mercurial or Subversion checkout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkout {
+    pub vcs: Vcs,
+    /// Active bookmark, else named branch, for Mercurial; the branch, tag
+    /// or `trunk` from the repository URL for Subversion.
+    pub branch: String,
+    pub dirty: bool,
+}
+
+/// The checkout containing `dir` when it is Mercurial or Subversion; `None`
+/// for git repositories, which have their own segment.
+pub fn checkout(dir: &Path, config: &VcsConfig) -> Option<Checkout> {
+    if !config.enabled {
+        return None;
+    }
+    let timeout = Duration::from_millis(config.timeout_ms);
+    match detect(dir)? {
+        (Vcs::Git, _) => None,
+        (Vcs::Mercurial, root) => Some(Checkout {
+            vcs: Vcs::Mercurial,
+            branch: hg_branch(&root.join(".hg")),
+            dirty: config.hg_dirty
+                && text_within("hg", dir, &["status", "-mard", "--color", "never"], timeout)
+                    .is_some_and(|out| !out.trim().is_empty()),
+        }),
+        (Vcs::Subversion, _) => {
+            let url = text_within("svn", dir, &["info", "--show-item", "url"], timeout)?;
+            Some(Checkout {
+                vcs: Vcs::Subversion,
+                branch: svn_branch(url.trim())?,
+                dirty: false,
+            })
+        }
+    }
+}
+
+/// Read from `.hg` without running `hg`: the active bookmark, else the
+/// named branch, which is `default` when `.hg/branch` is missing.
+fn hg_branch(hg_dir: &Path) -> String {
+    let read = |name: &str| {
+        std::fs::read_to_string(hg_dir.join(name))
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    };
+    read("bookmarks.current")
+        .or_else(|| read("branch"))
+        .unwrap_or_else(|| "default".to_string())
+}
+
+/// The branch of a Subversion URL laid out as `trunk`, `branches/<name>`
+/// and `tags/<name>`, e.g. `feature-x` for
+/// `https://svn.example.com/repo/branches/feature-x/src`.
+pub fn svn_branch(url: &str) -> Option<String> {
+    let parts: Vec<&str> = url.split('/').filter(|p| !p.is_empty()).collect();
+    parts.iter().enumerate().find_map(|(i, part)| match *part {
+        "trunk" => Some("trunk".to_string()),
+        "branches" | "tags" => parts.get(i + 1).map(|name| name.to_string()),
+        _ => None,
+    })
+}
+
+/// [`crate::process::output_within`] for `program args` in `dir`, as text.
+fn text_within(program: &str, dir: &Path, args: &[&str], timeout: Duration) -> Option<String> {
+    crate::process::output_within(Command::new(program).args(args).current_dir(dir), timeout)
+        .ok()
+        .flatten()
+        .map(|out| String::from_utf8_lossy(&out).into_owned())
+}
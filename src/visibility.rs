@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+/// A `[segments.show_when]` condition such as `"> 0.05"` or `">= 10m"`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rule {
+    pub op: Op,
+    pub value: f64,
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    /// Parses an operator and a number. `$` and `%` are ignored, and `s`,
+    /// `m` and `h` suffixes convert durations to seconds.
+    fn from_str(s: &str) -> Result<Rule, String> {
+        let s = s.trim();
+        let (op, rest) = [(">=", Op::Ge), ("<=", Op::Le), (">", Op::Gt), ("<", Op::Lt)]
+            .iter()
+            .find_map(|(prefix, op)| s.strip_prefix(prefix).map(|rest| (*op, rest)))
+            .ok_or_else(|| format!("{:?} must start with >, >=, < or <=", s))?;
+        let number = rest.trim().trim_start_matches('$').trim_end_matches('%');
+        let (number, scale) = match number.chars().last() {
+            Some('s') => (&number[..number.len() - 1], 1.0),
+            Some('m') => (&number[..number.len() - 1], 60.0),
+            Some('h') => (&number[..number.len() - 1], 3600.0),
+            _ => (number, 1.0),
+        };
+        let value: f64 = number
+            .trim()
+            .parse()
+            .map_err(|_| format!("{:?} has no number", s))?;
+        Ok(Rule {
+            op,
+            value: value * scale,
+        })
+    }
+}
+
+impl Rule {
+    pub fn matches(&self, value: f64) -> bool {
+        match self.op {
+            Op::Gt => value > self.value,
+            Op::Ge => value >= self.value,
+            Op::Lt => value < self.value,
+            Op::Le => value <= self.value,
+        }
+    }
+}
+
+/// Whether the segment `name`, currently at `value` (dollars for `cost`,
+/// percent for `context`, seconds for `duration`), passes its rule.
+/// Segments without a rule or a value, and invalid rules, are shown.
+pub fn visible(rules: &HashMap<String, String>, name: &str, value: Option<f64>) -> bool {
+    let (Some(rule), Some(value)) = (rules.get(name), value) else {
+        return true;
+    };
+    match rule.parse::<Rule>() {
+        Ok(rule) => rule.matches(value),
+        Err(e) => {
+            crate::debug::trace(&format!("segments.show_when.{}: {}", name, e));
+            true
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use unicode_width::UnicodeWidthChar;
+
+/// Removes ANSI escape sequences (CSI such as colors, and OSC such as
+/// hyperlinks) so the remaining text can be measured.
+pub fn strip_ansi(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+        match chars.peek() {
+            Some('[') => {
+                chars.next();
+                for c in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            Some(']') => {
+                chars.next();
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    out
+}
+
+/// Terminal cell width of a single character. Nerd Font glyphs live in the
+/// Private Use Area and occupy one cell in every terminal we care about.
+pub fn char_width(c: char) -> usize {
+    if is_private_use(c) {
+        return 1;
+    }
+    c.width().unwrap_or(0)
+}
+
+/// Number of terminal cells `s` occupies once ANSI sequences are removed.
+pub fn display_width(s: &str) -> usize {
+    strip_ansi(s).chars().map(char_width).sum()
+}
+
+/// Truncates `s` to at most `max` terminal cells, keeping ANSI sequences
+/// intact and ending with `…` when anything was cut.
+pub fn truncate_to_width(s: &str, max: usize) -> String {
+    if display_width(s) <= max {
+        return s.to_string();
+    }
+    if max == 0 {
+        return String::new();
+    }
+
+    let budget = max - 1;
+    let mut out = String::with_capacity(s.len());
+    let mut used = 0;
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            out.push(c);
+            match chars.peek() {
+                Some('[') => {
+                    out.push(chars.next().unwrap_or_default());
+                    for c in chars.by_ref() {
+                        out.push(c);
+                        if ('\x40'..='\x7e').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                Some(']') => {
+                    out.push(chars.next().unwrap_or_default());
+                    while let Some(c) = chars.next() {
+                        out.push(c);
+                        if c == '\x07' {
+                            break;
+                        }
+                        if c == '\x1b' && chars.peek() == Some(&'\\') {
+                            out.push(chars.next().unwrap_or_default());
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        let w = char_width(c);
+        if used + w > budget {
+            break;
+        }
+        used += w;
+        out.push(c);
+    }
+
+    out.push('…');
+    out.push_str("\x1b[0m");
+    out
+}
+
+fn is_private_use(c: char) -> bool {
+    matches!(c as u32, 0xE000..=0xF8FF | 0xF0000..=0xFFFFD | 0x100000..=0x10FFFD)
+}
@@ -0,0 +1,1091 @@
+//! Renders the payloads in `tests/fixtures/payloads` and compares the output,
+//! with and without colors, against the snapshots in `tests/snapshots`.
+//! After an intended rendering change, review and accept the new output with
+//! `cargo insta review` (or `INSTA_UPDATE=always cargo test`).
+
+use cc_statusline_rs::aggregate::{self, AggregateConfig};
+use cc_statusline_rs::alerts::{self, AlertChannel, AlertRule, AlertsConfig};
+use cc_statusline_rs::bar::BarStyle;
+use cc_statusline_rs::build_target;
+use cc_statusline_rs::custom::{CustomCommand, CustomSegment};
+use cc_statusline_rs::export::{self, ExportConfig};
+use cc_statusline_rs::git;
+use cc_statusline_rs::hooks::{self, HookEvent};
+use cc_statusline_rs::html;
+use cc_statusline_rs::project::ProjectLabel;
+use cc_statusline_rs::pull_request::{self, PullRequest};
+use cc_statusline_rs::remote::RemoteRepo;
+use cc_statusline_rs::remote_workspace::{shell_quote, RemoteWorkspaceConfig};
+use cc_statusline_rs::summary::SummarizerKind;
+use cc_statusline_rs::theme::{ColorDepth, Rgb};
+use cc_statusline_rs::transcript::Transcript;
+use cc_statusline_rs::vcs;
+use cc_statusline_rs::{
+    get_git_dirs, statusline_from_value, Config, CostMode, GitContext, Options, Registry,
+    StatuslineInput,
+};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Once;
+
+fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+/// Points `$HOME`, the config file and the cache and state directories into
+/// a fresh scratch directory, so renders neither see nor touch the real ones
+/// and paths under `$HOME` come out the same on every machine. It lives in
+/// the system temp directory, outside this crate's git checkout, so fixture
+/// workspaces aren't inside a repository. Colors are pinned to truecolor so
+/// the terminal running the tests doesn't matter.
+fn sandbox() -> PathBuf {
+    use std::hash::{Hash, Hasher};
+
+    static INIT: Once = Once::new();
+    let mut checkout = std::collections::hash_map::DefaultHasher::new();
+    env!("CARGO_MANIFEST_DIR").hash(&mut checkout);
+    let root =
+        std::env::temp_dir().join(format!("cc-statusline-snapshots-{:x}", checkout.finish()));
+    INIT.call_once(|| {
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("home")).expect("create sandbox");
+        std::env::set_var("HOME", root.join("home"));
+        std::env::set_var("XDG_CACHE_HOME", root.join("cache"));
+        std::env::set_var("XDG_STATE_HOME", root.join("state"));
+        std::env::set_var("XDG_CONFIG_HOME", root.join("config"));
+        std::env::set_var("CC_STATUSLINE_CONFIG", root.join("missing.toml"));
+        std::env::set_var("COLORTERM", "truecolor");
+        std::env::remove_var("NO_COLOR");
+    });
+    root
+}
+
+/// Loads a payload, replacing `{fixtures}` with the fixture directory and
+/// `{home}` with the sandbox's `$HOME`. Directories named under `{home}`
+/// are created, so the workspace renders like a live checkout.
+fn load_payload(path: &Path) -> serde_json::Value {
+    let home = sandbox().join("home").to_string_lossy().into_owned();
+    let data = std::fs::read_to_string(path).expect("read payload");
+    let data = data
+        .replace("{fixtures}", &fixtures_dir().to_string_lossy())
+        .replace("{home}", &home);
+    let payload = serde_json::from_str(&data).expect("payload is valid JSON");
+    create_dirs_under(&payload, &home);
+    payload
+}
+
+fn create_dirs_under(value: &serde_json::Value, home: &str) {
+    match value {
+        serde_json::Value::String(dir) if dir.starts_with(home) => {
+            std::fs::create_dir_all(dir).expect("create workspace dir");
+        }
+        serde_json::Value::Array(values) => values.iter().for_each(|v| create_dirs_under(v, home)),
+        serde_json::Value::Object(map) => map.values().for_each(|v| create_dirs_under(v, home)),
+        _ => {}
+    }
+}
+
+/// Renders without a time budget, which a debug build on a busy machine
+/// could spend at random; `large_transcript_within_budget` covers it.
+fn render(payload: serde_json::Value, mut config: Config) -> String {
+    config.layout.budget_ms = None;
+    statusline_from_value(&StatuslineInput::from(payload), &Options::new(config))
+}
+
+fn strip_ansi(line: &str) -> String {
+    let mut out = String::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' && chars.next() == Some('[') {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args([
+            "-c",
+            "user.name=snapshots",
+            "-c",
+            "user.email=snapshots@example.com",
+        ])
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .expect("git is installed");
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+#[test]
+fn payloads() {
+    sandbox();
+    insta::glob!("fixtures/payloads/*.json", |path| {
+        let line = render(load_payload(path), Config::default());
+        insta::assert_snapshot!("plain", strip_ansi(&line));
+        insta::assert_snapshot!("color", format!("{:?}", line));
+    });
+}
+
+#[test]
+fn compact_verbose_context() {
+    sandbox();
+    let mut config = Config::default();
+    config.layout.compact = true;
+    config.layout.verbose_context = true;
+    let line = render(
+        load_payload(&fixtures_dir().join("payloads/opus-basic.json")),
+        config,
+    );
+    insta::assert_snapshot!(strip_ansi(&line));
+}
+
+#[test]
+fn cost_modes() {
+    sandbox();
+    let payload = load_payload(&fixtures_dir().join("payloads/opus-basic.json"));
+    let lines: Vec<String> = [
+        CostMode::Api,
+        CostMode::Max5,
+        CostMode::Max20,
+        CostMode::Hidden,
+    ]
+    .into_iter()
+    .map(|mode| {
+        let mut config = Config::default();
+        config.cost.mode = mode;
+        format!(
+            "{:?}: {}",
+            mode,
+            strip_ansi(&render(payload.clone(), config))
+        )
+    })
+    .collect();
+    insta::assert_snapshot!(lines.join("\n"));
+}
+
+#[test]
+fn color_depths() {
+    sandbox();
+    let payload = load_payload(&fixtures_dir().join("payloads/opus-basic.json"));
+    let lines: Vec<String> = [ColorDepth::Ansi256, ColorDepth::Ansi16, ColorDepth::None]
+        .into_iter()
+        .map(|depth| {
+            let mut config = Config::default();
+            config.theme.color_depth = depth;
+            format!("{:?}: {:?}", depth, render(payload.clone(), config))
+        })
+        .collect();
+    insta::assert_snapshot!(lines.join("\n"));
+
+    for (term, depth) in [
+        (Some("xterm-direct"), ColorDepth::Truecolor),
+        (Some("kitty-direct"), ColorDepth::Truecolor),
+        (Some("xterm-256color"), ColorDepth::Ansi256),
+        (Some("xterm"), ColorDepth::Ansi16),
+        (Some("dumb"), ColorDepth::None),
+        (None, ColorDepth::Truecolor),
+    ] {
+        assert_eq!(ColorDepth::for_term(term), depth, "{:?}", term);
+    }
+
+    assert_eq!(Rgb::from_hex("#1e1e2e"), Some(Rgb(0x1e, 0x1e, 0x2e)));
+    assert_eq!(Rgb::from_hex("#a\u{e9}\u{20ac}"), None);
+    let mut config = Config::default();
+    config.theme.background = "#a\u{e9}\u{20ac}".to_string();
+    config.theme.min_contrast = 4.5;
+    assert!(!render(payload, config).is_empty());
+}
+
+#[test]
+fn bar_styles() {
+    sandbox();
+    let payload = load_payload(&fixtures_dir().join("payloads/sonnet-1m-high-context.json"));
+    let lines: Vec<String> = [
+        BarStyle::Blocks,
+        BarStyle::Smooth,
+        BarStyle::Braille,
+        BarStyle::Battery,
+    ]
+    .into_iter()
+    .map(|style| {
+        let mut config = Config::default();
+        config.bar.style = style;
+        config.bar.width = 10;
+        format!(
+            "{:?}: {}",
+            style,
+            strip_ansi(&render(payload.clone(), config))
+        )
+    })
+    .collect();
+    insta::assert_snapshot!(lines.join("\n"));
+}
+
+#[test]
+fn template_layout() {
+    sandbox();
+    let mut config = Config::default();
+    config.layout.template = Some(
+        "{dir}[ | {vim}][ | {agent}] :: {model:plain:max=4} {context:tokens}[ ${cost:plain}]"
+            .into(),
+    );
+    let lines: Vec<String> = ["opus-basic.json", "plan-mode-vim-agent.json"]
+        .into_iter()
+        .map(|name| {
+            let payload = load_payload(&fixtures_dir().join("payloads").join(name));
+            strip_ansi(&render(payload, config.clone()))
+        })
+        .collect();
+    insta::assert_snapshot!(lines.join("\n"));
+}
+
+#[test]
+fn show_when_rules() {
+    sandbox();
+    let mut config = Config::default();
+    for (name, rule) in [
+        ("cost", "> 10"),
+        ("context", ">= 30%"),
+        ("duration", ">= 1h"),
+    ] {
+        config
+            .segments
+            .show_when
+            .insert(name.to_string(), rule.to_string());
+    }
+    let lines: Vec<String> = ["opus-basic.json", "sonnet-1m-high-context.json"]
+        .into_iter()
+        .map(|name| {
+            let payload = load_payload(&fixtures_dir().join("payloads").join(name));
+            strip_ansi(&render(payload, config.clone()))
+        })
+        .collect();
+    insta::assert_snapshot!(lines.join("\n"));
+}
+
+#[test]
+fn duration_formats() {
+    use cc_statusline_rs::{format_duration, format_duration_clock, format_started};
+    use std::time::Duration;
+
+    let lines: Vec<String> = [0, 45, 252, 2_820, 5_025, 90_061]
+        .into_iter()
+        .map(|secs| {
+            let duration = Duration::from_secs(secs);
+            format!(
+                "{:>6}s: {:<7} {:<7} {}",
+                secs,
+                format_duration(duration, false),
+                format_duration(duration, true),
+                format_duration_clock(duration)
+            )
+        })
+        .collect();
+    insta::assert_snapshot!(lines.join("\n"));
+
+    use chrono::TimeZone;
+    let at = |day, hour, minute| {
+        chrono::Local
+            .with_ymd_and_hms(2026, 3, day, hour, minute, 0)
+            .single()
+            .expect("unambiguous local time")
+    };
+    let now = at(10, 14, 30);
+    assert_eq!(
+        format_started(at(10, 14, 2).timestamp_millis(), now),
+        "started 14:02"
+    );
+    assert_eq!(
+        format_started(at(9, 22, 15).timestamp_millis(), now),
+        "started Mon 22:15"
+    );
+
+    use cc_statusline_rs::timer::parse_duration;
+    assert_eq!(parse_duration("25"), Some(1500));
+    assert_eq!(parse_duration("1h30m"), Some(5400));
+    assert_eq!(parse_duration("9223372036854775807"), None);
+    assert_eq!(parse_duration("9000000000000000h"), None);
+    assert_eq!(parse_duration("9223372036854775800s10s"), None);
+}
+
+#[test]
+fn session_summary() {
+    sandbox();
+    let payload = load_payload(&fixtures_dir().join("payloads/renamed-fields.json"));
+    assert!(!strip_ansi(&render(payload.clone(), Config::default())).contains("refactor"));
+
+    let mut config = Config::default();
+    config.summary.summarizer = SummarizerKind::Heuristic;
+    config.summary.words = 3;
+    let line = strip_ansi(&render(payload, config));
+    assert!(line.contains(" • refactor the parser • "), "{}", line);
+}
+
+#[test]
+fn anomaly_detectors() {
+    use cc_statusline_rs::anomaly::AnomalyDetector;
+    use cc_statusline_rs::transcript::Turn;
+
+    struct Measure(&'static str, fn(&Turn) -> f64);
+
+    impl AnomalyDetector for Measure {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn measure(&self, turn: &Turn) -> Option<f64> {
+            Some((self.1)(turn))
+        }
+    }
+
+    sandbox();
+    let payload = load_payload(&fixtures_dir().join("payloads/anomaly.json"));
+    let input = StatuslineInput::from(payload);
+    let line = |config: &Config, registry: Registry| {
+        statusline_from_value(
+            &input,
+            &Options {
+                config: config.clone(),
+                registry,
+            },
+        )
+    };
+
+    let mut config = Config::default();
+    config.layout.budget_ms = None;
+    assert!(!line(&config, Registry::new()).contains("unusual"));
+    config.anomaly.enabled = true;
+    assert!(line(&config, Registry::new()).contains("unusual"));
+
+    // A registered detector replaces the built-in one of the same name.
+    config.anomaly.detectors = vec!["cost".to_string()];
+    assert!(line(&config, Registry::new()).contains("unusual"));
+    let mut registry = Registry::new();
+    registry.register_detector(Box::new(Measure("cost", |_| 1.0)));
+    assert!(!line(&config, registry).contains("unusual"));
+
+    // Registered detectors run without being listed in `detectors`.
+    config.anomaly.detectors.clear();
+    let mut registry = Registry::new();
+    registry.register_detector(Box::new(Measure("spend", |turn| turn.cost_usd)));
+    assert!(line(&config, registry).contains("unusual"));
+}
+
+#[test]
+fn html_export() {
+    let mut config = Config::default();
+    config.theme.color_depth = ColorDepth::Truecolor;
+    let line = render(
+        load_payload(&fixtures_dir().join("payloads/opus-basic.json")),
+        config,
+    );
+    insta::assert_snapshot!("html_export", html::to_html(&line, Rgb(0, 0, 0)));
+    insta::assert_snapshot!("svg_export", html::to_svg(&line, Rgb(255, 255, 255)));
+
+    let link = "\x1b]8;;https://example.com/a?b&c\x1b\\\x1b[1;38;2;1;2;3m<pr>\x1b[0m\x1b]8;;\x1b\\";
+    assert_eq!(
+        html::to_html(link, Rgb(0, 0, 0)),
+        "<pre class=\"cc-statusline\" style=\"background:#000000;color:#e5e5e5;padding:0.5em 1em;font-family:monospace\">\
+         <a href=\"https://example.com/a?b&amp;c\" style=\"color:inherit\">\
+         <span style=\"color:#010203;font-weight:bold\">&lt;pr&gt;</span></a></pre>\n"
+    );
+}
+
+/// An HTTP endpoint that accepts requests and never answers them.
+fn silent_endpoint() -> (std::net::TcpListener, String) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind endpoint");
+    let url = format!(
+        "http://{}/",
+        listener.local_addr().expect("endpoint address")
+    );
+    (listener, url)
+}
+
+/// The start of the next request sent to a [`silent_endpoint`].
+fn next_request(listener: &std::net::TcpListener) -> String {
+    use std::io::Read;
+
+    let (mut stream, _) = listener.accept().expect("request arrives");
+    stream
+        .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+        .expect("set read timeout");
+    let mut request = vec![0; 4096];
+    let n = stream.read(&mut request).expect("read request");
+    String::from_utf8_lossy(&request[..n]).into_owned()
+}
+
+#[test]
+fn webhook_alerts_dont_wait() {
+    sandbox();
+    let (listener, url) = silent_endpoint();
+    let config = AlertsConfig {
+        rules: vec![AlertRule {
+            name: "big-spend".to_string(),
+            when: "cost_usd > 1".to_string(),
+            channel: AlertChannel::Webhook,
+            message: None,
+            url: Some(url),
+            command: None,
+        }],
+        ..Default::default()
+    };
+    let mut metrics = cc_statusline_rs::alerts::AlertMetrics::new();
+    metrics.insert("cost_usd", 5.0);
+
+    let started = std::time::Instant::now();
+    alerts::evaluate(&config, &metrics, "webhook-session");
+    assert!(started.elapsed() < std::time::Duration::from_millis(500));
+    let request = next_request(&listener);
+    assert!(request.starts_with("POST / "), "{}", request);
+}
+
+#[test]
+fn otlp_export_doesnt_wait() {
+    sandbox();
+    let (listener, url) = silent_endpoint();
+    let config = ExportConfig {
+        otlp_endpoint: Some(format!("{}v1/metrics", url)),
+        ..Default::default()
+    };
+    let mut metrics = cc_statusline_rs::alerts::AlertMetrics::new();
+    metrics.insert("cost_usd", 7.5);
+
+    let started = std::time::Instant::now();
+    export::export(&config, &metrics, &vec![("model", "Opus")], "otlp-session");
+    assert!(started.elapsed() < std::time::Duration::from_millis(400));
+    let request = next_request(&listener);
+    assert!(request.starts_with("POST /v1/metrics "), "{}", request);
+}
+
+#[test]
+fn shared_cost_today() {
+    let root = sandbox();
+    assert_eq!(
+        AggregateConfig::default().transcript_globs,
+        vec![format!("{}/*/.claude/projects/*/*.jsonl", root.display())]
+    );
+
+    let projects = root.join("shared/alice/.claude/projects/app");
+    std::fs::create_dir_all(&projects).expect("create projects dir");
+    let entry = serde_json::json!({
+        "type": "assistant",
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "message": {
+            "id": "msg_1",
+            "model": "claude-opus-4-1-20250805",
+            "usage": {"input_tokens": 1000, "output_tokens": 1000},
+        },
+    });
+    std::fs::write(projects.join("session.jsonl"), format!("{}\n", entry))
+        .expect("write transcript");
+    let mut config = AggregateConfig {
+        enabled: true,
+        transcript_globs: vec![format!(
+            "{}/shared/*/.claude/projects/*/*.jsonl",
+            root.display()
+        )],
+        refresh_secs: 60,
+    };
+    let aliases = Default::default();
+
+    // The first render only starts the scan; later ones see its total.
+    assert_eq!(aggregate::shared_cost_today(&config, &aliases), None);
+    let started = std::time::Instant::now();
+    let cost = loop {
+        if let Some(cost) = aggregate::shared_cost_today(&config, &aliases) {
+            break cost;
+        }
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    };
+    assert!(cost > 0.0, "{}", cost);
+
+    // A stale total is still served while the rescan runs.
+    config.refresh_secs = 0;
+    assert_eq!(aggregate::shared_cost_today(&config, &aliases), Some(cost));
+}
+
+#[test]
+fn transcript_segments() {
+    sandbox();
+    let mut config = Config::default();
+    config.segments.tool_calls = true;
+    config.segments.compactions = true;
+    config.segments.thinking = true;
+    config.segments.tasks = true;
+    config.segments.errors = true;
+    config.segments.output = true;
+    let line = render(
+        load_payload(&fixtures_dir().join("payloads/tool-streak.json")),
+        config,
+    );
+    insta::assert_snapshot!(strip_ansi(&line));
+}
+
+#[test]
+fn transcript_parsed_incrementally() {
+    use std::io::Write;
+
+    let path = sandbox().join("incremental.jsonl");
+    let path_str = path.to_string_lossy().into_owned();
+    let tool_use = |id: &str, name: &str| {
+        format!(
+            "{{\"type\":\"assistant\",\"message\":{{\"content\":[{{\"type\":\"tool_use\",\"id\":\"{}\",\"name\":\"{}\"}}]}}}}\n",
+            id, name
+        )
+    };
+    let append = |text: &str| {
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(text.as_bytes()))
+            .expect("append to transcript");
+    };
+    let calls = || Transcript::load(&path_str).tools.calls;
+
+    std::fs::write(
+        &path,
+        format!("{}{}", tool_use("a", "Read"), tool_use("b", "Edit")),
+    )
+    .expect("write");
+    assert_eq!(calls(), 2);
+    // A line still being written waits for its newline.
+    let line = tool_use("c", "Bash");
+    let (start, end) = line.split_at(20);
+    append(start);
+    assert_eq!(calls(), 2);
+    append(end);
+    assert_eq!(calls(), 3);
+    // Streamed duplicates still count once across renders.
+    append(&tool_use("c", "Bash"));
+    assert_eq!(calls(), 3);
+
+    // A transcript replaced by another one is read from the start.
+    std::fs::write(&path, tool_use("x", "Grep")).expect("truncate");
+    assert_eq!(calls(), 1);
+    std::fs::write(
+        &path,
+        format!("{}{}", tool_use("y", "Edit"), tool_use("z", "Edit")),
+    )
+    .expect("rewrite");
+    let transcript = Transcript::load(&path_str);
+    assert_eq!(transcript.tools.calls, 2);
+    assert_eq!(transcript.tools.last_tool.as_deref(), Some("Edit"));
+}
+
+#[test]
+fn hook_event_log_is_capped() {
+    sandbox();
+    for ts_ms in 0..5000 {
+        let event = HookEvent {
+            event: "PostToolUse".to_string(),
+            tool: Some("Bash".to_string()),
+            ts_ms,
+        };
+        hooks::append_event("long-session", &event).expect("append event");
+    }
+    let size = std::fs::metadata(hooks::events_path("long-session"))
+        .expect("event log exists")
+        .len();
+    assert!(size <= 256 * 1024, "{}", size);
+
+    let events = hooks::read_events("long-session");
+    assert!(events.len() < 5000);
+    assert_eq!(events.last().map(|e| e.ts_ms), Some(4999));
+    // The first line read is a whole event, not the end of a cut one.
+    assert!(events.windows(2).all(|w| w[1].ts_ms == w[0].ts_ms + 1));
+}
+
+#[test]
+fn prometheus_export() {
+    let mut metrics = cc_statusline_rs::alerts::AlertMetrics::new();
+    metrics.insert("context_pct", 42.5);
+    metrics.insert("cost_usd", 7.5);
+    metrics.insert("custom.metric", 1.0);
+    let attributes = vec![("model", "claude-opus-4-1"), ("branch", "fix/\"quoted\"")];
+    let exposition = export::prometheus_exposition(&ExportConfig::default(), &metrics, &attributes);
+    assert_eq!(
+        exposition,
+        "# HELP claude_context_pct Context window used, in percent.\n\
+         # TYPE claude_context_pct gauge\n\
+         claude_context_pct{model=\"claude-opus-4-1\",branch=\"fix/\\\"quoted\\\"\"} 42.5\n\
+         # TYPE claude_custom_metric gauge\n\
+         claude_custom_metric{model=\"claude-opus-4-1\",branch=\"fix/\\\"quoted\\\"\"} 1\n\
+         # HELP claude_session_cost_usd Session cost in US dollars.\n\
+         # TYPE claude_session_cost_usd gauge\n\
+         claude_session_cost_usd{model=\"claude-opus-4-1\",branch=\"fix/\\\"quoted\\\"\"} 7.5\n"
+    );
+}
+
+#[test]
+fn branch_cost_survives_render_without_cost() {
+    use cc_statusline_rs::index::{self, SessionSnapshot};
+
+    sandbox();
+    for cost in [Some(1.0), None, Some(1.5)] {
+        index::record_session(&SessionSnapshot {
+            session_id: "branch-cost".to_string(),
+            branch: Some("main".to_string()),
+            cost_usd: cost,
+            ..Default::default()
+        })
+        .expect("record");
+    }
+    let snapshot = index::load_session("branch-cost").expect("recorded");
+    assert_eq!(snapshot.branch_costs.get("main"), Some(&1.5));
+}
+
+#[cfg(feature = "history")]
+#[test]
+fn history_report() {
+    use cc_statusline_rs::history::{self, HistoryConfig, HistoryRow};
+    use cc_statusline_rs::report::Grouping;
+
+    let config = HistoryConfig {
+        enabled: true,
+        path: Some(
+            sandbox()
+                .join("history.sqlite")
+                .to_string_lossy()
+                .into_owned(),
+        ),
+        interval_secs: 0,
+    };
+    let row = |at: i64, branch: &str, cost: f64, tokens: u64| HistoryRow {
+        session_id: "s1".to_string(),
+        at,
+        project_dir: Some("/w/my-app".to_string()),
+        model: Some("claude-opus-4-1".to_string()),
+        branch: Some(branch.to_string()),
+        cost_usd: Some(cost),
+        input_tokens: Some(tokens),
+        output_tokens: Some(0),
+        ..Default::default()
+    };
+    for r in [
+        row(1_000, "main", 1.0, 100),
+        row(1_060, "main", 2.5, 300),
+        row(1_120, "feature", 4.0, 700),
+    ] {
+        history::record(&config, &r).expect("record");
+    }
+
+    let rows = history::report(&config, None, &Default::default()).expect("report");
+    let rows: Vec<(&str, usize, u64, f64, i64)> = rows
+        .iter()
+        .map(|r| {
+            (
+                r.key.as_str(),
+                r.sessions,
+                r.tokens,
+                r.cost_usd,
+                r.duration_ms,
+            )
+        })
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            ("my-app:main", 1, 300, 2.5, 60_000),
+            ("my-app:feature", 1, 400, 1.5, 0),
+        ]
+    );
+
+    let models =
+        history::report(&config, Some(Grouping::Model), &Default::default()).expect("report");
+    assert_eq!(models.len(), 1);
+    assert_eq!(models[0].cost_usd, 4.0);
+}
+
+#[test]
+fn project_labels() {
+    let payload = load_payload(&fixtures_dir().join("payloads/opus-basic.json"));
+    let mut config = Config {
+        projects: vec![
+            ProjectLabel {
+                label: "OTHER".to_string(),
+                paths: vec!["~/work/other-*".to_string()],
+                ..Default::default()
+            },
+            ProjectLabel {
+                label: "ACME".to_string(),
+                color: Some("blue".to_string()),
+                paths: vec!["~/work/my-*".to_string()],
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+    let line = render(payload.clone(), config.clone());
+    assert!(
+        line.starts_with("\x1b[34m\x1b[1mACME\x1b[0m \x1b[36m~/w/my-app"),
+        "{:?}",
+        line
+    );
+
+    config.projects[1].paths = vec!["~/work".to_string()];
+    assert!(strip_ansi(&render(payload.clone(), config.clone())).starts_with("ACME "));
+
+    // `*` doesn't match across `/`.
+    config.projects[1].paths = vec!["~/*-app".to_string()];
+    assert!(!strip_ansi(&render(payload, config)).contains("ACME"));
+}
+
+#[test]
+fn other_vcs() {
+    let checkout = sandbox().join("hg");
+    let _ = std::fs::remove_dir_all(&checkout);
+    std::fs::create_dir_all(checkout.join(".hg")).expect("create .hg");
+    std::fs::create_dir_all(checkout.join("src")).expect("create src");
+    std::fs::write(checkout.join(".hg/branch"), "stable\n").expect("write branch");
+
+    let mut payload = load_payload(&fixtures_dir().join("payloads/opus-basic.json"));
+    let dir = checkout.join("src").to_string_lossy().into_owned();
+    payload["workspace"] = serde_json::json!({"current_dir": dir, "project_dir": dir});
+    let mut config = Config::default();
+    config.vcs.hg_dirty = false;
+    let line = strip_ansi(&render(payload.clone(), config.clone()));
+    assert!(line.contains(" hg stable"), "{}", line);
+
+    std::fs::write(checkout.join(".hg/bookmarks.current"), "topic").expect("write bookmark");
+    let line = strip_ansi(&render(payload, config));
+    assert!(line.contains(" hg topic"), "{}", line);
+
+    for (url, branch) in [
+        ("https://svn.example.com/repo/trunk/src", Some("trunk")),
+        ("svn://host/repo/branches/release-2.1", Some("release-2.1")),
+        ("https://host/repo/tags/v1.0/docs", Some("v1.0")),
+        ("https://host/repo/vendor", None),
+    ] {
+        assert_eq!(vcs::svn_branch(url).as_deref(), branch, "{}", url);
+    }
+}
+
+#[test]
+fn build_target_package() {
+    let workspace = sandbox().join("bazel");
+    let _ = std::fs::remove_dir_all(&workspace);
+    std::fs::create_dir_all(workspace.join("services/auth/internal")).expect("create package");
+    std::fs::write(workspace.join("MODULE.bazel"), "").expect("write MODULE.bazel");
+    std::fs::write(workspace.join("services/auth/BUILD.bazel"), "").expect("write BUILD");
+
+    let mut payload = load_payload(&fixtures_dir().join("payloads/opus-basic.json"));
+    let dir = workspace.join("services/auth/internal");
+    payload["workspace"] = serde_json::json!({
+        "current_dir": dir.to_string_lossy(),
+        "project_dir": workspace.to_string_lossy(),
+    });
+    let mut config = Config::default();
+    config.build_target.enabled = true;
+    let line = strip_ansi(&render(payload.clone(), config.clone()));
+    assert!(line.contains(" • //services/auth • "), "{}", line);
+
+    config.build_target.show_tool = true;
+    let line = strip_ansi(&render(payload, config));
+    assert!(line.contains(" • bazel //services/auth • "), "{}", line);
+
+    // Above every build file, the directory isn't in a package.
+    assert_eq!(build_target::package(&workspace.join("services")), None);
+
+    assert_eq!(
+        build_target::buck_build_files("[buildfile]\n  name_v2 = BUCK.v2\n[parser]\nname = x\n"),
+        vec!["BUCK.v2".to_string()]
+    );
+    assert_eq!(build_target::buck_build_files(""), vec!["BUCK", "TARGETS"]);
+}
+
+#[test]
+fn remote_workspace_commands() {
+    let mut remote = RemoteWorkspaceConfig {
+        enabled: true,
+        command: vec!["ssh".to_string(), "devbox".to_string()],
+        ..Default::default()
+    };
+    let missing = Path::new("/nonexistent/cc-statusline/workspace");
+    assert!(remote.applies_to(missing));
+    assert!(!remote.applies_to(&fixtures_dir()));
+
+    let command = remote.git_command(missing, &["log", "-1", "--format=%h %ct"]);
+    let args: Vec<String> = command
+        .get_args()
+        .map(|a| a.to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(command.get_program(), "ssh");
+    assert_eq!(
+        args.join(" "),
+        "devbox env GIT_OPTIONAL_LOCKS=0 GIT_TERMINAL_PROMPT=0 git -C \
+         /nonexistent/cc-statusline/workspace log -1 '--format=%h %ct'"
+    );
+
+    remote.quote = false;
+    remote.paths = vec!["/work/*".to_string()];
+    assert!(remote.applies_to(Path::new("/work/my-app")));
+    assert!(!remote.applies_to(missing));
+    let command = remote.git_command(Path::new("/work/my-app"), &["log", "--format=%h %ct"]);
+    assert_eq!(command.get_args().last().unwrap(), "--format=%h %ct");
+
+    assert_eq!(shell_quote("it's"), r"'it'\''s'");
+    assert_eq!(shell_quote(""), "''");
+
+    // A workspace that only exists on the "remote" side, whose git commands
+    // a shell wrapper runs in a local repository instead: `$1`..`$6` are
+    // `env GIT_OPTIONAL_LOCKS=0 GIT_TERMINAL_PROMPT=0 git -C <dir>`.
+    let repo = sandbox().join("remote-repo");
+    std::fs::create_dir_all(&repo).expect("create repo dir");
+    std::fs::write(repo.join("README.md"), "remote\n").expect("write file");
+    git(&repo, &["init", "-q", "-b", "feature/remote"]);
+    git(&repo, &["add", "."]);
+    git(&repo, &["commit", "-q", "-m", "init"]);
+
+    let mut payload = load_payload(&fixtures_dir().join("payloads/opus-basic.json"));
+    payload["workspace"] = serde_json::json!({
+        "current_dir": "/nonexistent/devbox/my-app",
+        "project_dir": "/nonexistent/devbox/my-app",
+    });
+    let config = Config {
+        remote_workspace: RemoteWorkspaceConfig {
+            enabled: true,
+            command: vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                format!("shift 6; exec git -C '{}' \"$@\"", repo.display()),
+                "sh".to_string(),
+            ],
+            quote: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let line = strip_ansi(&render(payload, config));
+    assert!(line.contains("feature/remote"), "{}", line);
+    assert!(!line.contains('\u{2716}'), "{}", line);
+}
+
+/// A background process left holding stdout must not hold the render past
+/// the segment's timeout.
+#[cfg(unix)]
+#[test]
+fn custom_segment_background_process() {
+    let payload = load_payload(&fixtures_dir().join("payloads/opus-basic.json"));
+    let mut config = Config::default();
+    config.segments.custom.push(CustomSegment {
+        name: "background".to_string(),
+        command: CustomCommand::Shell("echo from-custom; sleep 5 &".to_string()),
+        timeout_ms: 300,
+        ..Default::default()
+    });
+    let started = std::time::Instant::now();
+    let line = strip_ansi(&render(payload, config));
+    assert!(line.contains("from-custom"), "{}", line);
+    assert!(started.elapsed() < std::time::Duration::from_secs(2));
+}
+
+#[test]
+fn custom_segments_within_budget() {
+    let payload = load_payload(&fixtures_dir().join("payloads/opus-basic.json"));
+    let mut config = Config::default();
+    for name in ["slow-1", "slow-2", "slow-3"] {
+        config.segments.custom.push(CustomSegment {
+            name: name.to_string(),
+            command: CustomCommand::Shell(format!("sleep 0.5; echo {}", name)),
+            timeout_ms: 2000,
+            ..Default::default()
+        });
+    }
+    let options = Options::new(config.clone());
+    let started = std::time::Instant::now();
+    let line = strip_ansi(&statusline_from_value(
+        &StatuslineInput::from(payload.clone()),
+        &options,
+    ));
+    assert!(!line.contains("slow-1"), "{}", line);
+    assert!(started.elapsed() < std::time::Duration::from_millis(400));
+
+    // Cut short by the budget rather than broken, so it isn't cached.
+    config.segments.custom.truncate(1);
+    let line = strip_ansi(&render(payload, config));
+    assert!(line.contains("slow-1"), "{}", line);
+}
+
+#[test]
+fn default_branch_shared_by_worktrees() {
+    let repo = sandbox().join("worktrees/main");
+    let linked = sandbox().join("worktrees/linked");
+    std::fs::create_dir_all(&repo).expect("create repo dir");
+    std::fs::write(repo.join("README.md"), "shared\n").expect("write file");
+    git(&repo, &["init", "-q", "-b", "main"]);
+    git(&repo, &["add", "."]);
+    git(&repo, &["commit", "-q", "-m", "init"]);
+    git(
+        &repo,
+        &[
+            "worktree",
+            "add",
+            "-q",
+            "-b",
+            "feature",
+            &linked.to_string_lossy(),
+        ],
+    );
+
+    let context = GitContext::default();
+    let dirs = get_git_dirs(&repo, &context).expect("main checkout");
+    let linked_dirs = get_git_dirs(&linked, &context).expect("linked worktree");
+    assert_eq!(dirs.common_dir, linked_dirs.common_dir);
+    assert_eq!(
+        git::default_branch(&repo, &dirs, 3600, &context).as_deref(),
+        Some("main")
+    );
+
+    // Detection would now find `origin/develop`, but the worktree reuses
+    // the main checkout's answer.
+    git(
+        &repo,
+        &["update-ref", "refs/remotes/origin/develop", "HEAD"],
+    );
+    git(
+        &repo,
+        &[
+            "symbolic-ref",
+            "refs/remotes/origin/HEAD",
+            "refs/remotes/origin/develop",
+        ],
+    );
+    assert_eq!(
+        git::default_branch(&linked, &linked_dirs, 3600, &context).as_deref(),
+        Some("main")
+    );
+}
+
+#[test]
+fn pull_request_links() {
+    let repo = RemoteRepo::parse("git@github.com:acme/widgets.git").expect("GitHub remote");
+    let open = PullRequest {
+        number: 12,
+        state: "OPEN".to_string(),
+        is_draft: false,
+    };
+    assert_eq!(
+        pull_request::format_pull_request(&open, Some(&repo)),
+        "\x1b[32m\x1b]8;;https://github.com/acme/widgets/pull/12\x1b\\#12\x1b]8;;\x1b\\\x1b[0m"
+    );
+    let merged = PullRequest {
+        state: "MERGED".to_string(),
+        ..open
+    };
+    assert_eq!(
+        pull_request::format_pull_request(&merged, None),
+        "\x1b[35m#12\x1b[0m"
+    );
+    let answer: PullRequest =
+        serde_json::from_str(r#"{"isDraft":true,"number":7,"state":"OPEN"}"#).expect("gh output");
+    assert_eq!(
+        pull_request::format_pull_request(&answer, None),
+        "\x1b[90m#7\x1b[0m"
+    );
+}
+
+#[test]
+fn missing_workspace_dir() {
+    let mut payload = load_payload(&fixtures_dir().join("payloads/opus-basic.json"));
+    payload["workspace"] = serde_json::json!({
+        "current_dir": "/nonexistent/cc-statusline/dir",
+        "project_dir": "/nonexistent/cc-statusline",
+    });
+    let line = render(payload, Config::default());
+    assert!(line.starts_with("\x1b[36m\x1b[90m"), "{:?}", line);
+    assert!(
+        strip_ansi(&line).starts_with("/n/c/dir \u{2716} • "),
+        "{}",
+        strip_ansi(&line)
+    );
+}
+
+#[test]
+fn git_repository() {
+    let home = sandbox().join("home");
+    let repo = home.join("projects/my-app");
+    std::fs::create_dir_all(&repo).expect("create repo dir");
+    std::fs::write(repo.join("README.md"), "snapshot\n").expect("write file");
+    git(&repo, &["init", "-q", "-b", "feature/snapshots"]);
+    git(&repo, &["add", "."]);
+    git(&repo, &["commit", "-q", "-m", "init"]);
+
+    let mut payload = load_payload(&fixtures_dir().join("payloads/opus-basic.json"));
+    let dir = repo.to_string_lossy();
+    payload["workspace"] = serde_json::json!({"current_dir": dir, "project_dir": dir});
+    let line = render(payload, Config::default());
+    insta::assert_snapshot!("git_plain", strip_ansi(&line));
+    insta::assert_snapshot!("git_color", format!("{:?}", line));
+}
+
+#[test]
+fn large_transcript_within_budget() {
+    use std::io::Write;
+
+    // About 35MB, as long as a day's session.
+    let path = sandbox().join("large.jsonl");
+    let mut file = std::io::BufWriter::new(std::fs::File::create(&path).expect("create"));
+    for i in 0..100_000 {
+        if i % 5 == 0 {
+            writeln!(
+                file,
+                "{{\"type\":\"user\",\"uuid\":\"u{}\",\"timestamp\":\"2025-01-01T00:00:00Z\",\"message\":{{\"role\":\"user\",\"content\":\"continue\"}}}}",
+                i
+            )
+            .expect("write");
+        }
+        writeln!(
+            file,
+            "{{\"type\":\"assistant\",\"timestamp\":\"2025-01-01T00:00:01Z\",\"message\":{{\"id\":\"msg_{}\",\"model\":\"claude-sonnet-4-5-20250929\",\"content\":[{{\"type\":\"tool_use\",\"id\":\"tool_{}\",\"name\":\"Edit\",\"input\":{{}}}}],\"usage\":{{\"input_tokens\":1200,\"output_tokens\":400,\"cache_creation_input_tokens\":3000,\"cache_read_input_tokens\":90000}}}}}}",
+            i, i
+        )
+        .expect("write");
+    }
+    file.flush().expect("flush");
+
+    // No reported cost, so it's priced from the transcript, along with
+    // every segment reading it.
+    let mut payload = load_payload(&fixtures_dir().join("payloads/opus-basic.json"));
+    payload["transcript_path"] = serde_json::json!(path);
+    payload.as_object_mut().expect("object").remove("cost");
+    let input = StatuslineInput::from(payload);
+    let mut config = Config::default();
+    config.segments.tool_calls = true;
+    config.segments.output = true;
+    config.cost.rolling_window_minutes = 30;
+    config.anomaly.enabled = true;
+    let options = Options::new(config);
+    let budget = options.config.layout.budget_ms.expect("a default budget");
+    // A debug build renders the rest of the line several times slower.
+    let limit = std::time::Duration::from_millis(budget * 4);
+
+    // The first renders get through part of the file each, and the ones
+    // after that only read the cached totals.
+    let mut line = String::new();
+    for _ in 0..1000 {
+        let started = std::time::Instant::now();
+        line = strip_ansi(&statusline_from_value(&input, &options));
+        let elapsed = started.elapsed();
+        assert!(elapsed < limit, "render took {:?}", elapsed);
+        if line.contains("Edit") {
+            break;
+        }
+    }
+    assert!(line.contains("Edit \u{d7}100000"), "{}", line);
+    for _ in 0..3 {
+        let started = std::time::Instant::now();
+        statusline_from_value(&input, &options);
+        let elapsed = started.elapsed();
+        assert!(elapsed < limit, "render took {:?}", elapsed);
+    }
+}